@@ -13,8 +13,10 @@
 use super::rentry::{
     NotifyState, SectionService, ServiceCommand, ServiceRe, ServiceResult, ServiceState,
 };
+use crate::mng::PidFileWatch;
 use crate::monitor::ServiceMonitor;
 use crate::rentry::ExitStatus;
+use crate::spawn::DynamicUsers;
 use nix::unistd::Pid;
 use once_cell::sync::Lazy;
 use std::cell::RefCell;
@@ -70,12 +72,27 @@ impl ServiceUnitComm {
         self.umcomm.um()
     }
 
+    pub(super) fn pid_file_watch(&self) -> Rc<PidFileWatch> {
+        self.umcomm.pid_file_watch()
+    }
+
+    pub(super) fn dynamic_users(&self) -> Rc<DynamicUsers> {
+        self.umcomm.dynamic_users()
+    }
+
     pub(super) fn rentry_conf_insert(&self, service: &SectionService) {
         if let Some(u) = self.owner() {
             self.rentry().conf_insert(u.id(), service)
         }
     }
 
+    pub(super) fn rentry_remove(&self) {
+        if let Some(u) = self.owner() {
+            self.rentry().conf_remove(u.id());
+            self.rentry().mng_remove(u.id());
+        }
+    }
+
     pub(super) fn rentry_conf_get(&self) -> Option<SectionService> {
         self.owner().map(|u| self.rentry().conf_get(u.id()))?
     }
@@ -96,6 +113,7 @@ impl ServiceUnitComm {
         restarts: u32,
         exit_status: ExitStatus,
         monitor: ServiceMonitor,
+        dynamic_user: Option<(String, u32)>,
     ) {
         if let Some(u) = self.owner() {
             self.rentry().mng_insert(
@@ -113,6 +131,7 @@ impl ServiceUnitComm {
                 restarts,
                 exit_status,
                 monitor,
+                dynamic_user,
             )
         }
     }
@@ -134,6 +153,7 @@ impl ServiceUnitComm {
         u32,
         ExitStatus,
         ServiceMonitor,
+        Option<(String, u32)>,
     )> {
         self.owner().map(|u| self.rentry().mng_get(u.id()))?
     }
@@ -195,6 +215,16 @@ impl ServiceUmComm {
         let rdata = self.data.read().unwrap();
         rdata.rentry()
     }
+
+    pub(super) fn pid_file_watch(&self) -> Rc<PidFileWatch> {
+        let rdata = self.data.read().unwrap();
+        rdata.pid_file_watch()
+    }
+
+    pub(super) fn dynamic_users(&self) -> Rc<DynamicUsers> {
+        let rdata = self.data.read().unwrap();
+        rdata.dynamic_users()
+    }
 }
 
 struct ServiceUmCommData {
@@ -202,6 +232,8 @@ struct ServiceUmCommData {
     um: Option<Rc<dyn UmIf>>,
     _reli: Weak<Reliability>,
     rentry: Option<Rc<ServiceRe>>,
+    pid_file_watch: Rc<PidFileWatch>,
+    dynamic_users: Rc<DynamicUsers>,
 }
 
 // the declaration "pub(self)" is for identification only.
@@ -211,6 +243,8 @@ impl ServiceUmCommData {
             um: None,
             _reli: Weak::new(),
             rentry: None,
+            pid_file_watch: Rc::new(PidFileWatch::new()),
+            dynamic_users: Rc::new(DynamicUsers::new()),
         }
     }
 
@@ -245,4 +279,12 @@ impl ServiceUmCommData {
     pub(self) fn rentry(&self) -> Rc<ServiceRe> {
         self.rentry.as_ref().cloned().unwrap()
     }
+
+    pub(self) fn pid_file_watch(&self) -> Rc<PidFileWatch> {
+        Rc::clone(&self.pid_file_watch)
+    }
+
+    pub(self) fn dynamic_users(&self) -> Rc<DynamicUsers> {
+        Rc::clone(&self.dynamic_users)
+    }
 }
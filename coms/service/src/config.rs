@@ -21,7 +21,7 @@ use std::rc::Rc;
 use sysmaster::error::*;
 use sysmaster::exec::ExecCommand;
 use sysmaster::rel::ReStation;
-use sysmaster::unit::KillContext;
+use sysmaster::unit::{unit_fragment_load, KillContext};
 
 pub(super) struct ServiceConfig {
     // associated objects
@@ -65,8 +65,12 @@ impl ServiceConfig {
 
         log::debug!("service load path: {:?}", paths);
         // fragment
+        let name = self
+            .comm
+            .owner()
+            .map_or_else(String::new, |u| u.id().to_string());
         for v in paths {
-            builder = builder.file(v);
+            builder = builder.preloaded(unit_fragment_load(&name, &v)?);
         }
 
         *self.data.borrow_mut() = builder.load().context(ConfiqueSnafu)?;
@@ -49,7 +49,17 @@
 //!
 //! PIDFile
 //!
-//! When the Type field is forking, you need to configure this field to obtain the PID of the child process
+//! When the Type field is forking, you need to configure this field to obtain the PID of the child process.
+//! If the file does not exist yet when the start command exits, it is watched until a valid PID is read or TimeoutStartSec expires.
+//! The file must be owned by root or the service User, and a PID outside the cgroup of the service is only accepted from a root-owned file.
+//!
+//! GuessMainPID
+//!
+//! Support the configuration of true and false. When PIDFile is not configured, guess the main PID from the cgroup of the service. The default configuration is true.
+//!
+//! TimeoutStartSec
+//!
+//! The timeout in seconds of each of the condition, start pre, start and start post operations, 0 means no timeout. The default configuration is 90.
 //!
 //! RemainAfterExit
 //!
@@ -67,6 +77,58 @@
 //!
 //! Indicates the socket service that the current service depends on. The dependency is Wants. Support multiple configurations, and use ";" when configuring multiple division.
 //!
+//! ProtectSystem、ProtectHome、PrivateTmp、ReadOnlyPaths、InaccessiblePaths
+//!
+//! Sandbox the file system of the service processes in a private mount namespace. ProtectSystem supports no, yes, full and strict,
+//! ProtectHome supports no, yes, read-only and tmpfs, PrivateTmp mounts private /tmp and /var/tmp. ReadOnlyPaths and InaccessiblePaths
+//! support multiple absolute paths divided by ";", a path prefixed with "-" is ignored if it does not exist.
+//!
+//! RootDirectory、MountAPIVFS、BindPaths、BindReadOnlyPaths
+//!
+//! RootDirectory is an absolute path, which is pivoted to as the root of the service processes in a private mount namespace.
+//! MountAPIVFS supports true and false, /proc, /sys and /dev of the host are bound into RootDirectory when it is true.
+//! BindPaths and BindReadOnlyPaths bind the host paths into the namespace, like "SOURCE[:DESTINATION[:rbind|norbind]]" divided by ";".
+//!
+//! PrivateNetwork、PrivateIPC、ProtectHostname、PrivateUsers
+//!
+//! Support the configuration of true and false, the service processes run in private network, IPC, UTS or user namespaces.
+//! The network namespace only has the loopback device, and the user namespace only maps root and the user of the service.
+//! The network and IPC namespaces are shared by the units configured by JoinsNamespaceOf. The default configuration is false.
+//!
+//! SystemCallFilter、SystemCallErrorNumber、SystemCallArchitectures
+//!
+//! Filter the system calls of the service processes with seccomp. SystemCallFilter is a list of system calls and groups like @system-service,
+//! which is an allow-list unless the first entry is prefixed with "~". The denied system calls fail with SystemCallErrorNumber,
+//! or kill the process if it is not configured. SystemCallArchitectures only supports native.
+//!
+//! CapabilityBoundingSet、AmbientCapabilities、NoNewPrivileges
+//!
+//! CapabilityBoundingSet limits the capabilities of the service processes, AmbientCapabilities keeps the capabilities like CAP_NET_BIND_SERVICE
+//! for the processes running as a non-root User. Both support multiple capability names, an entry prefixed with "~" removes the capabilities.
+//! NoNewPrivileges supports true and false, the processes can never gain new privileges through execve when it is true. The default configuration is false.
+//!
+//! DynamicUser
+//!
+//! Support the configuration of true and false. When it is true, the service runs as a user allocated from the uid range 61184-65519
+//! when it starts, and the user is released when it stops. The user is named by User, or the unit name without the suffix. The default configuration is false.
+//!
+//! RuntimeDirectory、StateDirectory、CacheDirectory、LogsDirectory、ConfigurationDirectory
+//!
+//! The directories relative to /run, /var/lib, /var/cache, /var/log and /etc, which are created once per start before the first service process is spawned.
+//! They are owned by the service user except the configuration directories, and passed to the processes by $RUNTIME_DIRECTORY and so on.
+//! The access mode is configured by RuntimeDirectoryMode and so on, the default configuration is 0755.
+//!
+//! RuntimeDirectoryPreserve
+//!
+//! Support the configuration of no, yes and restart. RuntimeDirectory is removed when the service stops unless it is yes,
+//! restart preserves it during the automatic restarts. The default configuration is no.
+//!
+//! Nice、CPUSchedulingPolicy、CPUSchedulingPriority、CPUAffinity、IOSchedulingClass、IOSchedulingPriority、OOMScoreAdjust
+//!
+//! Set the scheduling of the service processes. CPUSchedulingPolicy supports other, batch, idle, fifo and rr, the priority is 1-99 for fifo and rr.
+//! CPUAffinity supports the cpu indexes and ranges like "0-3 8". IOSchedulingClass supports none, realtime, best-effort and idle, the priority is 0-7.
+//! Nice is -20-19 and OOMScoreAdjust is -1000-1000. The settings are inherited from sysmaster when they are not configured.
+//!
 
 // dependency:
 // service_base -> service_rentry -> {service_comm | service_config}
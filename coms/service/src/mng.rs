@@ -16,32 +16,28 @@ use super::comm::ServiceUnitComm;
 use super::config::ServiceConfig;
 use super::pid::ServicePid;
 use super::rentry::{
-    NotifyState, ServiceCommand, ServiceRestart, ServiceResult, ServiceState, ServiceType,
+    NotifyState, RuntimeDirectoryPreserve, ServiceCommand, ServiceRestart, ServiceResult,
+    ServiceState, ServiceType,
 };
 use super::spawn::ServiceSpawn;
 use crate::rentry::ExitStatus;
-use basic::{fd_util, IN_SET};
-use basic::{file_util, process_util};
+use basic::IN_SET;
+use basic::{file_util, process_util, user_group_util};
 use event::{EventState, EventType, Events, Source};
-use nix::errno::Errno;
 use nix::libc;
-use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, WatchDescriptor};
+use nix::sys::inotify::{AddWatchFlags, WatchDescriptor};
 use nix::sys::socket::UnixCredentials;
 use nix::sys::wait::WaitStatus;
 use nix::unistd::Pid;
 use std::cell::RefCell;
 use std::collections::{HashMap, VecDeque};
-use std::fmt;
-use std::os::unix::prelude::AsRawFd;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::prelude::RawFd;
 use std::path::Path;
-use std::rc::Rc;
-use std::{
-    os::unix::prelude::{FromRawFd, RawFd},
-    path::PathBuf,
-    rc::Weak,
-};
+use std::rc::{Rc, Weak};
 use sysmaster::error::*;
-use sysmaster::exec::{ExecCommand, ExecContext, ExecFlags};
+use sysmaster::exec::{ExecCommand, ExecCommandFlags, ExecContext, ExecDirectoryType, ExecFlags};
 use sysmaster::rel::ReStation;
 use sysmaster::unit::{KillOperation, UnitActiveState, UnitNotifyFlags};
 
@@ -49,6 +45,7 @@ pub(super) struct ServiceMng {
     // associated objects
     comm: Rc<ServiceUnitComm>,
     config: Rc<ServiceConfig>,
+    exec_ctx: Rc<ExecContext>,
 
     // owned objects
     pid: Rc<ServicePid>,
@@ -56,8 +53,10 @@ pub(super) struct ServiceMng {
     state: RefCell<ServiceState>,
     result: RefCell<ServiceResult>,
     main_command: RefCell<VecDeque<ExecCommand>>,
+    main_command_current: RefCell<Option<ExecCommand>>,
     control_cmd_type: RefCell<Option<ServiceCommand>>,
     control_command: RefCell<VecDeque<ExecCommand>>,
+    control_command_current: RefCell<Option<ExecCommand>>,
     rd: Rc<RunningData>,
     monitor: RefCell<ServiceMonitor>,
 }
@@ -83,6 +82,7 @@ impl ReStation for ServiceMng {
             restarts,
             exit_status,
             monitor,
+            dynamic_user,
         )) = self.comm.rentry_mng_get()
         {
             *self.state.borrow_mut() = state;
@@ -97,6 +97,12 @@ impl ReStation for ServiceMng {
             self.rd.set_restarts(restarts);
             self.rd.set_wait_status(WaitStatus::from(exit_status));
             *self.monitor.borrow_mut() = monitor;
+            if let Some((name, uid)) = dynamic_user {
+                self.comm
+                    .dynamic_users()
+                    .restore(&self.comm.get_owner_id(), &name, uid);
+                self.spawn.set_dynamic_user(Some((name, Some(uid))));
+            }
         }
     }
 
@@ -116,6 +122,9 @@ impl ReStation for ServiceMng {
             self.rd.restarts(),
             exit_status,
             *self.monitor.borrow(),
+            self.spawn
+                .dynamic_user()
+                .and_then(|(name, uid)| uid.map(|uid| (name, uid))),
         );
     }
 
@@ -134,13 +143,16 @@ impl ServiceMng {
         ServiceMng {
             comm: Rc::clone(commr),
             config: Rc::clone(configr),
+            exec_ctx: Rc::clone(exec_ctx),
             pid: Rc::clone(&_pid),
             spawn: ServiceSpawn::new(commr, &_pid, configr, exec_ctx),
             state: RefCell::new(ServiceState::Dead),
             result: RefCell::new(ServiceResult::Success),
             main_command: RefCell::new(VecDeque::new()),
+            main_command_current: RefCell::new(None),
             control_cmd_type: RefCell::new(None),
             control_command: RefCell::new(VecDeque::new()),
+            control_command_current: RefCell::new(None),
             rd: rd.clone(),
             monitor: RefCell::new(ServiceMonitor::new()),
         }
@@ -187,6 +199,26 @@ impl ServiceMng {
             self.rd.set_reset_restart(false);
         }
         self.set_result(ServiceResult::Success);
+        if let Err(e) = self.acquire_dynamic_user() {
+            log::error!(
+                "Failed to allocate the dynamic user of {}: {}",
+                self.comm.get_owner_id(),
+                e
+            );
+            self.enter_dead(ServiceResult::FailureResources, false);
+            self.db_update();
+            return;
+        }
+        if let Err(e) = self.spawn.setup_exec_directories() {
+            log::error!(
+                "Failed to set up the managed directories of {}: {}",
+                self.comm.get_owner_id(),
+                e
+            );
+            self.enter_dead(ServiceResult::FailureResources, false);
+            self.db_update();
+            return;
+        }
         self.enter_contion();
         self.db_update();
     }
@@ -236,6 +268,14 @@ impl ServiceMng {
         service_state_to_unit_state(self.config.service_type(), self.state())
     }
 
+    pub(super) fn set_socket_fd(&self, fd: RawFd) -> Result<()> {
+        if fd >= 0 && !IN_SET!(self.state(), ServiceState::Dead, ServiceState::Failed) {
+            return Err(Error::UnitActionEBusy);
+        }
+
+        self.spawn.set_socket_fd(fd)
+    }
+
     fn enter_contion(&self) {
         log::debug!("enter running service condition command");
         self.control_command_fill(ServiceCommand::Condition);
@@ -486,10 +526,17 @@ impl ServiceMng {
         self.set_state(state);
 
         if restart {
-            self.enable_timer(self.config.config_data().borrow().Service.RestartSec);
+            let restart_sec = self.restart_sec_next();
+            log::debug!(
+                "unit {} will be restarted in {} seconds",
+                self.comm.get_owner_id(),
+                restart_sec
+            );
+            self.enable_timer(restart_sec);
             self.set_state(ServiceState::AutoRestart);
         } else {
             self.rd.set_reset_restart(true);
+            self.spawn.close_socket_fd();
         }
 
         self.rd.set_forbid_restart(false);
@@ -638,6 +685,17 @@ impl ServiceMng {
             self.control_command.borrow_mut().clear();
         }
 
+        if !IN_SET!(state, ServiceState::Start, ServiceState::StartPost) {
+            self.unwatch_pid_file();
+        }
+
+        self.arm_start_timer(original_state, state);
+
+        self.remove_runtime_directory(state);
+        if IN_SET!(state, ServiceState::Dead, ServiceState::Failed) {
+            self.release_dynamic_user();
+        }
+
         log::debug!(
             "unit: {}, original state: {:?}, change to: {:?}",
             self.comm.get_owner_id(),
@@ -712,10 +770,22 @@ impl ServiceMng {
         if let Some(cmds) = self.config.get_exec_cmds(cmd_type) {
             *self.main_command.borrow_mut() = cmds
         }
+        *self.main_command_current.borrow_mut() = None;
     }
 
     fn main_command_pop(&self) -> Option<ExecCommand> {
-        self.main_command.borrow_mut().pop_front()
+        let cmd = self.main_command.borrow_mut().pop_front();
+        *self.main_command_current.borrow_mut() = cmd.clone();
+        cmd
+    }
+
+    fn main_command_ignore_failure(&self) -> bool {
+        self.main_command_current
+            .borrow()
+            .as_ref()
+            .map_or(false, |cmd| {
+                cmd.flags().contains(ExecCommandFlags::IGNORE_FAILURE)
+            })
     }
 
     fn main_command_update(&self, len: usize) {
@@ -731,10 +801,22 @@ impl ServiceMng {
         if let Some(cmds) = self.config.get_exec_cmds(cmd_type) {
             *self.control_command.borrow_mut() = cmds
         }
+        *self.control_command_current.borrow_mut() = None;
     }
 
     fn control_command_pop(&self) -> Option<ExecCommand> {
-        self.control_command.borrow_mut().pop_front()
+        let cmd = self.control_command.borrow_mut().pop_front();
+        *self.control_command_current.borrow_mut() = cmd.clone();
+        cmd
+    }
+
+    fn control_command_ignore_failure(&self) -> bool {
+        self.control_command_current
+            .borrow()
+            .as_ref()
+            .map_or(false, |cmd| {
+                cmd.flags().contains(ExecCommandFlags::IGNORE_FAILURE)
+            })
     }
 
     fn control_command_update(&self, cmd_type: Option<ServiceCommand>, len: usize) {
@@ -767,9 +849,24 @@ impl ServiceMng {
 
         let file = &pid_file.unwrap();
         let pid_file_path = Path::new(file);
-        if !pid_file_path.exists() || !pid_file_path.is_file() {
+        let metadata = match fs::metadata(pid_file_path) {
+            Ok(m) if m.is_file() => m,
+            _ => {
+                return Err(Error::Other {
+                    msg: "pid file is not a file or not exist".to_string(),
+                })
+            }
+        };
+
+        // a pid file written by an unprivileged user must not redirect the
+        // supervision to an arbitrary process.
+        let owner = metadata.uid();
+        if owner != 0 && Some(owner) != self.service_uid() {
             return Err(Error::Other {
-                msg: "pid file is not a file or not exist".to_string(),
+                msg: format!(
+                    "pid file {:?} is not owned by root or the service user, refusing",
+                    pid_file_path
+                ),
             });
         }
 
@@ -798,7 +895,21 @@ impl ServiceMng {
             return Ok(false);
         }
 
-        self.valid_main_pid(pid)?;
+        if !self.valid_main_pid(pid)? {
+            if owner != 0 {
+                return Err(Error::Other {
+                    msg: format!(
+                        "main pid {} does not belong to the service, and pid file {:?} is not owned by root, refusing",
+                        pid, pid_file_path
+                    ),
+                });
+            }
+            log::warn!(
+                "supervising process {} which is not in the cgroup of {}",
+                pid,
+                self.comm.get_owner_id()
+            );
+        }
 
         self.pid.unwatch_main();
         self.pid.set_main(pid).map_err(|_e| Error::Other {
@@ -812,6 +923,103 @@ impl ServiceMng {
         Ok(true)
     }
 
+    /// remove RuntimeDirectory when the service stops, unless RuntimeDirectoryPreserve keeps it
+    fn remove_runtime_directory(&self, state: ServiceState) {
+        let preserve = self
+            .config
+            .config_data()
+            .borrow()
+            .Service
+            .RuntimeDirectoryPreserve;
+        let remove = match state {
+            ServiceState::Dead | ServiceState::Failed => preserve != RuntimeDirectoryPreserve::Yes,
+            ServiceState::AutoRestart => preserve == RuntimeDirectoryPreserve::No,
+            _ => false,
+        };
+        if !remove {
+            return;
+        }
+
+        if let Some(directory) = self.exec_ctx.directories().get(&ExecDirectoryType::Runtime) {
+            directory.remove(ExecDirectoryType::Runtime);
+        }
+    }
+
+    /// the user name of DynamicUser, which is User or the unit name without the suffix
+    fn dynamic_user_name(&self) -> String {
+        let user = self.config.config_data().borrow().Service.User.clone();
+        if !user.is_empty() {
+            return user;
+        }
+
+        let id = self.comm.get_owner_id();
+        match id.rsplit_once('.') {
+            Some((name, _)) => name.to_string(),
+            None => id,
+        }
+    }
+
+    fn acquire_dynamic_user(&self) -> Result<()> {
+        if !self.config.config_data().borrow().Service.DynamicUser
+            || self.spawn.dynamic_user().is_some()
+        {
+            return Ok(());
+        }
+
+        // the static user with the name is used as it is
+        let name = self.dynamic_user_name();
+        if user_group_util::lookup_passwd_name(&name).is_some() {
+            self.spawn.set_dynamic_user(Some((name, None)));
+            return Ok(());
+        }
+
+        let dirs = self
+            .exec_ctx
+            .directories()
+            .iter()
+            .filter(|(t, _)| **t != ExecDirectoryType::Configuration)
+            .flat_map(|(t, d)| d.full_paths(*t))
+            .collect::<Vec<_>>();
+        let uid = self
+            .comm
+            .dynamic_users()
+            .acquire(&self.comm.get_owner_id(), &name, &dirs)?;
+        log::info!(
+            "Allocated dynamic user {} with uid {} for {}",
+            name,
+            uid,
+            self.comm.get_owner_id()
+        );
+        self.spawn.set_dynamic_user(Some((name, Some(uid))));
+        Ok(())
+    }
+
+    fn release_dynamic_user(&self) {
+        if let Some((name, Some(_))) = self.spawn.dynamic_user() {
+            self.comm
+                .dynamic_users()
+                .release(&self.comm.get_owner_id(), &name);
+        }
+        self.spawn.set_dynamic_user(None);
+    }
+
+    /// the uid of the service user, None if the user can not be resolved
+    fn service_uid(&self) -> Option<u32> {
+        let user = match self.spawn.dynamic_user() {
+            Some((_, Some(uid))) => return Some(uid),
+            Some((name, None)) => name,
+            None => self.config.config_data().borrow().Service.User.clone(),
+        };
+        if user.is_empty() {
+            return Some(0);
+        }
+
+        user_group_util::parse_uid(&user)
+            .or_else(|_| user_group_util::parse_name(&user))
+            .ok()
+            .map(|u| u.uid.as_raw())
+    }
+
     fn valid_main_pid(&self, pid: Pid) -> Result<bool> {
         if pid == nix::unistd::getpid() {
             return Err(Error::Other {
@@ -841,59 +1049,49 @@ impl ServiceMng {
         Ok(false)
     }
 
-    fn demand_pid_file(&self) -> Result<()> {
-        let pid_file_inotify = PathIntofy::new(PathBuf::from(
-            self.config
-                .config_data()
-                .borrow()
-                .Service
-                .PIDFile
-                .as_ref()
-                .unwrap(),
-        ));
-
-        self.rd.attach_inotify(Rc::new(pid_file_inotify));
-
-        self.watch_pid_file()
-    }
-
     fn watch_pid_file(&self) -> Result<()> {
-        let pid_file_inotify = self.rd.path_inotify();
-        log::debug!("watch pid file: {}", pid_file_inotify);
-        match pid_file_inotify.add_watch_path() {
-            Ok(_) => {
-                let events = self.comm.um().events();
-                let source = Rc::clone(&pid_file_inotify);
-                events.add_source(source).unwrap();
-                let source = Rc::clone(&pid_file_inotify);
-                events.set_enabled(source, EventState::On).unwrap();
-
-                if let Err(e) = self.retry_pid_file() {
-                    log::warn!("retry load pid file error: {}, Ignore and Continue", e);
-                }
-                Ok(())
+        let pid_file = match self.config.config_data().borrow().Service.PIDFile.as_ref() {
+            Some(f) => f.to_string(),
+            None => {
+                return Err(Error::Other {
+                    msg: "pid file is not configured".to_string(),
+                })
             }
+        };
 
-            Err(e) => {
-                log::debug!(
-                    "failed to add watch for pid file {:?}, err: {}",
-                    pid_file_inotify.path,
-                    e
-                );
-                self.unwatch_pid_file();
+        self.unwatch_pid_file();
+        log::debug!("watch pid file: {}", pid_file);
+        let events = self.comm.um().events();
+        let wd = self
+            .comm
+            .pid_file_watch()
+            .watch(&events, self.rd.mng(), Path::new(&pid_file))
+            .map_err(|e| {
+                log::debug!("failed to add watch for pid file {}, err: {}", pid_file, e);
+                e
+            })?;
+        self.rd.set_pid_file_wd(Some(wd));
 
-                Err(e)
-            }
+        // the pid file may be written before the watch point is added.
+        if let Err(e) = self.retry_pid_file() {
+            log::debug!("retry load pid file error: {}, keep waiting", e);
         }
+        Ok(())
     }
 
     fn unwatch_pid_file(&self) {
-        log::debug!("unwatch pid file {}", self.rd.path_inotify());
-        self.rd.path_inotify().unwatch();
+        if let Some(wd) = self.rd.pid_file_wd() {
+            log::debug!("unwatch pid file of {}", self.comm.get_owner_id());
+            let events = self.comm.um().events();
+            self.comm
+                .pid_file_watch()
+                .unwatch(&events, &self.rd.mng(), wd);
+            self.rd.set_pid_file_wd(None);
+        }
     }
 
     fn retry_pid_file(&self) -> Result<bool> {
-        log::debug!("retry loading pid file: {}", self.rd.path_inotify());
+        log::debug!("retry loading pid file of {}", self.comm.get_owner_id());
         self.load_pid_file()?;
 
         self.unwatch_pid_file();
@@ -902,6 +1100,24 @@ impl ServiceMng {
         Ok(true)
     }
 
+    fn dispatch_pid_file(&self) {
+        if self.rd.pid_file_wd().is_none() {
+            return;
+        }
+
+        if self.retry_pid_file().is_ok() {
+            return;
+        }
+
+        // the watched path may be changed, watch the nearest ancestor again.
+        if self.watch_pid_file().is_ok() {
+            return;
+        }
+
+        self.unwatch_pid_file();
+        self.enter_signal(ServiceState::StopSigterm, ServiceResult::FailureResources);
+    }
+
     fn cgroup_good(&self) -> bool {
         if let Some(Ok(v)) = self
             .comm
@@ -915,7 +1131,7 @@ impl ServiceMng {
     }
 
     fn guess_main_pid(&self) {
-        if self.pid.main().is_some() {
+        if self.pid.main().is_some() || !self.config.config_data().borrow().Service.GuessMainPID {
             return;
         }
 
@@ -937,6 +1153,7 @@ impl ServiceMng {
             return false;
         }
 
+        // RestartPreventExitStatus takes precedence over RestartForceExitStatus
         if self
             .config
             .config_data()
@@ -948,6 +1165,17 @@ impl ServiceMng {
             return false;
         }
 
+        if self
+            .config
+            .config_data()
+            .borrow()
+            .Service
+            .RestartForceExitStatus
+            .exit_status_enabled(self.rd.wait_status())
+        {
+            return true;
+        }
+
         match self.config.config_data().borrow().Service.Restart {
             ServiceRestart::No => false,
             ServiceRestart::OnSuccess => self.result() == ServiceResult::Success,
@@ -972,6 +1200,17 @@ impl ServiceMng {
         }
     }
 
+    fn restart_sec_next(&self) -> u64 {
+        let config = self.config.config_data();
+        let data = config.borrow();
+        restart_sec_next(
+            data.Service.RestartSec,
+            data.Service.RestartMaxDelaySec,
+            data.Service.RestartSteps,
+            self.rd.restarts() + 1,
+        )
+    }
+
     fn enable_timer(&self, usec: u64) {
         if self.rd.armd_timer() {
             self.rd.timer().set_time(usec);
@@ -990,6 +1229,36 @@ impl ServiceMng {
         events.set_enabled(timer, EventState::OneShot).unwrap();
     }
 
+    fn disable_timer(&self) {
+        if self.rd.armd_timer() {
+            let events = self.comm.um().events();
+            let _ = events.set_enabled(self.rd.timer(), EventState::Off);
+        }
+    }
+
+    /// the condition, start pre, start and start post operations are bounded by TimeoutStartSec
+    fn arm_start_timer(&self, original_state: ServiceState, state: ServiceState) {
+        if original_state == state || state == ServiceState::AutoRestart {
+            return;
+        }
+
+        let timeout = self.config.config_data().borrow().Service.TimeoutStartSec;
+        let infinity = timeout == 0
+            || (state == ServiceState::Start && self.config.service_type() == ServiceType::Oneshot);
+        if IN_SET!(
+            state,
+            ServiceState::Condition,
+            ServiceState::StartPre,
+            ServiceState::Start,
+            ServiceState::StartPost
+        ) && !infinity
+        {
+            self.enable_timer(timeout);
+        } else {
+            self.disable_timer();
+        }
+    }
+
     fn restart_watchdog(&self) {
         self.monitor
             .borrow_mut()
@@ -1046,6 +1315,17 @@ impl ServiceMng {
     }
 
     fn sigchld_result(&self, wait_status: WaitStatus) -> ServiceResult {
+        if self
+            .config
+            .config_data()
+            .borrow()
+            .Service
+            .SuccessExitStatus
+            .exit_status_enabled(wait_status)
+        {
+            return ServiceResult::Success;
+        }
+
         match wait_status {
             WaitStatus::Exited(_, status) => {
                 if status == 0 {
@@ -1079,6 +1359,17 @@ impl ServiceMng {
         let res = self.sigchld_result(wait_status);
 
         if self.pid.main() == Some(pid) {
+            let res = if res != ServiceResult::Success && self.main_command_ignore_failure() {
+                log::info!(
+                    "main command of {} failed with {:?}, ignoring",
+                    self.comm.get_owner_id(),
+                    res
+                );
+                ServiceResult::Success
+            } else {
+                res
+            };
+
             // for main pid updated by the process before its exited, updated the main pid.
             if let Ok(v) = self.load_pid_file() {
                 if v {
@@ -1151,6 +1442,17 @@ impl ServiceMng {
         } else if self.pid.control() == Some(pid) {
             self.pid.reset_control();
 
+            let res = if res != ServiceResult::Success && self.control_command_ignore_failure() {
+                log::info!(
+                    "control command of {} failed with {:?}, ignoring",
+                    self.comm.get_owner_id(),
+                    res
+                );
+                ServiceResult::Success
+            } else {
+                res
+            };
+
             if !self.control_command.borrow().is_empty() && res == ServiceResult::Success {
                 self.run_next_control();
                 return;
@@ -1202,9 +1504,9 @@ impl ServiceMng {
                         let loaded = self.load_pid_file();
                         log::debug!("service in Start state, load pid file result: {:?}", loaded);
                         if loaded.is_err() && !start_post_exist {
-                            match self.demand_pid_file() {
+                            match self.watch_pid_file() {
                                 Ok(_) => {
-                                    if !self.cgroup_good() {
+                                    if self.rd.pid_file_wd().is_some() && !self.cgroup_good() {
                                         self.enter_signal(
                                             ServiceState::StopSigterm,
                                             ServiceResult::FailureProtocol,
@@ -1221,8 +1523,6 @@ impl ServiceMng {
                             }
                             return;
                         }
-
-                        self.enter_start_post();
                     } else {
                         self.guess_main_pid();
                     }
@@ -1232,14 +1532,15 @@ impl ServiceMng {
                 ServiceState::StartPost => {
                     if res != ServiceResult::Success {
                         self.enter_signal(ServiceState::StopSigterm, res);
+                        return;
                     }
 
                     if self.config.config_data().borrow().Service.PIDFile.is_some() {
                         let loaded = self.load_pid_file();
                         if loaded.is_err() {
-                            match self.demand_pid_file() {
+                            match self.watch_pid_file() {
                                 Ok(_) => {
-                                    if !self.cgroup_good() {
+                                    if self.rd.pid_file_wd().is_some() && !self.cgroup_good() {
                                         self.enter_stop(ServiceResult::FailureProtocol);
                                     }
                                 }
@@ -1441,6 +1742,26 @@ fn service_state_to_unit_state(service_type: ServiceType, state: ServiceState) -
     state.to_unit_active_state()
 }
 
+/// the delay before the n-th restart, growing exponentially from RestartSec to
+/// RestartMaxDelaySec over RestartSteps restarts.
+fn restart_sec_next(restart_sec: u64, max_delay_sec: u64, steps: u32, n_restarts_next: u32) -> u64 {
+    if n_restarts_next <= 1
+        || steps == 0
+        || restart_sec == 0
+        || max_delay_sec == 0
+        || restart_sec >= max_delay_sec
+    {
+        return restart_sec;
+    }
+
+    if n_restarts_next > steps {
+        return max_delay_sec;
+    }
+
+    let factor = ((max_delay_sec as f64).ln() - (restart_sec as f64).ln()) / steps as f64;
+    (restart_sec as f64 * (factor * (n_restarts_next - 1) as f64).exp()).round() as u64
+}
+
 pub(super) struct RunningData {
     mng: RefCell<Weak<ServiceMng>>,
     data: RefCell<Rtdata>,
@@ -1458,13 +1779,16 @@ impl RunningData {
         *self.mng.borrow_mut() = Rc::downgrade(&mng);
     }
 
-    pub(self) fn attach_inotify(&self, path_inotify: Rc<PathIntofy>) {
-        path_inotify.attach(self.mng.borrow_mut().clone());
-        self.data.borrow_mut().attach_inotify(path_inotify);
+    pub(self) fn mng(&self) -> Weak<ServiceMng> {
+        self.mng.borrow().clone()
+    }
+
+    pub(self) fn set_pid_file_wd(&self, wd: Option<WatchDescriptor>) {
+        self.data.borrow_mut().set_pid_file_wd(wd);
     }
 
-    pub(self) fn path_inotify(&self) -> Rc<PathIntofy> {
-        self.data.borrow().path_inotify()
+    pub(self) fn pid_file_wd(&self) -> Option<WatchDescriptor> {
+        self.data.borrow().pid_file_wd()
     }
 
     pub(self) fn set_errno(&self, errno: i32) {
@@ -1549,7 +1873,7 @@ impl RunningData {
 struct Rtdata {
     errno: i32,
     notify_state: NotifyState,
-    path_inotify: Option<Rc<PathIntofy>>,
+    pid_file_wd: Option<WatchDescriptor>,
 
     forbid_restart: bool,
     reset_restarts: bool,
@@ -1566,7 +1890,7 @@ impl Rtdata {
         Rtdata {
             errno: 0,
             notify_state: NotifyState::Unknown,
-            path_inotify: None,
+            pid_file_wd: None,
 
             forbid_restart: false,
             reset_restarts: false,
@@ -1594,12 +1918,12 @@ impl Rtdata {
         self.errno
     }
 
-    pub(self) fn attach_inotify(&mut self, path_inotify: Rc<PathIntofy>) {
-        self.path_inotify = Some(path_inotify)
+    pub(self) fn set_pid_file_wd(&mut self, wd: Option<WatchDescriptor>) {
+        self.pid_file_wd = wd;
     }
 
-    pub(self) fn path_inotify(&self) -> Rc<PathIntofy> {
-        self.path_inotify.as_ref().unwrap().clone()
+    pub(self) fn pid_file_wd(&self) -> Option<WatchDescriptor> {
+        self.pid_file_wd
     }
 
     pub(self) fn set_forbid_restart(&mut self, forbid_restart: bool) {
@@ -1667,182 +1991,125 @@ impl Rtdata {
     }
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
-enum PathType {
-    Changed,
-    Modified,
+/// The PID files of all the services are watched through the single inotify
+/// of the event loop, whose events are routed to the waiting services by the
+/// watch descriptor.
+pub(super) struct PidFileWatch {
+    registered: RefCell<bool>,
+    watches: RefCell<HashMap<WatchDescriptor, Vec<Weak<ServiceMng>>>>,
 }
 
-struct PathIntofy {
-    path: PathBuf,
-    p_type: PathType,
-    inotify: RefCell<RawFd>,
-    wd: RefCell<Option<WatchDescriptor>>,
-    mng: RefCell<Weak<ServiceMng>>,
-}
-
-impl fmt::Display for PathIntofy {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "path: {:?}, path type: {:?}, inotify fd: {}",
-            self.path,
-            self.p_type,
-            *self.inotify.borrow()
-        )
-    }
-}
-
-impl PathIntofy {
-    fn new(path: PathBuf) -> Self {
-        PathIntofy {
-            path,
-            p_type: PathType::Modified,
-            inotify: RefCell::new(-1),
-            wd: RefCell::new(None),
-            mng: RefCell::new(Weak::new()),
-        }
-    }
-
-    pub(self) fn attach(&self, mng: Weak<ServiceMng>) {
-        log::debug!("attach service mng to path inotify");
-        *self.mng.borrow_mut() = mng;
-    }
-
-    fn add_watch_path(&self) -> Result<bool> {
-        self.unwatch();
-
-        let inotify = Inotify::init(InitFlags::all()).map_err(|_e| Error::Other {
-            msg: "create initofy fd err".to_string(),
-        })?;
-        *self.inotify.borrow_mut() = inotify.as_raw_fd();
-
-        let ansters = self.path.as_path().ancestors();
-        let mut primary: bool = true;
-        let mut flags: AddWatchFlags;
-
-        let mut exist = false;
-        for anster in ansters {
-            flags = if primary {
-                AddWatchFlags::IN_DELETE_SELF
-                    | AddWatchFlags::IN_MOVE_SELF
-                    | AddWatchFlags::IN_ATTRIB
-                    | AddWatchFlags::IN_CLOSE_WRITE
-                    | AddWatchFlags::IN_CREATE
-                    | AddWatchFlags::IN_DELETE
-                    | AddWatchFlags::IN_MOVED_FROM
-                    | AddWatchFlags::IN_MOVED_TO
-                    | AddWatchFlags::IN_MODIFY
-            } else {
-                AddWatchFlags::IN_DELETE_SELF
-                    | AddWatchFlags::IN_MOVE_SELF
-                    | AddWatchFlags::IN_ATTRIB
-                    | AddWatchFlags::IN_CREATE
-                    | AddWatchFlags::IN_MOVED_TO
-            };
-
-            log::debug!(
-                "inotify fd is: {}, flags is: {:?}, path: {:?}",
-                *self.inotify.borrow(),
-                flags,
-                anster
-            );
-
-            match inotify.add_watch(anster, flags) {
+impl PidFileWatch {
+    pub(super) fn new() -> Self {
+        PidFileWatch {
+            registered: RefCell::new(false),
+            watches: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// watch the PID file, or the nearest existing ancestor of it
+    fn watch(
+        self: &Rc<Self>,
+        events: &Events,
+        mng: Weak<ServiceMng>,
+        path: &Path,
+    ) -> Result<WatchDescriptor> {
+        if !*self.registered.borrow() {
+            let source: Rc<dyn Source> = self.clone();
+            events
+                .add_source(source.clone())
+                .map_err(|e| Error::Other {
+                    msg: format!("failed to add the pid file watch source: {}", e),
+                })?;
+            events
+                .set_enabled(source, EventState::On)
+                .map_err(|e| Error::Other {
+                    msg: format!("failed to enable the pid file watch source: {}", e),
+                })?;
+            *self.registered.borrow_mut() = true;
+        }
+
+        // the watch descriptors are shared by the services watching the same
+        // directory, so every watch point is added with the same mask.
+        let flags = AddWatchFlags::IN_DELETE_SELF
+            | AddWatchFlags::IN_MOVE_SELF
+            | AddWatchFlags::IN_ATTRIB
+            | AddWatchFlags::IN_CLOSE_WRITE
+            | AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_DELETE
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_MOVED_TO
+            | AddWatchFlags::IN_MODIFY;
+
+        for ancestor in path.ancestors() {
+            match events.add_watch(ancestor, flags) {
                 Ok(wd) => {
-                    if primary {
-                        *self.wd.borrow_mut() = Some(wd);
-                    }
-
-                    exist = true;
-                    break;
+                    log::debug!("watch on path {:?}, wd: {:?}", ancestor, wd);
+                    self.watches.borrow_mut().entry(wd).or_default().push(mng);
+                    return Ok(wd);
                 }
-                Err(err) => {
-                    log::error!("watch on path {:?} error: {:?}", anster, err);
+                Err(e) => {
+                    log::debug!("watch on path {:?} error: {}", ancestor, e);
                 }
             }
-
-            primary = false;
-        }
-
-        if !exist {
-            return Err(Error::Other {
-                msg: "watch on any of the ancestor failed".to_string(),
-            });
         }
 
-        Ok(true)
-    }
-
-    fn unwatch(&self) {
-        fd_util::close(*self.inotify.borrow());
-        *self.inotify.borrow_mut() = -1;
+        Err(Error::Other {
+            msg: format!("failed to watch any ancestor of {:?}", path),
+        })
     }
 
-    fn read_fd_event(&self) -> Result<bool> {
-        let inotify = unsafe { Inotify::from_raw_fd(*self.inotify.borrow_mut()) };
-        let events = match inotify.read_events() {
-            Ok(events) => events,
-            Err(e) => {
-                if e == Errno::EAGAIN || e == Errno::EINTR {
-                    return Ok(false);
-                }
-
-                return Err(Error::Other {
-                    msg: "read evnets from inotify error".to_string(),
-                });
+    fn unwatch(&self, events: &Events, mng: &Weak<ServiceMng>, wd: WatchDescriptor) {
+        let mut watches = self.watches.borrow_mut();
+        let empty = match watches.get_mut(&wd) {
+            Some(mngs) => {
+                mngs.retain(|m| !m.ptr_eq(mng));
+                mngs.is_empty()
             }
+            None => return,
         };
 
-        if IN_SET!(self.p_type, PathType::Changed, PathType::Modified) {
-            for event in events {
-                if let Some(ref wd) = *self.wd.borrow() {
-                    if event.wd == *wd {
-                        return Ok(true);
-                    }
-                }
-            }
+        if empty {
+            watches.remove(&wd);
+            events.rm_watch(wd);
         }
-
-        Ok(false)
-    }
-
-    pub(self) fn mng(&self) -> Rc<ServiceMng> {
-        self.mng.borrow().clone().upgrade().unwrap()
     }
 
-    fn do_dispatch(&self) -> i32 {
-        log::debug!("dispatch initify pid file: {:?}", self.path);
-        match self.read_fd_event() {
-            Ok(_) => {
-                if let Ok(_v) = self.mng().retry_pid_file() {
-                    return 0;
-                }
-
-                if let Ok(_v) = self.mng().watch_pid_file() {
-                    return 0;
+    fn do_dispatch(&self, events: &Events) -> i32 {
+        let mut mngs: Vec<Rc<ServiceMng>> = Vec::new();
+        for event in events.read_events() {
+            log::debug!("dispatch pid file inotify event: {:?}", event);
+            let mut watches = self.watches.borrow_mut();
+            if let Some(ws) = watches.get(&event.wd) {
+                for mng in ws.iter().filter_map(|m| m.upgrade()) {
+                    if !mngs.iter().any(|m| Rc::ptr_eq(m, &mng)) {
+                        mngs.push(mng);
+                    }
                 }
             }
-            Err(e) => {
-                log::error!("in inotify dispatch, read event error: {}", e);
-                return -1;
+
+            // the watch point is removed by the kernel, the services will
+            // watch again when dispatched.
+            if event.mask.contains(AddWatchFlags::IN_IGNORED) {
+                watches.remove(&event.wd);
             }
         }
 
-        self.mng().unwatch_pid_file();
-        self.mng()
-            .enter_signal(ServiceState::StopSigterm, ServiceResult::FailureResources);
+        for mng in mngs {
+            mng.dispatch_pid_file();
+            mng.db_update();
+        }
         0
     }
 }
 
-impl Source for PathIntofy {
+impl Source for PidFileWatch {
     fn fd(&self) -> RawFd {
-        *self.inotify.borrow()
+        0
     }
 
     fn event_type(&self) -> EventType {
-        EventType::Io
+        EventType::Inotify
     }
 
     fn epoll_event(&self) -> u32 {
@@ -1853,10 +2120,8 @@ impl Source for PathIntofy {
         0i8
     }
 
-    fn dispatch(&self, _: &Events) -> i32 {
-        let ret = self.do_dispatch();
-        self.mng().db_update();
-        ret
+    fn dispatch(&self, events: &Events) -> i32 {
+        self.do_dispatch(events)
     }
 
     fn token(&self) -> u64 {
@@ -1893,30 +2158,36 @@ impl ServiceTimer {
     fn do_dispatch(&self) -> i32 {
         log::debug!("dispatch service timer");
 
-        match self.mng().state() {
-            ServiceState::Dead => todo!(),
-            ServiceState::Condition => todo!(),
-            ServiceState::Start => todo!(),
-            ServiceState::StartPost => todo!(),
+        let mng = self.mng();
+        match mng.state() {
+            state @ (ServiceState::Condition | ServiceState::StartPre | ServiceState::Start) => {
+                log::warn!(
+                    "{} {:?} operation timed out, terminating",
+                    mng.comm.get_owner_id(),
+                    state
+                );
+                mng.enter_signal(ServiceState::StopSigterm, ServiceResult::FailureTimeout);
+            }
+            ServiceState::StartPost => {
+                log::warn!(
+                    "{} start post operation timed out, stopping",
+                    mng.comm.get_owner_id()
+                );
+                mng.enter_stop(ServiceResult::FailureTimeout);
+            }
             ServiceState::Running => {
-                self.mng().enter_stop(ServiceResult::FailureTimeout);
+                mng.enter_stop(ServiceResult::FailureTimeout);
             }
-            ServiceState::Exited => todo!(),
-            ServiceState::Reload => todo!(),
-            ServiceState::StartPre => todo!(),
-            ServiceState::Stop => todo!(),
-            ServiceState::StopWatchdog => todo!(),
-            ServiceState::StopPost => todo!(),
-            ServiceState::StopSigterm => todo!(),
-            ServiceState::StopSigkill => todo!(),
-            ServiceState::FinalWatchdog => todo!(),
-            ServiceState::FinalSigterm => todo!(),
-            ServiceState::FinalSigkill => todo!(),
             ServiceState::AutoRestart => {
-                self.mng().enter_restart();
+                mng.enter_restart();
+            }
+            state => {
+                log::debug!(
+                    "{} timer fired in state {:?}, ignoring",
+                    mng.comm.get_owner_id(),
+                    state
+                );
             }
-            ServiceState::Failed => todo!(),
-            ServiceState::Cleaning => todo!(),
         }
         0
     }
@@ -2023,8 +2294,11 @@ impl Source for ServiceMonitorData {
 
 #[cfg(test)]
 mod tests {
-    use super::{RunningData, ServiceMng};
+    use super::{restart_sec_next, RunningData, ServiceMng};
+    use crate::rentry::{ExitStatusSet, ServiceRestart, ServiceState};
     use crate::{comm::ServiceUnitComm, config::ServiceConfig};
+    use nix::sys::wait::WaitStatus;
+    use nix::unistd::Pid;
     use std::{collections::HashMap, rc::Rc};
     use sysmaster::{exec::ExecContext, UmIf};
 
@@ -2053,6 +2327,18 @@ mod tests {
         (mng, rt, config)
     }
 
+    #[test]
+    fn test_start_timer() {
+        let (mng, rt, config) = create_mng();
+        let timeout = config.config_data().borrow().Service.TimeoutStartSec;
+        assert!(timeout > 0);
+
+        // the start pre operation is bounded by TimeoutStartSec too
+        mng.arm_start_timer(ServiceState::Dead, ServiceState::StartPre);
+        assert!(rt.armd_timer());
+        assert_eq!(*rt.timer().time.borrow(), timeout);
+    }
+
     #[test]
     fn test_watchdog_on() {
         use nix::sys::socket::UnixCredentials;
@@ -2099,4 +2385,78 @@ mod tests {
         assert!(rt.armd_watchdog());
         assert_eq!(rt.watchdog().time(), 15);
     }
+
+    #[test]
+    fn test_load_pid_file() {
+        let (mng, _rt, config) = create_mng();
+        let pid_file = "/tmp/sysmaster_test_load_pid_file.pid";
+        let _ = std::fs::remove_file(pid_file);
+        config.config_data().borrow_mut().Service.PIDFile = Some(pid_file.to_string());
+
+        // not exist
+        assert!(mng.load_pid_file().is_err());
+
+        // not a pid
+        std::fs::write(pid_file, "abc\n").unwrap();
+        assert!(mng.load_pid_file().is_err());
+
+        // not in the cgroup of the service, only accepted from a root-owned file
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        std::fs::write(pid_file, format!("{}\n", child.id())).unwrap();
+        let loaded = mng.load_pid_file();
+        if nix::unistd::getuid().is_root() {
+            assert!(loaded.unwrap());
+            assert_eq!(mng.pid.main(), Some(Pid::from_raw(child.id() as i32)));
+        } else {
+            assert!(loaded.is_err());
+        }
+
+        child.kill().unwrap();
+        let _ = child.wait();
+        let _ = std::fs::remove_file(pid_file);
+    }
+
+    #[test]
+    fn test_restart_sec_next() {
+        // no steps configured, always RestartSec
+        assert_eq!(restart_sec_next(5, 0, 0, 1), 5);
+        assert_eq!(restart_sec_next(5, 100, 0, 10), 5);
+        // RestartMaxDelaySec is not larger than RestartSec
+        assert_eq!(restart_sec_next(5, 5, 3, 2), 5);
+
+        // 1 -> 2 -> 4 -> 8 over three steps
+        assert_eq!(restart_sec_next(1, 8, 3, 1), 1);
+        assert_eq!(restart_sec_next(1, 8, 3, 2), 2);
+        assert_eq!(restart_sec_next(1, 8, 3, 3), 4);
+        assert_eq!(restart_sec_next(1, 8, 3, 4), 8);
+        assert_eq!(restart_sec_next(1, 8, 3, 100), 8);
+    }
+
+    #[test]
+    fn test_shall_restart_exit_status() {
+        let (mng, rt, config) = create_mng();
+        config.config_data().borrow_mut().Service.Restart = ServiceRestart::No;
+        rt.set_wait_status(WaitStatus::Exited(Pid::from_raw(1), 3));
+        assert!(!mng.shall_restart());
+
+        let mut force = ExitStatusSet::default();
+        force.add_status(3);
+        config
+            .config_data()
+            .borrow_mut()
+            .Service
+            .RestartForceExitStatus = force.clone();
+        assert!(mng.shall_restart());
+
+        // a status in both lists is not restarted
+        config
+            .config_data()
+            .borrow_mut()
+            .Service
+            .RestartPreventExitStatus = force;
+        assert!(!mng.shall_restart());
+    }
 }
@@ -14,6 +14,7 @@
 use crate::monitor::ServiceMonitor;
 use confique::Config;
 use macros::EnumDisplay;
+use nix::sys::resource::Resource;
 use nix::sys::signal::Signal;
 use nix::sys::wait::WaitStatus;
 use nix::unistd::Pid;
@@ -22,7 +23,10 @@ use std::collections::VecDeque;
 use std::rc::Rc;
 use std::str::FromStr;
 use sysmaster::error::*;
-use sysmaster::exec::ExecCommand;
+use sysmaster::exec::{
+    CpuSchedulingPolicy, ExecCommand, ExecInput, ExecOutput, IoSchedulingClass, ProtectHome,
+    ProtectSystem, Rlimit,
+};
 use sysmaster::rel::{ReDb, ReDbRoTxn, ReDbRwTxn, ReDbTable, Reliability};
 use sysmaster::serialize::DeserializeWith;
 use sysmaster::unit::KillMode;
@@ -94,6 +98,17 @@ pub(super) enum ServiceRestart {
     Always,
 }
 
+#[derive(PartialEq, Eq, Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub(super) enum RuntimeDirectoryPreserve {
+    #[serde(alias = "no")]
+    #[default]
+    No,
+    #[serde(alias = "yes")]
+    Yes,
+    #[serde(alias = "restart")]
+    Restart,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ExitStatusSet {
     status: Vec<u8>,
@@ -101,7 +116,7 @@ pub struct ExitStatusSet {
 }
 
 impl ExitStatusSet {
-    fn add_status(&mut self, status: u8) {
+    pub(super) fn add_status(&mut self, status: u8) {
         self.status.push(status);
     }
 
@@ -149,7 +164,7 @@ impl DeserializeWith for ExitStatusSet {
                 status_set.add_signal(cmd.to_string());
                 continue;
             }
-            log::warn!("RestartPreventExitStatus: invalid config value {}", cmd);
+            log::warn!("ExitStatusSet: invalid config value {}", cmd);
         }
 
         Ok(status_set)
@@ -180,6 +195,10 @@ pub(super) struct SectionService {
     #[config(default = 0)]
     pub WatchdogSec: u64,
     pub PIDFile: Option<String>,
+    #[config(default = true)]
+    pub GuessMainPID: bool,
+    #[config(default = 90)]
+    pub TimeoutStartSec: u64,
     #[config(default = false)]
     pub RemainAfterExit: bool,
     pub NotifyAccess: Option<NotifyAccess>,
@@ -194,6 +213,8 @@ pub(super) struct SectionService {
     pub User: String,
     #[config(default = "")]
     pub Group: String,
+    #[config(default = false)]
+    pub DynamicUser: bool,
     #[config(default = "0022")]
     pub UMask: String,
     #[config(default = "no")]
@@ -203,14 +224,173 @@ pub(super) struct SectionService {
     pub RestartPreventExitStatus: ExitStatusSet,
     #[config(default = 0)]
     pub RestartSec: u64,
+    #[config(default = 0)]
+    pub RestartSteps: u32,
+    #[config(default = 0)]
+    pub RestartMaxDelaySec: u64,
+    #[config(deserialize_with = ExitStatusSet::deserialize_with)]
+    #[config(default = "")]
+    pub RestartForceExitStatus: ExitStatusSet,
+    #[config(deserialize_with = ExitStatusSet::deserialize_with)]
+    #[config(default = "")]
+    pub SuccessExitStatus: ExitStatusSet,
     #[config(deserialize_with = Vec::<String>::deserialize_with)]
     #[config(default = "")]
     pub EnvironmentFile: Vec<String>,
     #[config(default = "SIGTERM")]
     pub KillSignal: String,
+    #[config(deserialize_with = ExecInput::deserialize_with)]
+    #[config(default = "null")]
+    pub StandardInput: ExecInput,
+    #[config(deserialize_with = ExecOutput::deserialize_with)]
+    #[config(default = "inherit")]
+    pub StandardOutput: ExecOutput,
+    #[config(deserialize_with = ExecOutput::deserialize_with)]
+    #[config(default = "inherit")]
+    pub StandardError: ExecOutput,
+    #[config(deserialize_with = ProtectSystem::deserialize_with)]
+    #[config(default = "no")]
+    pub ProtectSystem: ProtectSystem,
+    #[config(deserialize_with = ProtectHome::deserialize_with)]
+    #[config(default = "no")]
+    pub ProtectHome: ProtectHome,
+    #[config(default = false)]
+    pub PrivateTmp: bool,
+    #[config(default = false)]
+    pub PrivateNetwork: bool,
+    #[config(default = false)]
+    pub PrivateIPC: bool,
+    #[config(default = false)]
+    pub ProtectHostname: bool,
+    #[config(default = false)]
+    pub PrivateUsers: bool,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub ReadOnlyPaths: Vec<String>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub InaccessiblePaths: Vec<String>,
+    #[config(default = "")]
+    pub RootDirectory: String,
+    #[config(default = false)]
+    pub MountAPIVFS: bool,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub BindPaths: Vec<String>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub BindReadOnlyPaths: Vec<String>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub SystemCallFilter: Vec<String>,
+    #[config(default = "")]
+    pub SystemCallErrorNumber: String,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub SystemCallArchitectures: Vec<String>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub CapabilityBoundingSet: Vec<String>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub AmbientCapabilities: Vec<String>,
+    #[config(default = false)]
+    pub NoNewPrivileges: bool,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub RuntimeDirectory: Vec<String>,
+    #[config(default = "0755")]
+    pub RuntimeDirectoryMode: String,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub StateDirectory: Vec<String>,
+    #[config(default = "0755")]
+    pub StateDirectoryMode: String,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub CacheDirectory: Vec<String>,
+    #[config(default = "0755")]
+    pub CacheDirectoryMode: String,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub LogsDirectory: Vec<String>,
+    #[config(default = "0755")]
+    pub LogsDirectoryMode: String,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub ConfigurationDirectory: Vec<String>,
+    #[config(default = "0755")]
+    pub ConfigurationDirectoryMode: String,
+    #[config(default = "no")]
+    pub RuntimeDirectoryPreserve: RuntimeDirectoryPreserve,
+    pub Nice: Option<i32>,
+    pub CPUSchedulingPolicy: Option<CpuSchedulingPolicy>,
+    pub CPUSchedulingPriority: Option<i32>,
+    #[config(default = "")]
+    pub CPUAffinity: String,
+    pub IOSchedulingClass: Option<IoSchedulingClass>,
+    pub IOSchedulingPriority: Option<i32>,
+    pub OOMScoreAdjust: Option<i32>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitCPU: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitFSIZE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitDATA: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitSTACK: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitCORE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitRSS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitNOFILE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitAS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitNPROC: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitMEMLOCK: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitLOCKS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitSIGPENDING: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitMSGQUEUE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_nice)]
+    pub LimitNICE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitRTPRIO: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitRTTIME: Option<Rlimit>,
 }
 
 impl SectionService {
+    /// the resource limits configured by Limit*=
+    pub(super) fn rlimits(&self) -> Vec<(Resource, Rlimit)> {
+        [
+            (Resource::RLIMIT_CPU, self.LimitCPU),
+            (Resource::RLIMIT_FSIZE, self.LimitFSIZE),
+            (Resource::RLIMIT_DATA, self.LimitDATA),
+            (Resource::RLIMIT_STACK, self.LimitSTACK),
+            (Resource::RLIMIT_CORE, self.LimitCORE),
+            (Resource::RLIMIT_RSS, self.LimitRSS),
+            (Resource::RLIMIT_NOFILE, self.LimitNOFILE),
+            (Resource::RLIMIT_AS, self.LimitAS),
+            (Resource::RLIMIT_NPROC, self.LimitNPROC),
+            (Resource::RLIMIT_MEMLOCK, self.LimitMEMLOCK),
+            (Resource::RLIMIT_LOCKS, self.LimitLOCKS),
+            (Resource::RLIMIT_SIGPENDING, self.LimitSIGPENDING),
+            (Resource::RLIMIT_MSGQUEUE, self.LimitMSGQUEUE),
+            (Resource::RLIMIT_NICE, self.LimitNICE),
+            (Resource::RLIMIT_RTPRIO, self.LimitRTPRIO),
+            (Resource::RLIMIT_RTTIME, self.LimitRTTIME),
+        ]
+        .into_iter()
+        .filter_map(|(r, l)| l.map(|l| (r, l)))
+        .collect()
+    }
+
     pub(super) fn set_notify_access(&mut self, v: NotifyAccess) {
         self.NotifyAccess = Some(v);
     }
@@ -330,6 +510,7 @@ struct ServiceReMng {
     restarts: u32,
     exit_status: ExitStatus,
     monitor: ServiceMonitor,
+    dynamic_user: Option<(String, u32)>, // DynamicUser: the user name and the allocated uid
 }
 
 impl ServiceReMng {
@@ -348,6 +529,7 @@ impl ServiceReMng {
         restarts: u32,
         exit_status: ExitStatus,
         monitor: ServiceMonitor,
+        dynamic_user: Option<(String, u32)>,
     ) -> ServiceReMng {
         ServiceReMng {
             state,
@@ -363,6 +545,7 @@ impl ServiceReMng {
             restarts,
             exit_status,
             monitor,
+            dynamic_user,
         }
     }
 }
@@ -387,7 +570,7 @@ impl ServiceRe {
         self.conf.0.insert(unit_id.to_string(), conf);
     }
 
-    pub(super) fn _conf_remove(&self, unit_id: &String) {
+    pub(super) fn conf_remove(&self, unit_id: &String) {
         self.conf.0.remove(unit_id);
     }
 
@@ -413,6 +596,7 @@ impl ServiceRe {
         restarts: u32,
         exit_status: ExitStatus,
         monitor: ServiceMonitor,
+        dynamic_user: Option<(String, u32)>,
     ) {
         let m_pid = main_pid.map(|x| x.as_raw());
         let c_pid = control_pid.map(|x| x.as_raw());
@@ -430,11 +614,12 @@ impl ServiceRe {
             restarts,
             exit_status,
             monitor,
+            dynamic_user,
         );
         self.mng.0.insert(unit_id.to_string(), mng);
     }
 
-    pub(super) fn _mng_remove(&self, unit_id: &String) {
+    pub(super) fn mng_remove(&self, unit_id: &String) {
         self.mng.0.remove(unit_id);
     }
 
@@ -456,6 +641,7 @@ impl ServiceRe {
         u32,
         ExitStatus,
         ServiceMonitor,
+        Option<(String, u32)>,
     )> {
         let mng = self.mng.0.get(unit_id);
         mng.map(|m| {
@@ -473,6 +659,7 @@ impl ServiceRe {
                 m.restarts,
                 m.exit_status,
                 m.monitor,
+                m.dynamic_user,
             )
         })
     }
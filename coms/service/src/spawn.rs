@@ -14,17 +14,31 @@ use super::comm::ServiceUnitComm;
 use super::config::ServiceConfig;
 use super::pid::ServicePid;
 use super::rentry::ServiceType;
-use nix::unistd::Pid;
+use basic::fd_util;
+use basic::user_group_util::{self, DYNAMIC_UID_MAX, DYNAMIC_UID_MIN};
+use nix::unistd::{Gid, Pid, Uid};
+use siphasher::sip::SipHasher24;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::prelude::RawFd;
+use std::path::PathBuf;
 use std::rc::Rc;
 use sysmaster::error::*;
-use sysmaster::exec::{ExecCommand, ExecContext, ExecFlags, ExecParameters};
+use sysmaster::exec::{ExecCommand, ExecContext, ExecDirectoryType, ExecFlags, ExecParameters};
 
 pub(super) struct ServiceSpawn {
     comm: Rc<ServiceUnitComm>,
     pid: Rc<ServicePid>,
     config: Rc<ServiceConfig>,
     exec_ctx: Rc<ExecContext>,
+    // the connection fd passed by the Accept socket
+    socket_fd: RefCell<RawFd>,
+    // DynamicUser: the user name and the allocated uid, None if a static user has the name
+    dynamic_user: RefCell<Option<(String, Option<u32>)>>,
 }
 
 impl ServiceSpawn {
@@ -39,6 +53,33 @@ impl ServiceSpawn {
             pid: Rc::clone(pidr),
             config: configr.clone(),
             exec_ctx: exec_ctx.clone(),
+            socket_fd: RefCell::new(-1),
+            dynamic_user: RefCell::new(None),
+        }
+    }
+
+    pub(super) fn set_dynamic_user(&self, user: Option<(String, Option<u32>)>) {
+        *self.dynamic_user.borrow_mut() = user;
+    }
+
+    pub(super) fn dynamic_user(&self) -> Option<(String, Option<u32>)> {
+        self.dynamic_user.borrow().clone()
+    }
+
+    pub(super) fn set_socket_fd(&self, fd: RawFd) -> Result<()> {
+        if fd >= 0 && *self.socket_fd.borrow() >= 0 {
+            return Err(Error::UnitActionEBusy);
+        }
+
+        self.close_socket_fd();
+        *self.socket_fd.borrow_mut() = fd;
+        Ok(())
+    }
+
+    pub(super) fn close_socket_fd(&self) {
+        let fd = self.socket_fd.replace(-1);
+        if fd >= 0 {
+            fd_util::close(fd);
         }
     }
 
@@ -71,7 +112,9 @@ impl ServiceSpawn {
         unit.prepare_exec()?;
 
         if ec_flags.contains(ExecFlags::PASS_FDS) {
-            params.insert_fds(self.collect_socket_fds());
+            let (fds, fd_names) = self.collect_socket_fds().into_iter().unzip();
+            params.insert_fds(fds);
+            params.insert_fd_names(fd_names);
         }
 
         if self.config.service_type() == ServiceType::Notify
@@ -83,21 +126,7 @@ impl ServiceSpawn {
             params.set_notify_sock(notify_sock);
         }
 
-        if let Err(e) = params.add_user(self.config.config_data().borrow().Service.User.clone()) {
-            log::error!(
-                "Failed to add user to execute parameters: {}",
-                e.to_string()
-            );
-            return Err(e);
-        }
-
-        if let Err(e) = params.add_group(self.config.config_data().borrow().Service.Group.clone()) {
-            log::error!(
-                "Failed to add group to execute parameters: {}",
-                e.to_string()
-            );
-            return Err(e);
-        }
+        self.add_credentials(&mut params)?;
 
         if let Err(e) = params.add_umask(self.config.config_data().borrow().Service.UMask.clone()) {
             log::error!(
@@ -134,7 +163,61 @@ impl ServiceSpawn {
         }
     }
 
-    fn collect_socket_fds(&self) -> Vec<i32> {
+    /// create the managed directories owned by the user of the service, this is done once
+    /// per start before the first command is spawned
+    pub(super) fn setup_exec_directories(&self) -> Result<()> {
+        let mut params = ExecParameters::new();
+        self.add_credentials(&mut params)?;
+        let uid = params.get_user().map_or(Uid::from_raw(0), |u| u.uid);
+        let gid = params.get_group().map_or(Gid::from_raw(0), |g| g.gid);
+
+        for (dir_type, directory) in self.exec_ctx.directories() {
+            directory.setup(dir_type, uid, gid)?;
+        }
+        Ok(())
+    }
+
+    fn add_credentials(&self, params: &mut ExecParameters) -> Result<()> {
+        let (user, dynamic_uid) = match self.dynamic_user() {
+            Some((name, uid)) => (name, uid),
+            None => (
+                self.config.config_data().borrow().Service.User.clone(),
+                None,
+            ),
+        };
+
+        if let Some(uid) = dynamic_uid {
+            params.set_dynamic_user(&user, uid);
+        } else {
+            if let Err(e) = params.add_user(user) {
+                log::error!(
+                    "Failed to add user to execute parameters: {}",
+                    e.to_string()
+                );
+                return Err(e);
+            }
+
+            if let Err(e) =
+                params.add_group(self.config.config_data().borrow().Service.Group.clone())
+            {
+                log::error!(
+                    "Failed to add group to execute parameters: {}",
+                    e.to_string()
+                );
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn collect_socket_fds(&self) -> Vec<(i32, String)> {
+        // the instance of the Accept socket only gets its connection
+        let fd = *self.socket_fd.borrow();
+        if fd >= 0 {
+            return vec![(fd, "connection".to_string())];
+        }
+
         self.comm.um().collect_socket_fds(&self.comm.get_owner_id())
     }
 
@@ -142,3 +225,174 @@ impl ServiceSpawn {
         self.config.config_data().borrow().Service.WatchdogSec
     }
 }
+
+/// the dynamic users allocated for the services, the services with the same user name share
+/// the same uid.
+pub(super) struct DynamicUsers {
+    // user name => (uid, the services using it)
+    users: RefCell<HashMap<String, (u32, HashSet<String>)>>,
+}
+
+impl DynamicUsers {
+    pub(super) fn new() -> Self {
+        DynamicUsers {
+            users: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// allocate an uid for the user name from the dynamic range, which is not used by the
+    /// other dynamic users or the user database. The uid still owning dirs, the managed
+    /// directories of the service, is reused if it is free. Otherwise the uids owning the
+    /// managed directories of other services are skipped, so they are not handed over.
+    pub(super) fn acquire(&self, unit_id: &str, name: &str, dirs: &[PathBuf]) -> Result<u32> {
+        let mut users = self.users.borrow_mut();
+        if let Some((uid, units)) = users.get_mut(name) {
+            units.insert(unit_id.to_string());
+            return Ok(*uid);
+        }
+
+        let mut used = user_group_util::used_ids();
+        used.extend(users.values().map(|(uid, _)| *uid));
+
+        let previous = dirs
+            .iter()
+            .filter_map(|d| fs::symlink_metadata(d).ok())
+            .map(|m| m.uid())
+            .find(|uid| (DYNAMIC_UID_MIN..=DYNAMIC_UID_MAX).contains(uid) && !used.contains(uid));
+
+        let uid = match previous {
+            Some(uid) => uid,
+            None => {
+                used.extend(directory_owners());
+
+                // start from the hash of the name with the fixed keys, so that the same name
+                // gets the same uid across the restarts as long as it is free
+                let range = DYNAMIC_UID_MAX - DYNAMIC_UID_MIN + 1;
+                let mut hasher = SipHasher24::new_with_keys(0, 0);
+                name.hash(&mut hasher);
+                let start = (hasher.finish() % range as u64) as u32;
+
+                (0..range)
+                    .map(|i| DYNAMIC_UID_MIN + (start + i) % range)
+                    .find(|uid| !used.contains(uid))
+                    .ok_or_else(|| Error::Other {
+                        msg: "no dynamic user id is available".to_string(),
+                    })?
+            }
+        };
+
+        users.insert(
+            name.to_string(),
+            (uid, HashSet::from([unit_id.to_string()])),
+        );
+        Ok(uid)
+    }
+
+    /// register the uid allocated before reexec
+    pub(super) fn restore(&self, unit_id: &str, name: &str, uid: u32) {
+        self.users
+            .borrow_mut()
+            .entry(name.to_string())
+            .or_insert_with(|| (uid, HashSet::new()))
+            .1
+            .insert(unit_id.to_string());
+    }
+
+    /// release the uid when no service uses it
+    pub(super) fn release(&self, unit_id: &str, name: &str) {
+        let mut users = self.users.borrow_mut();
+        if let Some((_, units)) = users.get_mut(name) {
+            units.remove(unit_id);
+            if units.is_empty() {
+                users.remove(name);
+            }
+        }
+    }
+}
+
+/// the owners of the managed directories, the configuration directories are owned by root
+fn directory_owners() -> HashSet<u32> {
+    let mut owners = HashSet::new();
+    for dir_type in [
+        ExecDirectoryType::Runtime,
+        ExecDirectoryType::State,
+        ExecDirectoryType::Cache,
+        ExecDirectoryType::Logs,
+    ] {
+        if let Ok(entries) = fs::read_dir(dir_type.prefix()) {
+            owners.extend(
+                entries
+                    .flatten()
+                    .filter_map(|e| e.metadata().ok())
+                    .map(|m| m.uid()),
+            );
+        }
+    }
+    owners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynamicUsers;
+    use basic::user_group_util::{DYNAMIC_UID_MAX, DYNAMIC_UID_MIN};
+    use nix::unistd::{self, Gid, Uid};
+    use std::fs;
+
+    #[test]
+    fn test_dynamic_users() {
+        let users = DynamicUsers::new();
+        let uid = users.acquire("foo.service", "foo", &[]).unwrap();
+        assert!((DYNAMIC_UID_MIN..=DYNAMIC_UID_MAX).contains(&uid));
+        assert_eq!(users.acquire("foo-bar.service", "foo", &[]).unwrap(), uid);
+
+        let other = users.acquire("bar.service", "bar", &[]).unwrap();
+        assert_ne!(other, uid);
+
+        users.release("foo.service", "foo");
+        assert!(users.users.borrow().contains_key("foo"));
+        users.release("foo-bar.service", "foo");
+        assert!(!users.users.borrow().contains_key("foo"));
+
+        users.restore("baz.service", "baz", DYNAMIC_UID_MIN);
+        assert_eq!(
+            users.acquire("qux.service", "baz", &[]).unwrap(),
+            DYNAMIC_UID_MIN
+        );
+    }
+
+    #[test]
+    fn test_dynamic_users_previous_uid() {
+        if !unistd::getuid().is_root() {
+            return;
+        }
+
+        let dir = std::env::temp_dir().join(format!("sysmaster-dynamic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let previous = DYNAMIC_UID_MIN + 7;
+        unistd::chown(
+            &dir,
+            Some(Uid::from_raw(previous)),
+            Some(Gid::from_raw(previous)),
+        )
+        .unwrap();
+
+        // the service gets the uid still owning its directories back
+        let users = DynamicUsers::new();
+        let uid = users
+            .acquire("foo.service", "foo", std::slice::from_ref(&dir))
+            .unwrap();
+        assert_eq!(uid, previous);
+
+        // but not when another dynamic user holds it
+        users.release("foo.service", "foo");
+        users.restore("bar.service", "bar", previous);
+        assert_ne!(
+            users
+                .acquire("foo.service", "foo", std::slice::from_ref(&dir))
+                .unwrap(),
+            previous
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -17,7 +17,7 @@ use super::comm::ServiceUnitComm;
 use super::config::ServiceConfig;
 use super::mng::RunningData;
 use super::mng::ServiceMng;
-use super::rentry::{NotifyAccess, ServiceCommand, ServiceType};
+use super::rentry::{NotifyAccess, SectionService, ServiceCommand, ServiceType};
 use basic::logger;
 use basic::special::{BASIC_TARGET, SHUTDOWN_TARGET, SYSINIT_TARGET};
 use nix::sys::signal::Signal;
@@ -33,7 +33,10 @@ use sysmaster::unit::{
     SubUnit, UmIf, UnitActiveState, UnitBase, UnitDependencyMask, UnitMngUtil, UnitRelations,
 };
 
-use sysmaster::exec::ExecContext;
+use sysmaster::exec::{
+    parse_cpu_set, ExecCapabilities, ExecContext, ExecDirectory, ExecDirectoryType, ExecNamespace,
+    ExecScheduling, SyscallFilter,
+};
 
 struct ServiceUnit {
     comm: Rc<ServiceUnitComm>,
@@ -76,7 +79,7 @@ impl SubUnit for ServiceUnit {
     }
 
     fn done(&self) {
-        todo!()
+        self.comm.rentry_remove();
     }
 
     fn load(&self, paths: Vec<PathBuf>) -> Result<()> {
@@ -155,6 +158,10 @@ impl SubUnit for ServiceUnit {
         self.db_insert();
     }
 
+    fn set_socket_fd(&self, fd: i32) -> Result<()> {
+        self.mng.set_socket_fd(fd)
+    }
+
     fn notify_message(
         &self,
         ucred: &UnixCredentials,
@@ -209,6 +216,124 @@ impl ServiceUnit {
         Ok(())
     }
 
+    fn parse_namespace(&self, service: &SectionService) -> Result<()> {
+        let parse_paths = |paths: &[String]| {
+            ExecNamespace::parse_paths(paths).map_err(|msg| Error::ConfigureError { msg })
+        };
+
+        let root_directory = match service.RootDirectory.as_str() {
+            "" => None,
+            root => Some(PathBuf::from(root)),
+        };
+        if let Some(root) = root_directory.as_ref().filter(|r| !r.is_absolute()) {
+            return Err(Error::ConfigureError {
+                msg: format!("RootDirectory {root:?} is not an absolute path"),
+            });
+        }
+
+        let parse_bind_paths = |paths: &[String], read_only: bool| {
+            ExecNamespace::parse_bind_paths(paths, read_only)
+                .map_err(|msg| Error::ConfigureError { msg })
+        };
+        let mut bind_paths = parse_bind_paths(&service.BindPaths, false)?;
+        bind_paths.extend(parse_bind_paths(&service.BindReadOnlyPaths, true)?);
+
+        self.exec_ctx.set_namespace(ExecNamespace {
+            protect_system: service.ProtectSystem,
+            protect_home: service.ProtectHome,
+            private_tmp: service.PrivateTmp,
+            read_only_paths: parse_paths(&service.ReadOnlyPaths)?,
+            inaccessible_paths: parse_paths(&service.InaccessiblePaths)?,
+            private_network: service.PrivateNetwork,
+            private_ipc: service.PrivateIPC,
+            protect_hostname: service.ProtectHostname,
+            private_users: service.PrivateUsers,
+            root_directory,
+            mount_api_vfs: service.MountAPIVFS,
+            bind_paths,
+        });
+        Ok(())
+    }
+
+    fn parse_syscall_filter(&self, service: &SectionService) -> Result<()> {
+        let filter = SyscallFilter::parse(
+            &service.SystemCallFilter,
+            &service.SystemCallErrorNumber,
+            &service.SystemCallArchitectures,
+        )
+        .map_err(|msg| Error::ConfigureError { msg })?;
+
+        self.exec_ctx.set_syscall_filter(filter);
+        Ok(())
+    }
+
+    fn parse_capabilities(&self, service: &SectionService) -> Result<()> {
+        let parse_set = |entries: &[String]| {
+            ExecCapabilities::parse_set(entries).map_err(|msg| Error::ConfigureError { msg })
+        };
+
+        self.exec_ctx.set_capabilities(ExecCapabilities {
+            bounding_set: parse_set(&service.CapabilityBoundingSet)?,
+            ambient_set: parse_set(&service.AmbientCapabilities)?.unwrap_or(0),
+            no_new_privileges: service.NoNewPrivileges,
+        });
+        Ok(())
+    }
+
+    fn parse_scheduling(&self, service: &SectionService) -> Result<()> {
+        let scheduling = ExecScheduling {
+            nice: service.Nice,
+            policy: service.CPUSchedulingPolicy,
+            priority: service.CPUSchedulingPriority,
+            cpu_affinity: parse_cpu_set(&service.CPUAffinity)
+                .map_err(|msg| Error::ConfigureError { msg })?,
+            io_class: service.IOSchedulingClass,
+            io_priority: service.IOSchedulingPriority,
+            oom_score_adjust: service.OOMScoreAdjust,
+        };
+        scheduling
+            .validate()
+            .map_err(|msg| Error::ConfigureError { msg })?;
+
+        self.exec_ctx.set_scheduling(scheduling);
+        Ok(())
+    }
+
+    fn parse_directories(&self, service: &SectionService) -> Result<()> {
+        for (dir_type, paths, mode) in [
+            (
+                ExecDirectoryType::Runtime,
+                &service.RuntimeDirectory,
+                &service.RuntimeDirectoryMode,
+            ),
+            (
+                ExecDirectoryType::State,
+                &service.StateDirectory,
+                &service.StateDirectoryMode,
+            ),
+            (
+                ExecDirectoryType::Cache,
+                &service.CacheDirectory,
+                &service.CacheDirectoryMode,
+            ),
+            (
+                ExecDirectoryType::Logs,
+                &service.LogsDirectory,
+                &service.LogsDirectoryMode,
+            ),
+            (
+                ExecDirectoryType::Configuration,
+                &service.ConfigurationDirectory,
+                &service.ConfigurationDirectoryMode,
+            ),
+        ] {
+            let directory =
+                ExecDirectory::parse(paths, mode).map_err(|msg| Error::ConfigureError { msg })?;
+            self.exec_ctx.set_directory(dir_type, directory);
+        }
+        Ok(())
+    }
+
     fn parse(&self) -> Result<()> {
         if let Some(envs) = self.config.environments() {
             for env in envs {
@@ -231,6 +356,19 @@ impl ServiceUnit {
                 .clone(),
         );
 
+        let service = self.config.config_data().borrow().Service.clone();
+        self.exec_ctx.set_stdio(
+            service.StandardInput.clone(),
+            service.StandardOutput.clone(),
+            service.StandardError.clone(),
+        );
+        self.exec_ctx.set_rlimits(service.rlimits());
+        self.parse_namespace(&service)?;
+        self.parse_syscall_filter(&service)?;
+        self.parse_capabilities(&service)?;
+        self.parse_scheduling(&service)?;
+        self.parse_directories(&service)?;
+
         if let Some(owner) = self.comm.owner() {
             if let Some(sockets) = self.config.sockets() {
                 let um = self.comm.um();
@@ -339,6 +477,19 @@ impl ServiceUnit {
         });
         }
 
+        let restart_sec = self.config.config_data().borrow().Service.RestartSec;
+        let restart_max_delay_sec = self
+            .config
+            .config_data()
+            .borrow()
+            .Service
+            .RestartMaxDelaySec;
+        if restart_max_delay_sec != 0 && restart_max_delay_sec < restart_sec {
+            return Err(Error::ConfigureError {
+                msg: "RestartMaxDelaySec= is configured smaller than RestartSec=".to_string(),
+            });
+        }
+
         Ok(())
     }
 
@@ -19,24 +19,26 @@ use crate::base::NetlinkProtocol;
 use basic::socket_util;
 use confique::Config;
 use nix::errno::Errno;
+use nix::libc;
 use nix::sys::signal::Signal;
 use nix::sys::socket::sockopt::ReuseAddr;
 use nix::sys::socket::{
     self, AddressFamily, NetlinkAddr, SockFlag, SockProtocol, SockType, SockaddrIn, SockaddrIn6,
-    SockaddrLike, UnixAddr,
+    SockaddrLike, UnixAddr, VsockAddr,
 };
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::fmt;
 use std::fs;
 use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::os::unix::prelude::RawFd;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::str::FromStr;
 use sysmaster::error::*;
 use sysmaster::exec::ExecCommand;
 use sysmaster::rel::ReStation;
-use sysmaster::unit::KillContext;
+use sysmaster::unit::{unit_fragment_load, KillContext};
 
 ///
 #[derive(Default)]
@@ -129,8 +131,12 @@ impl SocketConfig {
     pub(super) fn load(&self, paths: Vec<PathBuf>, update: bool) -> Result<()> {
         // get original configuration
         let mut builder = SocketConfigData::builder().env();
+        let name = self
+            .comm
+            .owner()
+            .map_or_else(String::new, |u| u.id().to_string());
         for v in paths {
-            builder = builder.file(v);
+            builder = builder.preloaded(unit_fragment_load(&name, &v)?);
         }
         let data = builder.load().context(ConfiqueSnafu)?;
 
@@ -205,6 +211,11 @@ impl SocketConfig {
 
         self.parse_listen_socket(ListeningItem::SequentialPacket, config.clone())?;
 
+        self.parse_listen_socket(ListeningItem::Fifo, config.clone())?;
+        self.parse_listen_socket(ListeningItem::Special, config.clone())?;
+        self.parse_listen_socket(ListeningItem::MessageQueue, config.clone())?;
+        self.parse_listen_socket(ListeningItem::USBFunction, config.clone())?;
+
         Ok(())
     }
 
@@ -250,6 +261,27 @@ impl SocketConfig {
                     self.parse_sockets(sequential_packet, SockType::SeqPacket)?;
                 }
             }
+            ListeningItem::Fifo => {
+                if let Some(listen_fifo) = socket_conf.borrow().Socket.ListenFIFO.clone() {
+                    self.parse_files(listen_fifo, PortType::Fifo)?;
+                }
+            }
+            ListeningItem::Special => {
+                if let Some(listen_special) = socket_conf.borrow().Socket.ListenSpecial.clone() {
+                    self.parse_files(listen_special, PortType::Special)?;
+                }
+            }
+            ListeningItem::MessageQueue => {
+                if let Some(listen_mqueue) = socket_conf.borrow().Socket.ListenMessageQueue.clone()
+                {
+                    self.parse_files(listen_mqueue, PortType::MessageQueue)?;
+                }
+            }
+            ListeningItem::USBFunction => {
+                if let Some(listen_usb) = socket_conf.borrow().Socket.ListenUSBFunction.clone() {
+                    self.parse_files(listen_usb, PortType::USBFunction)?;
+                }
+            }
         }
 
         Ok(())
@@ -273,6 +305,34 @@ impl SocketConfig {
         Ok(())
     }
 
+    fn parse_files(&self, listens: Vec<String>, p_type: PortType) -> Result<()> {
+        for v in &listens {
+            if v.is_empty() {
+                continue;
+            }
+
+            if !v.starts_with('/') {
+                log::error!(
+                    "parsing listening {:?} failed, not an absolute path: {}",
+                    p_type,
+                    v
+                );
+                return Err(format!("parsing listening {p_type:?} failed: {v}").into());
+            }
+
+            // the message queue name is like "/name", no more slash is allowed
+            if p_type == PortType::MessageQueue && v[1..].contains('/') {
+                log::error!("parsing listening message queue failed: {}", v);
+                return Err(format!("parsing listening message queue failed: {v}").into());
+            }
+
+            let port = SocketPortConf::new_file(p_type, v);
+            self.push_port(Rc::new(port));
+        }
+
+        Ok(())
+    }
+
     fn set_ref(&self, target: String) {
         if let Some(u) = self.comm.owner() {
             self.service
@@ -304,6 +364,10 @@ enum ListeningItem {
     Datagram,
     Netlink,
     SequentialPacket,
+    Fifo,
+    Special,
+    MessageQueue,
+    USBFunction,
 }
 
 #[derive(Config, Default, Debug)]
@@ -358,7 +422,8 @@ impl SocketConfigData {
 
 pub(super) struct SocketPortConf {
     p_type: PortType,
-    sa: SocketAddress,
+    // only the socket has the address, others are identified by the path
+    sa: Option<SocketAddress>,
     listen: String,
 }
 
@@ -366,7 +431,15 @@ impl SocketPortConf {
     pub(super) fn new(p_type: PortType, sa: SocketAddress, listenr: &str) -> SocketPortConf {
         SocketPortConf {
             p_type,
-            sa,
+            sa: Some(sa),
+            listen: String::from(listenr),
+        }
+    }
+
+    pub(super) fn new_file(p_type: PortType, listenr: &str) -> SocketPortConf {
+        SocketPortConf {
+            p_type,
+            sa: None,
             listen: String::from(listenr),
         }
     }
@@ -375,8 +448,8 @@ impl SocketPortConf {
         self.p_type
     }
 
-    pub(super) fn sa(&self) -> &SocketAddress {
-        &self.sa
+    pub(super) fn sa(&self) -> Option<&SocketAddress> {
+        self.sa.as_ref()
     }
 
     pub(super) fn listen(&self) -> &str {
@@ -428,7 +501,13 @@ impl SocketAddress {
         self.sock_addr.family().unwrap()
     }
 
-    pub(super) fn socket_listen(&self, flags: SockFlag, backlog: usize) -> Result<i32, Errno> {
+    /// create the socket, the options which must be set before binding are applied by pre_bind
+    pub(super) fn socket_listen(
+        &self,
+        flags: SockFlag,
+        backlog: usize,
+        pre_bind: impl FnOnce(RawFd),
+    ) -> Result<i32, Errno> {
         log::debug!(
             "create socket, family: {:?}, type: {:?}, protocol: {:?}",
             self.sock_addr.family().unwrap(),
@@ -442,29 +521,43 @@ impl SocketAddress {
             self.protocol,
         )?;
 
+        if let Err(e) = self.bind_listen(fd, backlog, pre_bind) {
+            nix::unistd::close(fd).ok();
+            return Err(e);
+        }
+
+        Ok(fd)
+    }
+
+    fn bind_listen(
+        &self,
+        fd: RawFd,
+        backlog: usize,
+        pre_bind: impl FnOnce(RawFd),
+    ) -> Result<(), Errno> {
         socket::setsockopt(fd, ReuseAddr, &true)?;
+        pre_bind(fd);
 
         if let Some(path) = self.path() {
             let parent_path = path.as_path().parent();
             fs::create_dir_all(parent_path.unwrap()).map_err(|_e| Errno::EINVAL)?;
-            if let Err(Errno::EADDRINUSE) = socket::bind(fd, &*self.sock_addr) {
-                self.unlink();
-                socket::bind(fd, &*self.sock_addr)?;
+            match socket::bind(fd, &*self.sock_addr) {
+                // the socket file is left by the previous run
+                Err(Errno::EADDRINUSE) => {
+                    self.unlink();
+                    socket::bind(fd, &*self.sock_addr)?;
+                }
+                ret => ret?,
             }
         } else {
             socket::bind(fd, &*self.sock_addr)?;
         }
 
         if self.can_accept() {
-            match socket::listen(fd, backlog) {
-                Ok(_) => {}
-                Err(e) => {
-                    return Err(e);
-                }
-            }
+            socket::listen(fd, backlog)?;
         }
 
-        Ok(fd)
+        Ok(())
     }
 
     pub(super) fn unlink(&self) {
@@ -494,6 +587,14 @@ impl fmt::Display for SocketAddress {
     }
 }
 
+/// parse the file mode in octal, like "0755"
+pub(super) fn parse_mode(mode: &str) -> Result<u32> {
+    match u32::from_str_radix(mode, 8) {
+        Ok(v) if v <= 0o7777 => Ok(v),
+        _ => Err(format!("invalid file mode: {mode}").into()),
+    }
+}
+
 fn parse_netlink_address(item: &str) -> Result<SocketAddress> {
     let words: Vec<String> = item.split_whitespace().map(|s| s.to_string()).collect();
     if words.len() != 2 {
@@ -520,18 +621,48 @@ fn parse_netlink_address(item: &str) -> Result<SocketAddress> {
     ))
 }
 
+/// parse the vsock address in the format of "CID:PORT", the CID can be omitted to listen on any CID
+fn parse_vsock_address(item: &str, socket_type: SockType) -> Result<SocketAddress> {
+    let (cid, port) = match item.split_once(':') {
+        Some(v) => v,
+        None => return Err(format!("vsock address format is not correct: {item}").into()),
+    };
+
+    let cid = if cid.is_empty() {
+        libc::VMADDR_CID_ANY
+    } else {
+        cid.parse::<u32>()
+            .map_err(|_| format!("invalid vsock cid: {cid}"))?
+    };
+    let port = port
+        .parse::<u32>()
+        .map_err(|_| format!("invalid vsock port: {port}"))?;
+
+    let addr = VsockAddr::new(cid, port);
+    Ok(SocketAddress::new(Box::new(addr), socket_type, None))
+}
+
 fn parse_socket_address(item: &str, socket_type: SockType) -> Result<SocketAddress> {
     if item.starts_with('/') {
         let unix_addr = UnixAddr::new(&PathBuf::from(item)).context(NixSnafu)?;
         return Ok(SocketAddress::new(Box::new(unix_addr), socket_type, None));
     }
 
-    if item.starts_with('@') {
-        let unix_addr = UnixAddr::new_abstract(item.as_bytes()).context(NixSnafu)?;
+    if let Some(name) = item.strip_prefix('@') {
+        if name.is_empty() {
+            return Err("the name of the abstract socket is empty"
+                .to_string()
+                .into());
+        }
+        let unix_addr = UnixAddr::new_abstract(name.as_bytes()).context(NixSnafu)?;
 
         return Ok(SocketAddress::new(Box::new(unix_addr), socket_type, None));
     }
 
+    if let Some(addr) = item.strip_prefix("vsock:") {
+        return parse_vsock_address(addr, socket_type);
+    }
+
     if let Ok(port) = item.parse::<u16>() {
         if port == 0 {
             return Err("invalid port number".to_string().into());
@@ -566,8 +697,10 @@ fn parse_socket_address(item: &str, socket_type: SockType) -> Result<SocketAddre
 #[cfg(test)]
 mod tests {
     use crate::comm::SocketUnitComm;
-    use crate::config::SocketConfig;
+    use crate::config::{parse_mode, parse_socket_address, SocketConfig};
+    use crate::rentry::PortType;
     use libtests::get_project_root;
+    use nix::sys::socket::{AddressFamily, SockType, SockaddrLike, UnixAddr, VsockAddr};
     use std::rc::Rc;
 
     #[test]
@@ -582,4 +715,87 @@ mod tests {
 
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_socket_parse_mode_integer() {
+        let mut file_path = get_project_root().unwrap();
+        file_path.push("tests/test_units/test.socket");
+        let paths = vec![file_path];
+
+        let comm = Rc::new(SocketUnitComm::new());
+        let config = SocketConfig::new(&comm);
+        assert!(config.load(paths, false).is_ok());
+
+        let data = config.config_data();
+        let socket = &data.borrow().Socket;
+        assert_eq!(parse_mode(&socket.SocketMode).unwrap(), 0o600);
+        assert_eq!(parse_mode(&socket.DirectoryMode).unwrap(), 0o755);
+    }
+
+    #[test]
+    fn test_socket_parse_files() {
+        let comm = Rc::new(SocketUnitComm::new());
+        let config = SocketConfig::new(&comm);
+
+        let fifos = vec!["/run/test.fifo".to_string(), "".to_string()];
+        assert!(config.parse_files(fifos, PortType::Fifo).is_ok());
+        let mqueues = vec!["/test.mqueue".to_string()];
+        assert!(config.parse_files(mqueues, PortType::MessageQueue).is_ok());
+
+        let ports = config.ports();
+        assert_eq!(ports.len(), 2);
+        assert_eq!(ports[0].p_type(), PortType::Fifo);
+        assert_eq!(ports[0].listen(), "/run/test.fifo");
+        assert!(ports[0].sa().is_none());
+        assert_eq!(ports[1].p_type(), PortType::MessageQueue);
+
+        let relative = vec!["test.fifo".to_string()];
+        assert!(config.parse_files(relative, PortType::Fifo).is_err());
+        let mqueue = vec!["/test/mqueue".to_string()];
+        assert!(config.parse_files(mqueue, PortType::MessageQueue).is_err());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse_mode("0755").unwrap(), 0o755);
+        assert_eq!(parse_mode("600").unwrap(), 0o600);
+        assert!(parse_mode("0855").is_err());
+        assert!(parse_mode("17777").is_err());
+    }
+
+    #[test]
+    fn test_socket_abstract_addr() {
+        let sa = parse_socket_address("@test.socket", SockType::Stream).unwrap();
+        assert_eq!(sa.family(), AddressFamily::Unix);
+        assert!(sa.path().is_none());
+        assert!(sa.can_accept());
+
+        let unix_addr =
+            unsafe { UnixAddr::from_raw(sa.sock_addr.as_ptr(), Some(sa.sock_addr.len())) }.unwrap();
+        assert_eq!(unix_addr.as_abstract().unwrap(), b"test.socket");
+
+        assert!(parse_socket_address("@", SockType::Stream).is_err());
+    }
+
+    #[test]
+    fn test_socket_vsock_addr() {
+        let sa = parse_socket_address("vsock:3:1024", SockType::Stream).unwrap();
+        assert_eq!(sa.family(), AddressFamily::Vsock);
+        assert!(sa.path().is_none());
+        let vsock_addr =
+            unsafe { VsockAddr::from_raw(sa.sock_addr.as_ptr(), Some(sa.sock_addr.len())) }
+                .unwrap();
+        assert_eq!(vsock_addr.cid(), 3);
+        assert_eq!(vsock_addr.port(), 1024);
+
+        let sa = parse_socket_address("vsock::1024", SockType::SeqPacket).unwrap();
+        let vsock_addr =
+            unsafe { VsockAddr::from_raw(sa.sock_addr.as_ptr(), Some(sa.sock_addr.len())) }
+                .unwrap();
+        assert_eq!(vsock_addr.cid(), u32::MAX);
+
+        assert!(parse_socket_address("vsock:3", SockType::Stream).is_err());
+        assert!(parse_socket_address("vsock:x:1024", SockType::Stream).is_err());
+        assert!(parse_socket_address("vsock:3:", SockType::Stream).is_err());
+    }
 }
@@ -46,6 +46,8 @@
 //!
 //!  Unix sockets starting with @ will be created as abstract namespaces by default.
 //!
+//!  If the format is vsock:CID:PORT, create an AF_VSOCK socket with the context ID of CID and port of PORT, the CID can be omitted to listen on any CID.
+//!
 //!  If it is a number type, it will create a socket of IPv6 type by default. If it does not support IPv6 type, it will create a socket of IPv4 type.
 //!
 //!  If the format is a.b.c.d: x, create an IPv4 socket with the IP address of "a.b.c.d" and port of x.
@@ -57,6 +59,21 @@
 //!  Set a Netlink socket to listen to. The format is {name}+{group ID}
 //!  The currently supported protocols are named route, inet diag, selinux, iscsi, audit, fib lookup, netfilter, ip6 fw, dnrtmsg, kobject_ uevent、scsitransport、rdma
 //!
+//!  ListenFIFO、ListenSpecial
+//!
+//!  Set a FIFO or a special file (character device or regular file) to listen to, the path must be absolute.
+//!  The FIFO is created if it does not exist, the special file must already exist.
+//!
+//!  ListenMessageQueue
+//!
+//!  Set a POSIX message queue to listen to, the name must start with / and contain no other /.
+//!  The attributes of the queue are set with MessageQueueMaxMessages and MessageQueueMessageSize, they are only applied if both are larger than 0.
+//!
+//!  ListenUSBFunction
+//!
+//!  Set the mount point of a FunctionFS to listen to, the descriptors and strings in the files configured by
+//!  USBFunctionDescriptors and USBFunctionStrings are written to its ep0 endpoint, and both of them are required.
+//!
 //!  ReceiveBuffer 、SendBuffer
 //!
 //!  Set the size of the socket's receive and send buffers. The configured type is numeric.
@@ -73,9 +90,111 @@
 //!
 //!  Can be set to true or false (default), whether to allow the socket to receive the security context sent by the peer process in the auxiliary message.
 //!
+//!  ReusePort、FreeBind、Transparent
+//!
+//!  Can be set to true or false, set SO_REUSEPORT, IP_FREEBIND and IP_TRANSPARENT (or the IPv6 ones) before binding. Transparent requires CAP_NET_ADMIN.
+//!
+//!  BindIPv6Only
+//!
+//!  Can be set to default, both or ipv6-only, whether the IPv6 socket accepts IPv4 connections too. default follows the kernel setting.
+//!
+//!  BindToDevice
+//!
+//!  Bind the socket to the network interface, the traffic is only accepted from it.
+//!
+//!  Backlog
+//!
+//!  The length of the queue of the pending connections, the default is 128.
+//!
+//!  IPTOS、IPTTL
+//!
+//!  Set the TOS field (traffic class for IPv6) and the TTL (hop limit for IPv6) of the IP packets, the configured type is numeric.
+//!
+//!  Mark、Priority
+//!
+//!  Set the firewall mark and the priority of the packets sent through the socket, the configured type is numeric.
+//!
+//!  DeferAcceptSec
+//!
+//!  Only wake up the listener when data arrives on the connection, in seconds, only for TCP sockets.
+//!
+//!  NoDelay
+//!
+//!  Can be set to true or false, whether to disable the Nagle algorithm of TCP sockets.
+//!
+//!  Timestamping
+//!
+//!  Can be set to off, us (usec) or ns (nsec), the resolution of the timestamps of the received packets.
+//!
+//!  TCPCongestion
+//!
+//!  Set the TCP congestion algorithm, such as cubic or reno.
+//!
+//!  NetworkNamespacePath
+//!
+//!  Set the absolute path of a network namespace, such as /run/netns/foo, the sockets are created and bound inside it.
+//!
+//!  SmackLabel、SmackLabelIPIn、SmackLabelIPOut
+//!
+//!  Set the SMACK label of the FIFO or special file, and the labels of the packets received and sent by the socket.
+//!  The labels of the socket are applied before binding, they are ignored if SMACK is not enabled.
+//!
+//!  SELinuxContext
+//!
+//!  Set the SELinux context of the sockets and FIFOs created, it is ignored if SELinux is not enabled.
+//!
+//!  SELinuxContextFromNet
+//!
+//!  Not supported and ignored. The context comes from the peer of the connection, and sysmaster can not apply an SELinux context to the spawned service.
+//!
 //!  SocketMode
 //!
-//!  Set the access mode when creating a file node, which is applicable to files created when unix sockets are used.
+//!  Set the access mode in octal when creating a file node, which is applicable to the unix socket files, FIFOs and message queues, the default is 0666. Both a string such as "0600" and an integer such as 600 are accepted.
+//!
+//!  DirectoryMode
+//!
+//!  Set the access mode of the parent directories created for unix sockets and FIFOs, the default is 0755.
+//!
+//!  SocketUser、SocketGroup
+//!
+//!  Set the owner user and group of the unix socket file, FIFO or message queue, in the form of a name or a numeric id, the default is not changed.
+//!
+//!  RemoveOnStop
+//!
+//!  Can be set to true or false (default), whether to remove the unix socket file, FIFO, message queue and the symlinks when the socket is stopped.
+//!  The unix socket file left by the previous run is always replaced when the socket is started.
+//!
+//!  Symlinks
+//!
+//!  Set the absolute paths of the symlinks to the unix socket file or FIFO, only one of them can be listened to if it is configured.
+//!
+//!  TriggerLimitIntervalSec、TriggerLimitBurst
+//!
+//!  Limit how often the socket is triggered, the socket fails with the result trigger-limit-hit if it is triggered more than
+//!  TriggerLimitBurst times in TriggerLimitIntervalSec seconds. The default interval is 2 seconds, and the default burst is 200
+//!  for the accepting socket and 20 otherwise. Setting either of them to 0 disables the limit.
+//!
+//!  FileDescriptorName
+//!
+//!  Set the name of the fds passed to the service in LISTEN_FDNAMES, which is separated by ':' and matches the order of the fds.
+//!  The name must be printable ASCII without ':', the default is the socket unit name. The connection of the accepting socket is named "connection".
+//!
+//!  Accept
+//!
+//!  Can be set to true or false (default). If true, a service instance is spawned for each accepted connection,
+//!  the instance is named "{prefix}@{nr}-{local}-{remote}.service" for IP sockets, like "foo@0-127.0.0.1:80-127.0.0.1:5000.service",
+//!  and "{prefix}@{nr}-{pid}-{uid}.service" for unix sockets, it is created from the template "{prefix}@.service".
+//!  The connection is passed to the instance as the only socket in LISTEN_FDS, or as stdin/stdout with StandardInput="socket".
+//!  Service can't be configured together with Accept=true.
+//!
+//!  MaxConnections
+//!
+//!  The maximum number of the concurrent connections when Accept=true, the connection is refused if exceeded, the default is 64.
+//!
+//!  MaxConnectionsPerSource
+//!
+//!  The maximum number of the concurrent connections from one source when Accept=true, the source is the peer address for IP sockets,
+//!  and the peer uid for unix sockets. The default is 0, which means only MaxConnections is limited.
 
 // dependency:
 // socket_base -> service_rentry -> {socket_comm | socket_config}
@@ -13,7 +13,7 @@
 //! socket_load mod parse the field of section Socket and add the extra dependency。
 //!
 use crate::comm::SocketUnitComm;
-use crate::config::SocketConfig;
+use crate::config::{parse_mode, SocketConfig};
 use crate::rentry::PortType;
 use basic::special::{SHUTDOWN_TARGET, SOCKETS_TARGET, SYSINIT_TARGET};
 use std::path::Path;
@@ -59,9 +59,82 @@ impl SocketLoad {
     }
 
     pub(super) fn socket_verify(&self) -> Result<()> {
+        let socket = self.config.config_data().borrow().Socket.clone();
+        if socket.Accept && socket.Service.is_some() {
+            return Err(
+                "explicit service configuration for accepting socket is not supported"
+                    .to_string()
+                    .into(),
+            );
+        }
+
+        if socket.Accept && socket.MaxConnections == 0 {
+            return Err(
+                "MaxConnections of the accepting socket must be larger than 0"
+                    .to_string()
+                    .into(),
+            );
+        }
+
+        parse_mode(&socket.DirectoryMode)?;
+        parse_mode(&socket.SocketMode)?;
+
+        if let Some(symlinks) = &socket.Symlinks {
+            if !symlinks.is_empty() && !self.has_one_file_port() {
+                return Err(
+                    "Symlinks requires exactly one unix socket path or FIFO to listen to"
+                        .to_string()
+                        .into(),
+                );
+            }
+            if let Some(link) = symlinks.iter().find(|l| !Path::new(l).is_absolute()) {
+                return Err(format!("symlink is not absolute: {link}").into());
+            }
+        }
+
+        if let Some(path) = &socket.NetworkNamespacePath {
+            if !Path::new(path).is_absolute() {
+                return Err(format!("NetworkNamespacePath is not absolute: {path}").into());
+            }
+        }
+
+        if let Some(name) = &socket.FileDescriptorName {
+            if !fd_name_is_valid(name) {
+                return Err(format!("invalid FileDescriptorName: {name}").into());
+            }
+        }
+
+        let has_usb = self
+            .config
+            .ports()
+            .iter()
+            .any(|p| p.p_type() == PortType::USBFunction);
+        if has_usb
+            && (socket.USBFunctionDescriptors.is_empty() || socket.USBFunctionStrings.is_empty())
+        {
+            return Err(
+                "USB function socket lacks USBFunctionDescriptors or USBFunctionStrings"
+                    .to_string()
+                    .into(),
+            );
+        }
+
         Ok(())
     }
 
+    fn has_one_file_port(&self) -> bool {
+        let ports = self.config.ports();
+        if ports.len() != 1 {
+            return false;
+        }
+
+        match ports[0].p_type() {
+            PortType::Socket => ports[0].sa().map_or(false, |sa| sa.path().is_some()),
+            PortType::Fifo => true,
+            _ => false,
+        }
+    }
+
     fn load_related_unit(&self, related_type: UnitType) -> Result<()> {
         let unit_name = self.comm.owner().map(|u| u.id().to_string());
         let suffix = String::from(related_type);
@@ -92,7 +165,7 @@ impl SocketLoad {
                 return true;
             }
 
-            if !port.sa().can_accept() {
+            if !port.sa().map_or(false, |sa| sa.can_accept()) {
                 return true;
             }
         }
@@ -139,8 +212,16 @@ impl SocketLoad {
     }
 }
 
+/// the name is passed in LISTEN_FDNAMES separated by ':', so it must be printable ASCII without ':'
+fn fd_name_is_valid(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 255
+        && name.bytes().all(|c| (b' '..0x7f).contains(&c) && c != b':')
+}
+
 #[cfg(test)]
 mod tests {
+    use super::fd_name_is_valid;
     use crate::{comm::SocketUnitComm, config::SocketConfig};
     use libtests::get_project_root;
     use std::rc::Rc;
@@ -156,4 +237,14 @@ mod tests {
         let config = SocketConfig::new(&comm);
         assert!(config.load(paths, false).is_ok());
     }
+
+    #[test]
+    fn test_fd_name_is_valid() {
+        assert!(fd_name_is_valid("stored"));
+        assert!(fd_name_is_valid("foo bar"));
+        assert!(!fd_name_is_valid(""));
+        assert!(!fd_name_is_valid("foo:bar"));
+        assert!(!fd_name_is_valid("foo\nbar"));
+        assert!(!fd_name_is_valid(&"a".repeat(256)));
+    }
 }
@@ -20,10 +20,11 @@ use super::{
     rentry::{PortType, SocketCommand, SocketRe, SocketReFrame, SocketResult, SocketState},
     spawn::SocketSpawn,
 };
-use basic::IN_SET;
+use basic::{fd_util, unit_name, IN_SET};
 use event::EventState;
 use event::{EventType, Events, Source};
 use nix::libc::{self};
+use nix::sys::socket::{self, sockopt, AddressFamily, SockaddrLike, SockaddrStorage};
 use nix::sys::wait::WaitStatus;
 use std::os::unix::prelude::RawFd;
 use std::rc::{Rc, Weak};
@@ -32,7 +33,10 @@ use sysmaster::error::*;
 use sysmaster::exec::{ExecCommand, ExecContext};
 use sysmaster::rel::ReliLastFrame;
 use sysmaster::rel::{ReStation, Reliability};
-use sysmaster::unit::{KillOperation, UnitActiveState, UnitNotifyFlags, UnitType};
+use sysmaster::unit::{
+    KillOperation, RateLimit, UnitActiveState, UnitBase, UnitDependencyMask, UnitNotifyFlags,
+    UnitRelations, UnitType,
+};
 
 impl SocketState {
     pub(super) fn to_unit_active_state(self) -> UnitActiveState {
@@ -129,6 +133,13 @@ impl SocketMng {
         state.to_string()
     }
 
+    pub(super) fn get_result(&self) -> Option<String> {
+        match self.data.result() {
+            SocketResult::Success => None,
+            res => Some(res.to_string()),
+        }
+    }
+
     pub(super) fn sigchld_event(&self, wait_status: WaitStatus) {
         self.data.sigchld_event(wait_status);
         self.db_update();
@@ -162,6 +173,10 @@ struct SocketMngData {
     control_cmd_type: RefCell<Option<SocketCommand>>,
     control_command: RefCell<VecDeque<ExecCommand>>,
     refused: RefCell<i32>,
+    n_accepted: RefCell<u32>,
+    // the instances spawned for the accepted connections, and the source of the connections
+    conns: RefCell<Vec<(String, String)>>,
+    trigger_limit: RefCell<RateLimit>,
 }
 
 // the declaration "pub(self)" is for identification only.
@@ -183,6 +198,9 @@ impl SocketMngData {
             control_cmd_type: RefCell::new(None),
             control_command: RefCell::new(VecDeque::new()),
             refused: RefCell::new(0),
+            n_accepted: RefCell::new(0),
+            conns: RefCell::new(Vec::new()),
+            trigger_limit: RefCell::new(RateLimit::new(0, 0)),
         })
     }
 
@@ -383,9 +401,19 @@ impl SocketMngData {
 
     fn enter_running(&self, fd: i32) {
         if let Some(u) = self.comm.owner() {
+            if !self.trigger_limit_below() {
+                log::warn!("{} trigger limit hit, refusing further activation.", u.id());
+                if fd >= 0 {
+                    fd_util::close(fd);
+                }
+                self.enter_stop_pre(SocketResult::FailureTriggerLimitHit);
+                return;
+            }
+
             if self.comm.um().has_stop_job(u.id()) {
                 if fd >= 0 {
                     *self.refused.borrow_mut() += 1;
+                    fd_util::close(fd);
                     return;
                 }
                 self.flush_ports();
@@ -409,13 +437,131 @@ impl SocketMngData {
                     }
                 }
                 self.set_state(SocketState::Running);
-            } else {
-                // template support
-                todo!()
+            } else if let Err(e) = self.spawn_connection(&u, fd) {
+                log::error!("Failed to serve the connection of {}: {}", u.id(), e);
             }
         }
     }
 
+    fn trigger_limit_below(&self) -> bool {
+        let (interval, burst) = {
+            let data = self.config.config_data();
+            let socket = &data.borrow().Socket;
+            // the accepting socket is triggered by every connection, allow more
+            let burst = if socket.Accept { 200 } else { 20 };
+            (
+                socket.TriggerLimitIntervalSec.unwrap_or(2),
+                socket.TriggerLimitBurst.unwrap_or(burst),
+            )
+        };
+
+        let mut limit = self.trigger_limit.borrow_mut();
+        limit.init_from_config(interval, burst);
+        limit.ratelimit_below()
+    }
+
+    /// start a service instance for the accepted connection, the fd is taken over.
+    fn spawn_connection(&self, u: &Rc<dyn UnitBase>, fd: RawFd) -> Result<()> {
+        let source = connection_source(fd);
+        if let Err(e) = self.check_connections(&source) {
+            *self.refused.borrow_mut() += 1;
+            fd_util::close(fd);
+            return Err(e);
+        }
+
+        let nr = self.n_accepted.replace_with(|n| n.wrapping_add(1));
+        let template = format!("{}@.service", unit_name::unit_name_to_prefix(u.id()));
+        let service =
+            unit_name::unit_name_replace_instance(&template, &connection_instance(fd, nr)).unwrap();
+
+        let um = self.comm.um();
+        if !um.load_unit_success(&service) {
+            fd_util::close(fd);
+            return Err(format!("failed to load unit {service}").into());
+        }
+
+        let ret = um
+            .unit_add_two_dependency(
+                u.id(),
+                UnitRelations::UnitBefore,
+                UnitRelations::UnitTriggers,
+                &service,
+                false,
+                UnitDependencyMask::Implicit,
+            )
+            .and_then(|_| um.unit_set_socket_fd(&service, fd));
+        if let Err(e) = ret {
+            fd_util::close(fd);
+            return Err(e);
+        }
+
+        self.rentry().set_last_frame(SocketReFrame::FdListen(false)); // protect 'start_unit'
+        let ret = um.start_unit(&service);
+        self.rentry().set_last_frame(SocketReFrame::FdListen(true));
+        if let Err(e) = ret {
+            // release the fd that the service will never use
+            let _ = um.unit_set_socket_fd(&service, -1);
+            return Err(e);
+        }
+
+        self.conns.borrow_mut().push((service, source));
+        Ok(())
+    }
+
+    /// forget the instances which have finished and unload them, each of them
+    /// serves only one connection and is never started again.
+    fn collect_connections(&self) {
+        let um = self.comm.um();
+        let mut finished = Vec::new();
+        self.conns.borrow_mut().retain(|(service, _)| {
+            let running = um.has_start_job(service)
+                || !matches!(
+                    um.current_active_state(service),
+                    UnitActiveState::UnitInActive | UnitActiveState::UnitFailed
+                );
+            if !running {
+                finished.push(service.clone());
+            }
+            running
+        });
+
+        for service in finished {
+            if let Err(e) = um.unload_unit(&service) {
+                log::warn!("Failed to unload the finished instance {}: {}", service, e);
+            }
+        }
+    }
+
+    /// check the limits of the connections before spawning a new instance
+    fn check_connections(&self, source: &str) -> Result<()> {
+        self.collect_connections();
+
+        let data = self.config.config_data();
+        let max = data.borrow().Socket.MaxConnections;
+        let max_per_source = data.borrow().Socket.MaxConnectionsPerSource;
+
+        let conns = self.conns.borrow();
+        if conns.len() >= max as usize {
+            return Err(Error::Other {
+                msg: format!(
+                    "too many incoming connections ({}), dropping connection",
+                    conns.len()
+                ),
+            });
+        }
+
+        let n = conns.iter().filter(|(_, s)| s == source).count();
+        if max_per_source > 0 && !source.is_empty() && n >= max_per_source as usize {
+            return Err(Error::Other {
+                msg: format!(
+                    "too many incoming connections ({n}) from source {source}, dropping connection"
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
     fn enter_stop_pre(&self, res: SocketResult) {
         log::debug!("enter stop pre command");
         if self.result() == SocketResult::Success {
@@ -891,7 +1037,7 @@ impl SocketMngPort {
     }
 
     fn dispatch_io(&self) -> Result<i32> {
-        let afd: i32 = -1;
+        let mut afd: i32 = -1;
 
         if self.mng().state() != SocketState::Listening {
             return Ok(0);
@@ -899,12 +1045,16 @@ impl SocketMngPort {
 
         if self.mng().config.config_data().borrow().Socket.Accept
             && self.port.p_type() == PortType::Socket
-            && self.port.sa().can_accept()
+            && self.port.sa().map_or(false, |sa| sa.can_accept())
         {
-            let afd = self.port.accept().map_err(|_e| Error::Other {
+            afd = self.port.accept().map_err(|_e| Error::Other {
                 msg: "accept err".to_string(),
             })?;
 
+            // the connection may be used as the stdio of the service
+            if let Err(e) = fd_util::fd_nonblock(afd, false) {
+                log::warn!("Failed to set the connection blocking: {}", e);
+            }
             self.port.apply_sock_opt(afd)
         }
 
@@ -927,10 +1077,76 @@ impl SocketMngPort {
     }
 }
 
+/// return the instance name of the service spawned for the connection,
+/// like "{nr}-{local}-{remote}" for IP sockets and "{nr}-{pid}-{uid}" for unix sockets.
+fn connection_instance(fd: RawFd, nr: u32) -> String {
+    let local = socket::getsockname::<SockaddrStorage>(fd);
+    let remote = socket::getpeername::<SockaddrStorage>(fd);
+    if let (Ok(local), Ok(remote)) = (&local, &remote) {
+        if let (Some(l), Some(r)) = (local.as_sockaddr_in(), remote.as_sockaddr_in()) {
+            return format!("{nr}-{l}-{r}");
+        }
+        // no brackets around the IPv6 address, it's not allowed in the unit name
+        if let (Some(l), Some(r)) = (local.as_sockaddr_in6(), remote.as_sockaddr_in6()) {
+            return format!("{}-{}:{}-{}:{}", nr, l.ip(), l.port(), r.ip(), r.port());
+        }
+    }
+
+    match socket::getsockopt(fd, sockopt::PeerCredentials) {
+        Ok(cred) => format!("{}-{}-{}", nr, cred.pid(), cred.uid()),
+        Err(_) => format!("{nr}-unknown"),
+    }
+}
+
+/// return the source of the connection used by MaxConnectionsPerSource,
+/// the address of the peer for IP sockets and the uid of the peer for unix sockets.
+fn connection_source(fd: RawFd) -> String {
+    if let Ok(remote) = socket::getpeername::<SockaddrStorage>(fd) {
+        match remote.family() {
+            Some(AddressFamily::Inet) => {
+                if let Some(addr) = remote.as_sockaddr_in() {
+                    return std::net::Ipv4Addr::from(addr.ip()).to_string();
+                }
+            }
+            Some(AddressFamily::Inet6) => {
+                if let Some(addr) = remote.as_sockaddr_in6() {
+                    return addr.ip().to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match socket::getsockopt(fd, sockopt::PeerCredentials) {
+        Ok(cred) => format!("uid:{}", cred.uid()),
+        Err(_) => String::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::SocketState;
+    use super::{connection_instance, connection_source, SocketState};
+    use nix::sys::socket::{self, AddressFamily, SockFlag, SockType};
+    use nix::unistd::{self, Uid};
     use sysmaster::unit::UnitActiveState;
+
+    #[test]
+    fn test_connection_instance() {
+        let (fd, peer) = socket::socketpair(
+            AddressFamily::Unix,
+            SockType::Stream,
+            None,
+            SockFlag::SOCK_CLOEXEC,
+        )
+        .unwrap();
+
+        let expect = format!("3-{}-{}", unistd::getpid(), Uid::current());
+        assert_eq!(connection_instance(fd, 3), expect);
+        assert_eq!(connection_source(fd), format!("uid:{}", Uid::current()));
+
+        unistd::close(fd).unwrap();
+        unistd::close(peer).unwrap();
+    }
     #[test]
     fn test_socket_active_state() {
         assert_eq!(
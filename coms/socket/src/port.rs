@@ -15,20 +15,34 @@
 
 use crate::{
     comm::SocketUnitComm,
-    config::{SocketAddress, SocketConfig, SocketPortConf},
-    rentry::PortType,
+    config::{self, SocketAddress, SocketConfig, SocketPortConf},
+    rentry::{BindIPv6Only, PortType, Timestamping},
 };
-use basic::{fd_util, io_util, socket_util};
+use basic::{fd_util, io_util, socket_util, user_group_util};
 use nix::{
     errno::Errno,
+    fcntl::{self, OFlag},
+    libc, mqueue,
     poll::PollFlags,
-    sys::socket::{
-        self,
-        sockopt::{self},
-        AddressFamily, SockFlag,
+    sched::{self, CloneFlags},
+    sys::{
+        socket::{
+            self,
+            sockopt::{self},
+            AddressFamily, SockFlag,
+        },
+        stat::{self, Mode, SFlag},
     },
+    unistd::{self, Gid, Group, Uid, User},
+};
+use std::{
+    cell::RefCell,
+    ffi::CString,
+    fmt, fs,
+    os::unix::{fs::DirBuilderExt, prelude::RawFd},
+    path::{Path, PathBuf},
+    rc::Rc,
 };
-use std::{cell::RefCell, fmt, os::unix::prelude::RawFd, rc::Rc};
 use sysmaster::error::*;
 
 pub(super) const SOCKET_INVALID_FD: RawFd = -1;
@@ -78,23 +92,60 @@ impl SocketPort {
         let fd = match self.p_conf.p_type() {
             PortType::Socket => {
                 let flag = SockFlag::SOCK_CLOEXEC | SockFlag::SOCK_NONBLOCK;
+                let sa = self.p_conf.sa().unwrap();
+                let path = sa.path();
+                if let Some(p) = &path {
+                    self.mkdir_parents(p)?;
+                }
 
-                self.p_conf
-                    .sa()
-                    .socket_listen(flag, 128)
-                    .context(NixSnafu)?
+                let (backlog, netns) = {
+                    let data = self.config.config_data();
+                    let socket = &data.borrow().Socket;
+                    (socket.Backlog as usize, socket.NetworkNamespacePath.clone())
+                };
+                let context = self.selinux_label();
+
+                // the socket belongs to the network namespace where it is created
+                let _netns = match netns {
+                    Some(path) => Some(NetnsGuard::enter(&path)?),
+                    None => None,
+                };
+                let _label = CreateLabel::set(SELINUX_SOCKCREATE, context.as_deref())?;
+                // the socket file is created by bind with the mode of SocketMode
+                let old_mask = stat::umask(Mode::from_bits_truncate(!self.socket_mode() & 0o777));
+                let ret = sa.socket_listen(flag, backlog, |fd| {
+                    self.apply_smack_ip_label(fd);
+                    self.apply_bind_opt(fd)
+                });
+                stat::umask(old_mask);
+                let fd = ret.context(NixSnafu)?;
+
+                if let Some(p) = &path {
+                    if let Err(e) = self.chown_path(p) {
+                        fd_util::close(fd);
+                        return Err(e);
+                    }
+                }
+                fd
+            }
+            PortType::Fifo => self.open_fifo()?,
+            PortType::Special => self.open_special()?,
+            PortType::MessageQueue => self.open_mqueue()?,
+            PortType::USBFunction => self.open_usb_function()?,
+            PortType::Invalid => {
+                return Err(format!("invalid port type of {}", self.listen()).into());
             }
-            PortType::Fifo => todo!(),
-            PortType::Invalid => todo!(),
         };
 
+        self.set_fd(fd);
         if update {
             if let Err(e) = self.comm.reli().fd_cloexec(fd, false) {
                 self.close(update);
                 return Err(e);
             }
         }
-        self.set_fd(fd);
+
+        self.create_symlinks();
 
         Ok(())
     }
@@ -115,12 +166,28 @@ impl SocketPort {
 
         fd_util::close(fd);
 
+        let remove = self.config.config_data().borrow().Socket.RemoveOnStop;
+        if remove {
+            self.remove_symlinks();
+        }
         match self.p_conf.p_type() {
-            PortType::Socket => {
-                self.p_conf.sa().unlink();
+            PortType::Socket if remove => {
+                self.p_conf.sa().unwrap().unlink();
+            }
+            PortType::Fifo if remove => {
+                if let Err(e) = unistd::unlink(self.listen()) {
+                    log::warn!("Unable to unlink {}, error: {}", self.listen(), e);
+                }
+            }
+            PortType::MessageQueue if remove => {
+                let ret = CString::new(self.listen())
+                    .map_err(|_| Errno::EINVAL)
+                    .and_then(|name| mqueue::mq_unlink(&name));
+                if let Err(e) = ret {
+                    log::warn!("Unable to unlink {}, error: {}", self.listen(), e);
+                }
             }
-            PortType::Fifo => todo!(),
-            PortType::Invalid => todo!(),
+            _ => {}
         }
 
         self.set_fd(SOCKET_INVALID_FD);
@@ -169,8 +236,13 @@ impl SocketPort {
     }
 
     pub(super) fn apply_sock_opt(&self, fd: RawFd) {
+        let family = match self.family() {
+            Some(family) => family,
+            None => return,
+        };
+
         if let Some(v) = self.config.config_data().borrow().Socket.PassPacketInfo {
-            if let Err(e) = socket_util::set_pkginfo(fd, self.family(), v) {
+            if let Err(e) = socket_util::set_pkginfo(fd, family, v) {
                 log::warn!("set socket pkginfo errno: {}", e);
             }
         }
@@ -228,6 +300,110 @@ impl SocketPort {
                 log::warn!("set broadcast state errno: {}", e);
             }
         }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.PassSecurity {
+            if let Err(e) = socket_util::set_pass_sec(fd, v) {
+                log::warn!("set socket pass security errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.IPTOS {
+            if let Err(e) = socket_util::set_ip_tos(fd, family, v) {
+                log::warn!("set ip tos errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.IPTTL {
+            if let Err(e) = socket_util::set_ip_ttl(fd, family, v) {
+                log::warn!("set ip ttl errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.Mark {
+            if let Err(e) = socket_util::set_mark(fd, v) {
+                log::warn!("set socket mark errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.Priority {
+            if let Err(e) = socket_util::set_priority(fd, v) {
+                log::warn!("set socket priority errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.DeferAcceptSec {
+            if let Err(e) = socket_util::set_tcp_defer_accept(fd, v) {
+                log::warn!("set tcp defer accept errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.NoDelay {
+            if let Err(e) = socket_util::set_tcp_nodelay(fd, v) {
+                log::warn!("set tcp nodelay errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.Timestamping {
+            let ret = match v {
+                Timestamping::Off => socket_util::set_receive_timestamp(fd, false)
+                    .and_then(|_| socket_util::set_receive_timestampns(fd, false)),
+                Timestamping::Microseconds => socket_util::set_receive_timestamp(fd, true),
+                Timestamping::Nanoseconds => socket_util::set_receive_timestampns(fd, true),
+            };
+            if let Err(e) = ret {
+                log::warn!("set socket timestamping errno: {}", e);
+            }
+        }
+
+        if let Some(v) = &self.config.config_data().borrow().Socket.TCPCongestion {
+            if let Err(e) = socket_util::set_tcp_congestion(fd, v) {
+                log::warn!("set tcp congestion errno: {}", e);
+            }
+        }
+    }
+
+    /// apply the options which only take effect before binding
+    fn apply_bind_opt(&self, fd: RawFd) {
+        let family = match self.family() {
+            Some(family) => family,
+            None => return,
+        };
+
+        if let Some(v) = self.config.config_data().borrow().Socket.ReusePort {
+            if let Err(e) = socket_util::set_reuse_port(fd, v) {
+                log::warn!("set socket reuse port errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.FreeBind {
+            if let Err(e) = socket_util::set_free_bind(fd, family, v) {
+                log::warn!("set socket free bind errno: {}", e);
+            }
+        }
+
+        if let Some(v) = self.config.config_data().borrow().Socket.Transparent {
+            if let Err(e) = socket_util::set_transparent(fd, family, v) {
+                log::warn!("set socket transparent errno: {}", e);
+            }
+        }
+
+        if family == AddressFamily::Inet6 {
+            let v = self.config.config_data().borrow().Socket.BindIPv6Only;
+            let ret = match v {
+                Some(BindIPv6Only::Both) => socket_util::set_bind_ipv6_only(fd, false),
+                Some(BindIPv6Only::Ipv6Only) => socket_util::set_bind_ipv6_only(fd, true),
+                _ => Ok(()),
+            };
+            if let Err(e) = ret {
+                log::warn!("set socket ipv6 only errno: {}", e);
+            }
+        }
+
+        if let Some(v) = &self.config.config_data().borrow().Socket.BindToDevice {
+            if let Err(e) = socket_util::set_bind_to_device(fd, v) {
+                log::warn!("set socket bind to device errno: {}", e);
+            }
+        }
     }
 
     pub(super) fn fd(&self) -> RawFd {
@@ -238,7 +414,7 @@ impl SocketPort {
         self.p_conf.p_type()
     }
 
-    pub(super) fn sa(&self) -> &SocketAddress {
+    pub(super) fn sa(&self) -> Option<&SocketAddress> {
         self.p_conf.sa()
     }
 
@@ -246,19 +422,426 @@ impl SocketPort {
         self.p_conf.listen()
     }
 
-    fn family(&self) -> AddressFamily {
-        self.p_conf.sa().family()
+    fn family(&self) -> Option<AddressFamily> {
+        self.p_conf.sa().map(|sa| sa.family())
+    }
+
+    fn open_fifo(&self) -> Result<RawFd> {
+        let path = Path::new(self.listen());
+        self.mkdir_parents(path)?;
+
+        let mode = Mode::from_bits_truncate(self.socket_mode());
+        let context = self.selinux_label();
+        let label = CreateLabel::set(SELINUX_FSCREATE, context.as_deref())?;
+        let old_mask = stat::umask(Mode::empty());
+        let ret = unistd::mkfifo(path, mode);
+        stat::umask(old_mask);
+        drop(label);
+        match ret {
+            Ok(_) | Err(Errno::EEXIST) => {}
+            Err(e) => return Err(e).context(NixSnafu),
+        }
+
+        let flags = OFlag::O_RDWR | OFlag::O_NOFOLLOW | OFlag::O_NOCTTY;
+        let fd = open_nonblock(path, flags)?;
+        let ret = check_file_type(fd, &[SFlag::S_IFIFO])
+            .and_then(|_| self.chown_fd(fd))
+            .and_then(|_| self.apply_smack_label(fd));
+        if let Err(e) = ret {
+            fd_util::close(fd);
+            return Err(e);
+        }
+
+        Ok(fd)
+    }
+
+    fn open_special(&self) -> Result<RawFd> {
+        let path = Path::new(self.listen());
+        let flags = OFlag::O_RDONLY | OFlag::O_NOFOLLOW | OFlag::O_NOCTTY;
+        let fd = open_nonblock(path, flags)?;
+        let ret = check_file_type(fd, &[SFlag::S_IFCHR, SFlag::S_IFREG])
+            .and_then(|_| self.apply_smack_label(fd));
+        if let Err(e) = ret {
+            fd_util::close(fd);
+            return Err(e);
+        }
+
+        Ok(fd)
+    }
+
+    fn open_mqueue(&self) -> Result<RawFd> {
+        let mode = self.socket_mode();
+        let (maxmsg, msgsize) = {
+            let data = self.config.config_data();
+            let socket = &data.borrow().Socket;
+            (
+                socket.MessageQueueMaxMessages,
+                socket.MessageQueueMessageSize,
+            )
+        };
+
+        let name = CString::new(self.listen()).map_err(|e| Error::Other { msg: e.to_string() })?;
+        let flags = libc::O_RDWR | libc::O_CREAT | libc::O_CLOEXEC | libc::O_NONBLOCK;
+        // the attributes are only applied if both of them are configured
+        let mut attr: libc::mq_attr = unsafe { std::mem::zeroed() };
+        attr.mq_maxmsg = maxmsg as _;
+        attr.mq_msgsize = msgsize as _;
+        let attr_ptr = if maxmsg > 0 && msgsize > 0 {
+            &attr as *const libc::mq_attr
+        } else {
+            std::ptr::null()
+        };
+
+        let old_mask = stat::umask(Mode::empty());
+        let ret = unsafe { libc::mq_open(name.as_ptr(), flags, mode as libc::mode_t, attr_ptr) };
+        stat::umask(old_mask);
+        let fd = Errno::result(ret).context(NixSnafu)?;
+
+        if let Err(e) = self.chown_fd(fd) {
+            fd_util::close(fd);
+            return Err(e);
+        }
+
+        Ok(fd)
+    }
+
+    fn open_usb_function(&self) -> Result<RawFd> {
+        let (descriptors, strings) = {
+            let data = self.config.config_data();
+            let socket = &data.borrow().Socket;
+            (
+                socket.USBFunctionDescriptors.clone(),
+                socket.USBFunctionStrings.clone(),
+            )
+        };
+
+        // the FunctionFS is mounted on the path, and ep0 is the control endpoint
+        let ep0 = Path::new(self.listen()).join("ep0");
+        let fd = open_nonblock(&ep0, OFlag::O_RDWR)?;
+        for file in [descriptors, strings] {
+            if let Err(e) = write_file_to_fd(Path::new(&file), fd) {
+                log::error!("Failed to write {} to {:?}: {}", file, ep0, e);
+                fd_util::close(fd);
+                return Err(e);
+            }
+        }
+
+        Ok(fd)
+    }
+
+    /// the SELinux context of the sockets and FIFOs, configured by SELinuxContext
+    fn selinux_label(&self) -> Option<String> {
+        self.config
+            .config_data()
+            .borrow()
+            .Socket
+            .SELinuxContext
+            .clone()
+    }
+
+    /// label the FIFO or special file with SmackLabel
+    fn apply_smack_label(&self, fd: RawFd) -> Result<()> {
+        let label = self.config.config_data().borrow().Socket.SmackLabel.clone();
+        match label {
+            Some(label) if smack_enabled() => fsetxattr(fd, SMACK_XATTR, &label),
+            _ => Ok(()),
+        }
+    }
+
+    /// label the packets received and sent by the socket with SmackLabelIPIn and SmackLabelIPOut
+    fn apply_smack_ip_label(&self, fd: RawFd) {
+        if !smack_enabled() {
+            return;
+        }
+
+        let (label_in, label_out) = {
+            let data = self.config.config_data();
+            let socket = &data.borrow().Socket;
+            (
+                socket.SmackLabelIPIn.clone(),
+                socket.SmackLabelIPOut.clone(),
+            )
+        };
+
+        for (name, label) in [(SMACK_XATTR_IPIN, label_in), (SMACK_XATTR_IPOUT, label_out)] {
+            if let Some(label) = label {
+                if let Err(e) = fsetxattr(fd, name, &label) {
+                    log::warn!("set socket {} errno: {}", name, e);
+                }
+            }
+        }
+    }
+
+    fn mkdir_parents(&self, path: &Path) -> Result<()> {
+        let parent = match path.parent() {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        // validated when loading
+        let mode = config::parse_mode(&self.config.config_data().borrow().Socket.DirectoryMode)
+            .unwrap_or(0o755);
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(mode)
+            .create(parent)
+            .context(IoSnafu)
+    }
+
+    /// the access mode of the socket file, FIFO and message queue, validated when loading
+    fn socket_mode(&self) -> u32 {
+        config::parse_mode(&self.config.config_data().borrow().Socket.SocketMode).unwrap_or(0o666)
+    }
+
+    fn owner(&self) -> Result<(Option<Uid>, Option<Gid>)> {
+        let (user, group) = {
+            let data = self.config.config_data();
+            let socket = &data.borrow().Socket;
+            (socket.SocketUser.clone(), socket.SocketGroup.clone())
+        };
+
+        let uid = if user.is_empty() {
+            None
+        } else {
+            Some(parse_user(&user)?)
+        };
+        let gid = if group.is_empty() {
+            None
+        } else {
+            Some(parse_group(&group)?)
+        };
+
+        Ok((uid, gid))
+    }
+
+    fn chown_fd(&self, fd: RawFd) -> Result<()> {
+        match self.owner()? {
+            (None, None) => Ok(()),
+            (uid, gid) => unistd::fchown(fd, uid, gid).context(NixSnafu),
+        }
+    }
+
+    fn chown_path(&self, path: &Path) -> Result<()> {
+        match self.owner()? {
+            (None, None) => Ok(()),
+            (uid, gid) => {
+                unistd::fchownat(None, path, uid, gid, unistd::FchownatFlags::NoFollowSymlink)
+                    .context(NixSnafu)
+            }
+        }
+    }
+
+    /// the path of the unix socket or FIFO, which Symlinks point to
+    fn file_path(&self) -> Option<PathBuf> {
+        match self.p_conf.p_type() {
+            PortType::Socket => self.p_conf.sa().and_then(|sa| sa.path()),
+            PortType::Fifo => Some(PathBuf::from(self.listen())),
+            _ => None,
+        }
+    }
+
+    fn create_symlinks(&self) {
+        let target = match self.file_path() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let symlinks = self.config.config_data().borrow().Socket.Symlinks.clone();
+        for link in symlinks.unwrap_or_default() {
+            let link = Path::new(&link);
+            if let Err(e) = self.mkdir_parents(link) {
+                log::warn!("Failed to create the parent directory of {:?}: {}", link, e);
+                continue;
+            }
+
+            // replace the symlink left by the previous run
+            if let Ok(true) = fs::symlink_metadata(link).map(|m| m.file_type().is_symlink()) {
+                let _ = fs::remove_file(link);
+            }
+            if let Err(e) = std::os::unix::fs::symlink(&target, link) {
+                log::warn!("Failed to create symlink {:?} to {:?}: {}", link, target, e);
+            }
+        }
+    }
+
+    fn remove_symlinks(&self) {
+        let target = match self.file_path() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let symlinks = self.config.config_data().borrow().Socket.Symlinks.clone();
+        for link in symlinks.unwrap_or_default() {
+            // only remove the symlinks pointing to the socket
+            if fs::read_link(&link).map_or(false, |p| p == target) {
+                if let Err(e) = fs::remove_file(&link) {
+                    log::warn!("Failed to remove symlink {}: {}", link, e);
+                }
+            }
+        }
     }
 }
 
 impl fmt::Display for SocketPort {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "port type: {:?}, socket address: {}",
-            self.p_conf.p_type(),
-            self.p_conf.sa()
+        match self.p_conf.sa() {
+            Some(sa) => write!(
+                f,
+                "port type: {:?}, socket address: {}",
+                self.p_conf.p_type(),
+                sa
+            ),
+            None => write!(
+                f,
+                "port type: {:?}, path: {}",
+                self.p_conf.p_type(),
+                self.listen()
+            ),
+        }
+    }
+}
+
+const SMACK_XATTR: &str = "security.SMACK64";
+const SMACK_XATTR_IPIN: &str = "security.SMACK64IPIN";
+const SMACK_XATTR_IPOUT: &str = "security.SMACK64IPOUT";
+const SELINUX_SOCKCREATE: &str = "/proc/thread-self/attr/sockcreate";
+const SELINUX_FSCREATE: &str = "/proc/thread-self/attr/fscreate";
+
+fn smack_enabled() -> bool {
+    Path::new("/sys/fs/smackfs").is_dir()
+}
+
+fn selinux_enabled() -> bool {
+    Path::new("/sys/fs/selinux/enforce").exists()
+}
+
+fn fsetxattr(fd: RawFd, name: &str, value: &str) -> Result<()> {
+    let name = CString::new(name).map_err(|e| Error::Other { msg: e.to_string() })?;
+    let ret = unsafe {
+        libc::fsetxattr(
+            fd,
+            name.as_ptr(),
+            value.as_ptr() as *const libc::c_void,
+            value.len(),
+            0,
         )
+    };
+    Errno::result(ret).map(drop).context(NixSnafu)
+}
+
+/// switch to the network namespace, and switch back when dropped
+struct NetnsGuard {
+    orig: RawFd,
+}
+
+impl NetnsGuard {
+    fn enter(path: &str) -> Result<NetnsGuard> {
+        let flags = OFlag::O_RDONLY | OFlag::O_CLOEXEC;
+        let orig = fcntl::open("/proc/self/ns/net", flags, Mode::empty()).context(NixSnafu)?;
+        let target = match fcntl::open(path, flags, Mode::empty()) {
+            Ok(fd) => fd,
+            Err(e) => {
+                fd_util::close(orig);
+                return Err(e).context(NixSnafu);
+            }
+        };
+
+        let ret = sched::setns(target, CloneFlags::CLONE_NEWNET);
+        fd_util::close(target);
+        if let Err(e) = ret {
+            fd_util::close(orig);
+            log::error!("Failed to enter the network namespace {}: {}", path, e);
+            return Err(e).context(NixSnafu);
+        }
+
+        Ok(NetnsGuard { orig })
+    }
+}
+
+impl Drop for NetnsGuard {
+    fn drop(&mut self) {
+        // staying in the namespace would create all the later sockets of
+        // sysmaster in it, which can't be recovered, so give up
+        if let Err(e) = sched::setns(self.orig, CloneFlags::CLONE_NEWNET) {
+            log::error!("Failed to restore the network namespace: {}, aborting", e);
+            std::process::abort();
+        }
+        fd_util::close(self.orig);
+    }
+}
+
+/// set the SELinux context of the objects created by the thread, and reset it when dropped
+struct CreateLabel {
+    attr: &'static str,
+}
+
+impl CreateLabel {
+    fn set(attr: &'static str, context: Option<&str>) -> Result<Option<CreateLabel>> {
+        match context {
+            Some(c) if selinux_enabled() => CreateLabel::write(attr, c).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn write(attr: &'static str, context: &str) -> Result<CreateLabel> {
+        fs::write(attr, context).context(IoSnafu)?;
+        Ok(CreateLabel { attr })
+    }
+}
+
+impl Drop for CreateLabel {
+    fn drop(&mut self) {
+        // an empty write resets the context to the default one
+        if let Err(e) = fs::write(self.attr, "") {
+            log::error!("Failed to reset {}: {}", self.attr, e);
+        }
+    }
+}
+
+fn open_nonblock(path: &Path, flags: OFlag) -> Result<RawFd> {
+    let flags = flags | OFlag::O_CLOEXEC | OFlag::O_NONBLOCK;
+    fcntl::open(path, flags, Mode::empty()).context(NixSnafu)
+}
+
+fn check_file_type(fd: RawFd, types: &[SFlag]) -> Result<()> {
+    let st = stat::fstat(fd).context(NixSnafu)?;
+    let file_type = SFlag::from_bits_truncate(st.st_mode) & SFlag::S_IFMT;
+    if !types.contains(&file_type) {
+        return Err(format!("unexpected file type: {:o}", file_type.bits()).into());
+    }
+
+    Ok(())
+}
+
+fn write_file_to_fd(path: &Path, fd: RawFd) -> Result<()> {
+    let content = fs::read(path).context(IoSnafu)?;
+    let n = unistd::write(fd, &content).context(NixSnafu)?;
+    if n != content.len() {
+        return Err(format!("short write of {path:?}").into());
+    }
+
+    Ok(())
+}
+
+fn parse_user(user: &str) -> Result<Uid> {
+    if let Ok(u) = user_group_util::parse_uid(&user.to_string()) {
+        return Ok(u.uid);
+    }
+
+    match User::from_name(user).context(NixSnafu)? {
+        Some(u) => Ok(u.uid),
+        None => Err(format!("user {user} is not found").into()),
+    }
+}
+
+fn parse_group(group: &str) -> Result<Gid> {
+    if let Ok(g) = user_group_util::parse_gid(&group.to_string()) {
+        return Ok(g.gid);
+    }
+
+    match Group::from_name(group).context(NixSnafu)? {
+        Some(g) => Ok(g.gid),
+        None => Err(format!("group {group} is not found").into()),
     }
 }
 
@@ -273,6 +856,8 @@ mod tests {
     use nix::sys::socket::{
         AddressFamily, NetlinkAddr, SockProtocol, SockType, SockaddrIn, UnixAddr,
     };
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::io::AsRawFd;
     use std::path::PathBuf;
     use std::{
         net::{Ipv4Addr, SocketAddrV4},
@@ -300,13 +885,51 @@ mod tests {
         assert!(ret.is_ok());
 
         assert_ne!(port.fd(), SOCKET_INVALID_FD);
-        assert_eq!(port.family(), AddressFamily::Inet);
+        assert_eq!(port.family(), Some(AddressFamily::Inet));
 
         port.flush_accept();
         port.flush_fd();
         port.close(false);
     }
 
+    #[test]
+    fn test_socket_unix_lifecycle() {
+        let mut file_path = get_project_root().unwrap();
+        file_path.push("tests/test_units/test_unix.socket.toml");
+        let comm = Rc::new(SocketUnitComm::new());
+        let config = Rc::new(SocketConfig::new(&comm));
+        assert!(config.load(vec![file_path], false).is_ok());
+
+        let dir = PathBuf::from("/tmp/sysmaster_test_unix");
+        let unix_path = dir.join("test.sock");
+        let link_path = dir.join("link.sock");
+        let _ = fs::remove_dir_all(&dir);
+
+        // the socket file left by the previous run
+        fs::create_dir_all(&dir).unwrap();
+        drop(std::os::unix::net::UnixListener::bind(&unix_path).unwrap());
+        assert!(unix_path.exists());
+
+        let unix_addr = UnixAddr::new(&unix_path).unwrap();
+        let socket_addr = SocketAddress::new(Box::new(unix_addr), SockType::Stream, None);
+        let p_conf = Rc::new(SocketPortConf::new(
+            PortType::Socket,
+            socket_addr,
+            unix_path.to_str().unwrap(),
+        ));
+        let port = Rc::new(SocketPort::new(&comm, &config, &p_conf));
+        assert!(port.open_port(false).is_ok());
+
+        let meta = fs::symlink_metadata(&unix_path).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read_link(&link_path).unwrap(), unix_path);
+
+        port.close(false);
+        assert!(!unix_path.exists());
+        assert!(fs::symlink_metadata(&link_path).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn test_socket_unix_addr() {
         let comm = Rc::new(SocketUnitComm::new());
@@ -331,7 +954,7 @@ mod tests {
         assert!(ret.is_ok());
 
         assert_ne!(port.fd(), SOCKET_INVALID_FD);
-        assert_eq!(port.family(), AddressFamily::Unix);
+        assert_eq!(port.family(), Some(AddressFamily::Unix));
 
         port.flush_accept();
         port.flush_fd();
@@ -368,7 +991,7 @@ mod tests {
         assert!(ret.is_ok());
 
         assert_ne!(port.fd(), SOCKET_INVALID_FD);
-        assert_eq!(port.family(), AddressFamily::Netlink);
+        assert_eq!(port.family(), Some(AddressFamily::Netlink));
 
         port.flush_accept();
         port.flush_fd();
@@ -403,7 +1026,7 @@ mod tests {
         let ret = port.open_port(false);
         assert!(ret.is_ok());
         assert_ne!(port.fd(), SOCKET_INVALID_FD);
-        assert_eq!(port.family(), AddressFamily::Inet);
+        assert_eq!(port.family(), Some(AddressFamily::Inet));
 
         port.apply_sock_opt(port.fd());
 
@@ -469,4 +1092,140 @@ mod tests {
         port.flush_fd();
         port.close(false);
     }
+
+    #[test]
+    fn test_apply_bind_opt() {
+        let mut file_path = get_project_root().unwrap();
+        file_path.push("tests/test_units/test_opts.socket.toml");
+        let comm = Rc::new(SocketUnitComm::new());
+        let config = Rc::new(SocketConfig::new(&comm));
+        assert!(config.load(vec![file_path], false).is_ok());
+
+        let sock_addr = SockaddrIn::from(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 31458));
+        let socket_addr = SocketAddress::new(Box::new(sock_addr), SockType::Stream, None);
+        let p_conf = Rc::new(SocketPortConf::new(
+            PortType::Socket,
+            socket_addr,
+            "127.0.0.1:31458",
+        ));
+        let port = Rc::new(SocketPort::new(&comm, &config, &p_conf));
+        assert!(port.open_port(false).is_ok());
+        port.apply_sock_opt(port.fd());
+        let fd = port.fd();
+
+        assert!(socket::getsockopt(fd, sockopt::ReusePort).unwrap());
+        assert!(socket::getsockopt(fd, sockopt::IpFreebind).unwrap());
+        assert!(socket::getsockopt(fd, sockopt::TcpNoDelay).unwrap());
+        assert!(socket::getsockopt(fd, sockopt::ReceiveTimestamp).unwrap());
+        assert_eq!(socket::getsockopt(fd, sockopt::Ipv4Ttl).unwrap(), 42);
+        assert_eq!(
+            socket_util::getsockopt_int(fd, libc::IPPROTO_IP, libc::IP_TOS).unwrap(),
+            16
+        );
+        assert_eq!(
+            socket_util::getsockopt_int(fd, libc::SOL_SOCKET, libc::SO_PRIORITY).unwrap(),
+            3
+        );
+        // the kernel converts the seconds to retransmits and back, so it is rounded up
+        assert!(
+            socket_util::getsockopt_int(fd, libc::IPPROTO_TCP, libc::TCP_DEFER_ACCEPT).unwrap()
+                >= 5
+        );
+        match socket::getsockopt(fd, sockopt::TcpCongestion) {
+            Ok(v) => assert!(v.to_string_lossy().starts_with("reno")),
+            Err(e) => println!("Error get TCPCongestion: {:?}", e),
+        }
+
+        // the socket is listening and reachable on the loopback
+        let cfd = socket::socket(
+            AddressFamily::Inet,
+            SockType::Stream,
+            SockFlag::SOCK_CLOEXEC,
+            None,
+        )
+        .unwrap();
+        assert!(socket::connect(cfd, &sock_addr).is_ok());
+        fd_util::close(cfd);
+
+        port.close(false);
+    }
+
+    fn fgetxattr(fd: RawFd, name: &str) -> nix::Result<String> {
+        let name = CString::new(name).unwrap();
+        let mut buf = [0u8; 256];
+        let ret = unsafe {
+            libc::fgetxattr(
+                fd,
+                name.as_ptr(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        };
+        let len = Errno::result(ret)?;
+        Ok(String::from_utf8_lossy(&buf[..len as usize]).to_string())
+    }
+
+    #[test]
+    fn test_create_label() {
+        // nothing is written without a context
+        assert!(CreateLabel::set(SELINUX_FSCREATE, None).unwrap().is_none());
+
+        const ATTR: &str = "/tmp/sysmaster_test_fscreate";
+        fs::write(ATTR, "").unwrap();
+        let label = CreateLabel::write(ATTR, "system_u:object_r:tmp_t:s0").unwrap();
+        assert_eq!(
+            fs::read_to_string(ATTR).unwrap(),
+            "system_u:object_r:tmp_t:s0"
+        );
+        drop(label);
+        assert!(fs::read_to_string(ATTR).unwrap().is_empty());
+        let _ = fs::remove_file(ATTR);
+    }
+
+    #[test]
+    fn test_selinux_label() {
+        let comm = Rc::new(SocketUnitComm::new());
+        let config = Rc::new(SocketConfig::new(&comm));
+        let p_conf = Rc::new(SocketPortConf::new_file(PortType::Fifo, "/tmp/test.fifo"));
+        let port = SocketPort::new(&comm, &config, &p_conf);
+        assert!(port.selinux_label().is_none());
+
+        config.config_data().borrow_mut().Socket.SELinuxContext =
+            Some("system_u:object_r:tmp_t:s0".to_string());
+        assert_eq!(port.selinux_label().unwrap(), "system_u:object_r:tmp_t:s0");
+    }
+
+    #[test]
+    fn test_smack_label() {
+        let comm = Rc::new(SocketUnitComm::new());
+        let config = Rc::new(SocketConfig::new(&comm));
+        config.config_data().borrow_mut().Socket.SmackLabel = Some("test".to_string());
+
+        let fifo = PathBuf::from("/tmp/sysmaster_test_smack.fifo");
+        let _ = fs::remove_file(&fifo);
+        let p_conf = Rc::new(SocketPortConf::new_file(
+            PortType::Fifo,
+            fifo.to_str().unwrap(),
+        ));
+        let port = SocketPort::new(&comm, &config, &p_conf);
+        assert!(port.open_port(false).is_ok());
+        match fgetxattr(port.fd(), SMACK_XATTR) {
+            Ok(label) => assert_eq!(label, "test"),
+            Err(e) => assert!(!smack_enabled(), "get {}: {}", SMACK_XATTR, e),
+        }
+        port.close(false);
+        let _ = fs::remove_file(&fifo);
+
+        // the labels are applied by the same helper, which works with the user namespace too
+        let file = PathBuf::from("/tmp/sysmaster_test_smack.file");
+        let f = fs::File::create(&file).unwrap();
+        match fsetxattr(f.as_raw_fd(), "user.sysmaster_test", "test") {
+            Ok(_) => assert_eq!(
+                fgetxattr(f.as_raw_fd(), "user.sysmaster_test").unwrap(),
+                "test"
+            ),
+            Err(e) => println!("user xattr is not supported: {:?}", e),
+        }
+        let _ = fs::remove_file(&file);
+    }
 }
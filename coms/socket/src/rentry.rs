@@ -13,12 +13,14 @@
 #![allow(non_snake_case)]
 use confique::Config;
 use macros::EnumDisplay;
+use nix::sys::resource::Resource;
 use nix::unistd::Pid;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
 use std::collections::VecDeque;
+use std::fmt;
 use std::os::unix::prelude::RawFd;
 use std::rc::Rc;
-use sysmaster::exec::ExecCommand;
+use sysmaster::exec::{ExecCommand, ExecInput, ExecOutput, Rlimit};
 use sysmaster::rel::{ReDb, ReDbRoTxn, ReDbRwTxn, ReDbTable, Reliability};
 use sysmaster::serialize::DeserializeWith;
 use sysmaster::unit::KillMode;
@@ -51,10 +53,30 @@ pub(super) struct SectionSocket {
     pub ListenNetlink: Option<Vec<String>>,
     #[config(deserialize_with = Vec::<String>::deserialize_with)]
     pub ListenSequentialPacket: Option<Vec<String>>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    pub ListenFIFO: Option<Vec<String>>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    pub ListenSpecial: Option<Vec<String>>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    pub ListenMessageQueue: Option<Vec<String>>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    pub ListenUSBFunction: Option<Vec<String>>,
     pub PassPacketInfo: Option<bool>,
     #[config(default = false)]
     pub Accept: bool,
+    #[config(default = 64)]
+    pub MaxConnections: u32,
+    #[config(default = 0)]
+    pub MaxConnectionsPerSource: u32,
     pub Service: Option<String>,
+    pub FileDescriptorName: Option<String>,
+    pub TriggerLimitIntervalSec: Option<u64>,
+    pub TriggerLimitBurst: Option<u32>,
+    pub NetworkNamespacePath: Option<String>,
+    pub SmackLabel: Option<String>,
+    pub SmackLabelIPIn: Option<String>,
+    pub SmackLabelIPOut: Option<String>,
+    pub SELinuxContext: Option<String>,
     pub ReceiveBuffer: Option<u64>,
     pub SendBuffer: Option<u64>,
     pub PassCredentials: Option<bool>,
@@ -63,15 +85,176 @@ pub(super) struct SectionSocket {
     pub KeepAliveIntervalSec: Option<u32>,
     pub KeepAliveProbes: Option<u32>,
     pub Broadcast: Option<bool>,
+    pub ReusePort: Option<bool>,
+    pub FreeBind: Option<bool>,
+    pub Transparent: Option<bool>,
+    pub BindIPv6Only: Option<BindIPv6Only>,
+    #[config(default = 128)]
+    pub Backlog: u32,
+    pub BindToDevice: Option<String>,
+    pub IPTOS: Option<i32>,
+    pub IPTTL: Option<i32>,
+    pub Mark: Option<u32>,
+    pub Priority: Option<i32>,
+    pub DeferAcceptSec: Option<u32>,
+    pub NoDelay: Option<bool>,
+    pub Timestamping: Option<Timestamping>,
+    pub TCPCongestion: Option<String>,
     #[config(deserialize_with = Vec::<String>::deserialize_with)]
     pub Symlinks: Option<Vec<String>>,
     pub PassSecurity: Option<bool>,
-    pub SocketMode: Option<u32>,
+    #[config(deserialize_with = deserialize_mode)]
+    #[config(default = "0666")]
+    pub SocketMode: String,
+    #[config(deserialize_with = deserialize_mode)]
+    #[config(default = "0755")]
+    pub DirectoryMode: String,
+    #[config(default = "")]
+    pub SocketUser: String,
+    #[config(default = "")]
+    pub SocketGroup: String,
+    #[config(default = false)]
+    pub RemoveOnStop: bool,
+    #[config(default = 0)]
+    pub MessageQueueMaxMessages: i64,
+    #[config(default = 0)]
+    pub MessageQueueMessageSize: i64,
+    #[config(default = "")]
+    pub USBFunctionDescriptors: String,
+    #[config(default = "")]
+    pub USBFunctionStrings: String,
     #[config(deserialize_with = KillMode::deserialize_with)]
     #[config(default = "none")]
     pub KillMode: KillMode,
     #[config(default = "SIGTERM")]
     pub KillSignal: String,
+    #[config(deserialize_with = ExecInput::deserialize_with)]
+    #[config(default = "null")]
+    pub StandardInput: ExecInput,
+    #[config(deserialize_with = ExecOutput::deserialize_with)]
+    #[config(default = "inherit")]
+    pub StandardOutput: ExecOutput,
+    #[config(deserialize_with = ExecOutput::deserialize_with)]
+    #[config(default = "inherit")]
+    pub StandardError: ExecOutput,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitCPU: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitFSIZE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitDATA: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitSTACK: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitCORE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitRSS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitNOFILE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitAS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitNPROC: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitMEMLOCK: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitLOCKS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitSIGPENDING: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitMSGQUEUE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_nice)]
+    pub LimitNICE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitRTPRIO: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub LimitRTTIME: Option<Rlimit>,
+}
+
+impl SectionSocket {
+    /// the resource limits configured by Limit*=
+    pub(super) fn rlimits(&self) -> Vec<(Resource, Rlimit)> {
+        [
+            (Resource::RLIMIT_CPU, self.LimitCPU),
+            (Resource::RLIMIT_FSIZE, self.LimitFSIZE),
+            (Resource::RLIMIT_DATA, self.LimitDATA),
+            (Resource::RLIMIT_STACK, self.LimitSTACK),
+            (Resource::RLIMIT_CORE, self.LimitCORE),
+            (Resource::RLIMIT_RSS, self.LimitRSS),
+            (Resource::RLIMIT_NOFILE, self.LimitNOFILE),
+            (Resource::RLIMIT_AS, self.LimitAS),
+            (Resource::RLIMIT_NPROC, self.LimitNPROC),
+            (Resource::RLIMIT_MEMLOCK, self.LimitMEMLOCK),
+            (Resource::RLIMIT_LOCKS, self.LimitLOCKS),
+            (Resource::RLIMIT_SIGPENDING, self.LimitSIGPENDING),
+            (Resource::RLIMIT_MSGQUEUE, self.LimitMSGQUEUE),
+            (Resource::RLIMIT_NICE, self.LimitNICE),
+            (Resource::RLIMIT_RTPRIO, self.LimitRTPRIO),
+            (Resource::RLIMIT_RTTIME, self.LimitRTTIME),
+        ]
+        .into_iter()
+        .filter_map(|(r, l)| l.map(|l| (r, l)))
+        .collect()
+    }
+}
+
+/// the file mode is octal, written as a string like "0600" or an integer like 600,
+/// the digits of the integer are taken as octal too.
+fn deserialize_mode<'de, D>(de: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ModeVisitor;
+
+    impl<'de> de::Visitor<'de> for ModeVisitor {
+        type Value = String;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an octal file mode")
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<String, E> {
+            if v < 0 {
+                return Err(E::custom(format!("invalid file mode: {v}")));
+            }
+            Ok(v.to_string())
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<String, E> {
+            Ok(v.to_string())
+        }
+    }
+
+    de.deserialize_any(ModeVisitor)
+}
+
+/// whether an IPv6 socket accepts IPv4 connections too
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(super) enum BindIPv6Only {
+    /// follow the kernel setting net.ipv6.bindv6only
+    #[serde(alias = "default")]
+    Default,
+    /// accept both IPv4 and IPv6
+    #[serde(alias = "both")]
+    Both,
+    /// accept IPv6 only
+    #[serde(alias = "ipv6-only")]
+    Ipv6Only,
+}
+
+/// the resolution of the timestamps of the received packets
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(super) enum Timestamping {
+    #[serde(alias = "off")]
+    Off,
+    #[serde(alias = "us", alias = "usec", alias = "µs")]
+    Microseconds,
+    #[serde(alias = "ns", alias = "nsec")]
+    Nanoseconds,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -123,6 +306,24 @@ pub(super) enum SocketResult {
     ResultInvalid,
 }
 
+impl fmt::Display for SocketResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            SocketResult::Success => "success",
+            SocketResult::FailureResources => "resources",
+            SocketResult::FailureTimeout => "timeout",
+            SocketResult::FailureExitCode => "exit-code",
+            SocketResult::FailureSignal => "signal",
+            SocketResult::FailureCoreDump => "core-dump",
+            SocketResult::FailureStartLimitHit => "start-limit-hit",
+            SocketResult::FailureTriggerLimitHit => "trigger-limit-hit",
+            SocketResult::FailureServiceStartLimitHit => "service-start-limit-hit",
+            SocketResult::ResultInvalid => "invalid",
+        };
+        write!(f, "{s}")
+    }
+}
+
 /// the command that running in different stage.
 #[allow(dead_code)]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Copy, Clone, Serialize, Deserialize)]
@@ -139,6 +340,10 @@ pub(super) enum PortType {
     Socket,
     Fifo,
     Invalid,
+    // appended after Invalid to keep the serialized values of the old ones
+    Special,
+    MessageQueue,
+    USBFunction,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
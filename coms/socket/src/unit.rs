@@ -32,6 +32,7 @@ struct SocketUnit {
     config: Rc<SocketConfig>,
     mng: SocketMng,
     load: SocketLoad,
+    exec_ctx: Rc<ExecContext>,
 }
 
 impl ReStation for SocketUnit {
@@ -75,6 +76,14 @@ impl SubUnit for SocketUnit {
 
         self.mng.build_ports();
 
+        let socket = self.config.config_data().borrow().Socket.clone();
+        self.exec_ctx.set_stdio(
+            socket.StandardInput.clone(),
+            socket.StandardOutput.clone(),
+            socket.StandardError.clone(),
+        );
+        self.exec_ctx.set_rlimits(socket.rlimits());
+
         self.load.socket_verify()
     }
 
@@ -117,10 +126,23 @@ impl SubUnit for SocketUnit {
         self.mng.get_state()
     }
 
+    fn get_subunit_result(&self) -> Option<String> {
+        self.mng.get_result()
+    }
+
     fn collect_fds(&self) -> Vec<i32> {
         self.mng.collect_fds()
     }
 
+    fn fd_name(&self) -> Option<String> {
+        self.config
+            .config_data()
+            .borrow()
+            .Socket
+            .FileDescriptorName
+            .clone()
+    }
+
     fn attach_unit(&self, unit: Rc<dyn UnitBase>) {
         self.comm.attach_unit(unit);
         self.db_insert();
@@ -148,6 +170,7 @@ impl SocketUnit {
             config: Rc::clone(&_config),
             mng: SocketMng::new(&_comm, &_config, &context),
             load: SocketLoad::new(&_config, &_comm),
+            exec_ctx: Rc::clone(&context),
         }
     }
 }
@@ -12,6 +12,9 @@
 //
 #![allow(non_snake_case)]
 use confique::Config;
+use nix::sys::resource::Resource;
+use sysmaster::exec::{parse_cpu_set, Rlimit};
+use sysmaster::serialize::DeserializeWith;
 
 pub const SYSTEM_CONFIG: &str = "/etc/sysmaster/system.toml";
 
@@ -27,10 +30,76 @@ pub struct SectionManager {
     pub DefaultRestartSec: u64,
     #[config(default = 90)]
     pub DefaultTimeoutSec: u64,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitCPU: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitFSIZE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitDATA: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitSTACK: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitCORE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitRSS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitNOFILE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitAS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitNPROC: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitMEMLOCK: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitLOCKS: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitSIGPENDING: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitMSGQUEUE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_nice)]
+    pub DefaultLimitNICE: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitRTPRIO: Option<Rlimit>,
+    #[config(deserialize_with = Rlimit::deserialize_with)]
+    pub DefaultLimitRTTIME: Option<Rlimit>,
+    #[config(default = "")]
+    pub CPUAffinity: String,
+}
+
+impl SectionManager {
+    /// the default resource limits of the spawned processes, which are overridden by
+    /// the Limit*= of the units
+    pub fn rlimits(&self) -> Vec<(Resource, Rlimit)> {
+        [
+            (Resource::RLIMIT_CPU, self.DefaultLimitCPU),
+            (Resource::RLIMIT_FSIZE, self.DefaultLimitFSIZE),
+            (Resource::RLIMIT_DATA, self.DefaultLimitDATA),
+            (Resource::RLIMIT_STACK, self.DefaultLimitSTACK),
+            (Resource::RLIMIT_CORE, self.DefaultLimitCORE),
+            (Resource::RLIMIT_RSS, self.DefaultLimitRSS),
+            (Resource::RLIMIT_NOFILE, self.DefaultLimitNOFILE),
+            (Resource::RLIMIT_AS, self.DefaultLimitAS),
+            (Resource::RLIMIT_NPROC, self.DefaultLimitNPROC),
+            (Resource::RLIMIT_MEMLOCK, self.DefaultLimitMEMLOCK),
+            (Resource::RLIMIT_LOCKS, self.DefaultLimitLOCKS),
+            (Resource::RLIMIT_SIGPENDING, self.DefaultLimitSIGPENDING),
+            (Resource::RLIMIT_MSGQUEUE, self.DefaultLimitMSGQUEUE),
+            (Resource::RLIMIT_NICE, self.DefaultLimitNICE),
+            (Resource::RLIMIT_RTPRIO, self.DefaultLimitRTPRIO),
+            (Resource::RLIMIT_RTTIME, self.DefaultLimitRTTIME),
+        ]
+        .into_iter()
+        .filter_map(|(r, l)| l.map(|l| (r, l)))
+        .collect()
+    }
+
+    /// the cpus which sysmaster itself runs on, inherited by the spawned processes
+    pub fn cpu_affinity(&self) -> Result<Vec<usize>, String> {
+        parse_cpu_set(&self.CPUAffinity)
+    }
 }
 
 impl ManagerConfig {
-    #[allow(dead_code)]
     pub fn new(file: Option<&str>) -> ManagerConfig {
         let builder = ManagerConfig::builder().env();
         let manager_config = builder.file(file.unwrap_or(SYSTEM_CONFIG));
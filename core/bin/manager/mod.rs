@@ -12,6 +12,7 @@
 
 #[cfg(test)]
 pub(crate) use rentry::RELI_HISTORY_MAX_DBS;
+use sysmaster::exec;
 use sysmaster::unit::UnitStatus;
 
 pub(crate) mod alive_timer;
@@ -29,6 +30,7 @@ use cgroup::CgController;
 use cgroup::{cg_create_and_attach, CgFlags};
 use cmdproto::proto::execute::ExecuterAction;
 use commands::Commands;
+use config::ManagerConfig;
 use event::{EventState, Events};
 use nix::sys::reboot::{self, RebootMode};
 use nix::sys::signal::Signal;
@@ -143,6 +145,16 @@ impl ExecuterAction for CommandActionMgr {
         self.um.get_all_units()
     }
 
+    fn logs(
+        &self,
+        unit_name: &str,
+        lines: u32,
+        follow: bool,
+        cursor: u64,
+    ) -> Result<(String, u64), Self::Error> {
+        self.um.get_unit_logs(unit_name, lines, follow, cursor)
+    }
+
     fn suspend(&self) -> Result<i32, Self::Error> {
         self.set_state(State::Suspend);
         Ok(0)
@@ -226,6 +238,17 @@ impl Manager {
             &lookup_path,
             Rc::clone(&state),
         ));
+        let config = ManagerConfig::new(None);
+        um.set_default_rlimits(config.Manager.rlimits());
+        match config.Manager.cpu_affinity() {
+            Ok(cpus) if !cpus.is_empty() => {
+                if let Err(e) = exec::apply_cpu_affinity(&cpus) {
+                    log::warn!("Failed to set the cpu affinity of sysmaster: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Invalid CPUAffinity of sysmaster: {}", e),
+        }
 
         let res = KeepAlive::get_instance();
         let connect_fd;
@@ -14,6 +14,7 @@
 use crate::unit::{unit_name_to_type, UeConfigInstall, UnitType};
 use basic::fs_util;
 use basic::path_lookup::LookupPaths;
+use basic::unit_name;
 use bitflags::bitflags;
 use confique::Config;
 use nix::unistd::UnlinkatFlags;
@@ -27,6 +28,7 @@ use std::{
     str::FromStr,
 };
 use sysmaster::error::*;
+use sysmaster::unit::unit_fragment_load;
 use walkdir::{DirEntry, WalkDir};
 
 #[derive(PartialEq, Eq)]
@@ -118,6 +120,7 @@ struct UnitInstall {
     wanted_by: RefCell<Vec<String>>,
     required_by: RefCell<Vec<String>>,
     also: RefCell<Vec<String>>,
+    default_instance: RefCell<String>,
     u_type: RefCell<UnitFileType>,
 }
 
@@ -130,6 +133,7 @@ impl UnitInstall {
             wanted_by: RefCell::new(Vec::new()),
             required_by: RefCell::new(Vec::new()),
             also: RefCell::new(Vec::new()),
+            default_instance: RefCell::new(String::new()),
             u_type: RefCell::new(UnitFileType::Invalid),
         }
     }
@@ -170,6 +174,22 @@ impl UnitInstall {
         for v in &config.Install.Also {
             self.also.borrow_mut().push(v.to_string());
         }
+
+        *self.default_instance.borrow_mut() = config.Install.DefaultInstance.to_string();
+    }
+
+    /// the name of the wants/requires symlinks, the template is enabled
+    /// with its default instance, return None if it has no one.
+    fn instance_name(&self) -> Option<String> {
+        if !unit_name::unit_name_is_template(&self.name) {
+            return Some(self.name());
+        }
+
+        let instance = self.default_instance.borrow();
+        if instance.is_empty() {
+            return None;
+        }
+        unit_name::unit_name_replace_instance(&self.name, &instance)
     }
 
     fn wanted_by(&self) -> Vec<String> {
@@ -337,11 +357,23 @@ impl InstallContext {
             return Ok(0);
         }
 
+        let name = match install.instance_name() {
+            Some(name) => name,
+            None => {
+                log::warn!(
+                    "{} is a template without DefaultInstance, skip creating its {} symlinks",
+                    install.name(),
+                    suffix
+                );
+                return Ok(0);
+            }
+        };
+
         let mut n = 0;
         let source = install.path();
 
         for symlink in symlinks {
-            let target = format!("{}/{}.{}/{}", target_path, symlink, suffix, install.name());
+            let target = format!("{target_path}/{symlink}.{suffix}/{name}");
 
             let path = Path::new(&target);
             let parent_path = path.parent();
@@ -511,11 +543,18 @@ impl Install {
             self.unit_file_load(&unit_install.path(), unit_install.clone(), ctx.clone())?;
         }
 
+        // the instance is enabled with the file of its template
+        let name = unit_install.name();
+        let template = unit_name::unit_name_to_template(&name);
         for v in &self.lookup_path.search_path {
-            let unit = Path::new(v).join(unit_install.clone().name());
-            if !unit.exists() {
-                continue;
-            }
+            let unit = match std::iter::once(&name)
+                .chain(template.iter())
+                .map(|n| Path::new(v).join(n))
+                .find(|p| p.exists())
+            {
+                Some(unit) => unit,
+                None => continue,
+            };
 
             // Skip unit which we can't load, instead of panic.
             if let Err(e) =
@@ -553,21 +592,23 @@ impl Install {
         }
 
         let canon_path = path.canonicalize()?;
-        let tmp = format!("{}.toml", canon_path.as_path().display());
-        if let Err(e) = std::fs::copy(canon_path, &tmp).context(IoSnafu) {
-            log::warn!("copy file content to toml file error: {}", e);
-            return Err(e);
-        }
-
+        let name = unit_install.name();
         let mut builder = UeConfigData::builder().env();
-        builder = builder.file(&tmp);
+        builder = builder.preloaded(unit_fragment_load(&name, &canon_path)?);
 
-        let dropin_dir_name = format!("{}.d", unit_install.name());
+        let mut dropin_dir_names = vec![format!("{name}.d")];
+        if let Some(template) = unit_name::unit_name_to_template(&name) {
+            dropin_dir_names.push(format!("{template}.d"));
+        }
 
-        for v in &self.lookup_path.search_path {
+        for (v, dropin_dir_name) in self
+            .lookup_path
+            .search_path
+            .iter()
+            .flat_map(|v| dropin_dir_names.iter().map(move |d| (v, d)))
+        {
             let base_dir = Path::new(v);
-            #[allow(clippy::needless_borrow)]
-            let dropin_dir = base_dir.join(&dropin_dir_name);
+            let dropin_dir = base_dir.join(dropin_dir_name);
 
             if !dropin_dir.exists() {
                 log::debug!("dropin path is not exist, ignore it: {:?}", &dropin_dir);
@@ -580,11 +621,11 @@ impl Install {
                 let fragment = dir_entry.path();
                 if fragment.is_file() {
                     let file_name = String::from(fragment.file_name().unwrap().to_str().unwrap());
-                    if file_name.starts_with('.') || !file_name.ends_with(".toml") {
+                    if file_name.starts_with('.') || file_name.ends_with(".toml") {
                         continue;
                     }
 
-                    builder = builder.file(fragment);
+                    builder = builder.preloaded(unit_fragment_load(&name, &fragment)?);
                 }
             }
         }
@@ -595,7 +636,6 @@ impl Install {
         for also in configer.Install.Also {
             self.unit_install_discover(&also, ctx.clone())?;
         }
-        // fs::remove_file(&tmp);
 
         Ok(())
     }
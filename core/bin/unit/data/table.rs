@@ -135,6 +135,15 @@ impl DataManager {
     }
 
     // repeating protection
+    /// remove all the data of the unit when it's unloaded
+    pub(crate) fn remove_unit(&self, u_name: &str) {
+        let name = u_name.to_string();
+        self.tables.0.borrow_mut().remove(&name);
+        self.tables.1.borrow_mut().remove(&name);
+        self.tables.2.borrow_mut().remove(&name);
+        self.tables.3.borrow_mut().remove(&name);
+    }
+
     pub(crate) fn clear(&self) {
         self.tables.0.borrow_mut().clear();
         self.tables.1.borrow_mut().clear();
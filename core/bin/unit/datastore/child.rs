@@ -133,8 +133,10 @@ impl UnitChildData {
         self.watch_pids.borrow().get(&pid).cloned()
     }
 
-    fn remove_unit(&self, _unit: &UnitX) {
-        todo!();
+    fn remove_unit(&self, unit: &UnitX) {
+        self.watch_pids
+            .borrow_mut()
+            .retain(|_, u| u.id() != unit.id());
     }
 }
 
@@ -270,6 +270,7 @@ impl UnitDepData {
                 self.remove(source, *relation, dest);
             }
         }
+        self.t.remove(source);
     }
 
     pub(self) fn gets(&self, source: &UnitX, relation: UnitRelations) -> Vec<Rc<UnitX>> {
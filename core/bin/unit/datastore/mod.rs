@@ -74,7 +74,6 @@ impl UnitDb {
         self.units.insert(name, unit)
     }
 
-    #[allow(dead_code)]
     pub fn unit_remove(&self, name: &str) {
         self.units.remove(name);
     }
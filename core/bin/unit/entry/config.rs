@@ -21,6 +21,7 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use sysmaster::error::*;
 use sysmaster::rel::ReStation;
+use sysmaster::unit::unit_fragment_load;
 
 pub(crate) struct UeConfig {
     // associated objects
@@ -137,7 +138,7 @@ impl UeConfig {
                 log::error!("config file is not exist");
                 return Err(format!("config file is not exist {name}").into());
             }
-            builder = builder.file(&v);
+            builder = builder.preloaded(unit_fragment_load(name, &v)?);
         }
 
         let mut configer = builder.load().context(ConfiqueSnafu)?;
@@ -179,6 +179,10 @@ impl UeLoad {
                 UnitRelations::UnitConflicts,
                 config_data.borrow().Unit.Conflicts.clone(),
             ),
+            (
+                UnitRelations::UnitJoinsNameSpaceOf,
+                config_data.borrow().Unit.JoinsNamespaceOf.clone(),
+            ),
         ];
 
         for ud_conf_relation in ud_conf_insert_table {
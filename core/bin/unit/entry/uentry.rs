@@ -498,6 +498,11 @@ impl Unit {
         self.sub.get_subunit_state()
     }
 
+    ///
+    pub fn get_subunit_result(&self) -> Option<String> {
+        self.sub.get_subunit_result()
+    }
+
     /// test start rate, if start more than burst times in interval time, return error
     fn test_start_limit(&self) -> bool {
         if self.config.config_data().borrow().Unit.StartLimitInterval > 0
@@ -545,6 +550,10 @@ impl Unit {
         self.load.set_in_target_dep_queue(t);
     }
 
+    pub(super) fn done(&self) {
+        self.sub.done();
+    }
+
     pub(super) fn load_unit(&self) -> Result<()> {
         self.set_in_load_queue(false);
         // Mount unit doesn't have config file, set its loadstate to
@@ -668,6 +677,14 @@ impl Unit {
         self.sub.collect_fds()
     }
 
+    pub(super) fn fd_name(&self) -> Option<String> {
+        self.sub.fd_name()
+    }
+
+    pub(super) fn set_socket_fd(&self, fd: i32) -> Result<()> {
+        self.sub.set_socket_fd(fd)
+    }
+
     pub(crate) fn notify_message(
         &self,
         ucred: &UnixCredentials,
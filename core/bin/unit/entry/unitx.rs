@@ -70,8 +70,9 @@ impl UnitX {
 
     #[allow(dead_code)]
     pub(crate) fn init(&self) {}
-    #[allow(dead_code)]
-    pub(crate) fn done(&self) {}
+    pub(crate) fn done(&self) {
+        self.0.done()
+    }
     #[allow(dead_code)]
     pub(crate) fn load(&self) -> Result<()> {
         self.0.load_unit()
@@ -218,6 +219,14 @@ impl UnitX {
         self.0.collect_fds()
     }
 
+    pub(crate) fn fd_name(&self) -> Option<String> {
+        self.0.fd_name()
+    }
+
+    pub(crate) fn set_socket_fd(&self, fd: i32) -> Result<()> {
+        self.0.set_socket_fd(fd)
+    }
+
     pub fn get_config(&self) -> Rc<UeConfig> {
         self.0.get_config()
     }
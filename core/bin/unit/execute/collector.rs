@@ -0,0 +1,527 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+use basic::{fd_util, time_util, unit_name};
+use event::{EventState, EventType, Events, Source};
+use nix::errno::Errno;
+use nix::fcntl::OFlag;
+use nix::unistd::{self, Pid};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+use std::time::SystemTime;
+use sysmaster::error::*;
+
+const LOG_DIR: &str = "/var/log/sysmaster";
+/// the log file is rotated when it grows larger than this
+const LOG_FILE_MAX: u64 = 1024 * 1024;
+/// the number of the rotated log files kept for each unit
+const LOG_FILE_ROTATE: u32 = 3;
+/// the longest line is split when it is longer than this
+const LOG_LINE_MAX: usize = 4096;
+/// the cursor holds the inode of the log file above these bits and the offset below,
+/// so the reader can still find its position after the log file is rotated.
+const CURSOR_OFFSET_BITS: u32 = 40;
+const CURSOR_OFFSET_MASK: u64 = (1 << CURSOR_OFFSET_BITS) - 1;
+
+type LogPipes = RefCell<HashMap<RawFd, Rc<LogPipe>>>;
+
+/// collect the output of the spawned processes into the log files of the units
+pub(in crate::unit) struct LogCollector {
+    events: Rc<Events>,
+    pipes: Rc<LogPipes>,
+    // the opened log files, shared by the pipes of the same unit
+    files: RefCell<HashMap<String, Weak<RefCell<LogFile>>>>,
+}
+
+impl LogCollector {
+    pub(in crate::unit) fn new(eventr: &Rc<Events>) -> LogCollector {
+        LogCollector {
+            events: Rc::clone(eventr),
+            pipes: Rc::new(RefCell::new(HashMap::new())),
+            files: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// return the log file of the unit, it's closed when all the pipes of the unit are closed
+    fn log_file(&self, unit: &str) -> Rc<RefCell<LogFile>> {
+        let mut files = self.files.borrow_mut();
+        files.retain(|_, f| f.strong_count() > 0);
+        if let Some(file) = files.get(unit).and_then(|f| f.upgrade()) {
+            return file;
+        }
+
+        let file = Rc::new(RefCell::new(LogFile::new(Path::new(LOG_DIR), unit)));
+        files.insert(unit.to_string(), Rc::downgrade(&file));
+        file
+    }
+
+    /// create a pipe whose output is written to the log file of the unit,
+    /// return the read end and the write end, the write end is passed to the child.
+    pub(in crate::unit) fn open_pipe(&self, unit: &str) -> Result<(RawFd, RawFd)> {
+        let (rfd, wfd) = unistd::pipe2(OFlag::O_CLOEXEC).context(NixSnafu)?;
+        if let Err(e) = fd_util::fd_nonblock(rfd, true) {
+            fd_util::close(rfd);
+            fd_util::close(wfd);
+            return Err(e.into());
+        }
+
+        let pipe = Rc::new(LogPipe::new(
+            unit,
+            rfd,
+            Rc::downgrade(&self.pipes),
+            self.log_file(unit),
+        ));
+        let ret = self
+            .events
+            .add_source(pipe.clone())
+            .and_then(|_| self.events.set_enabled(pipe.clone(), EventState::On));
+        if let Err(e) = ret {
+            log::error!("Failed to watch the log pipe of {}: {}", unit, e);
+            fd_util::close(rfd);
+            fd_util::close(wfd);
+            return Err(Error::Other { msg: e.to_string() });
+        }
+
+        self.pipes.borrow_mut().insert(rfd, pipe);
+        Ok((rfd, wfd))
+    }
+
+    /// record the process which writes to the pipe, it's used to tag the log lines
+    pub(in crate::unit) fn set_pipe_pid(&self, rfd: RawFd, pid: Pid) {
+        if let Some(pipe) = self.pipes.borrow().get(&rfd) {
+            pipe.pid.set(Some(pid));
+        }
+    }
+}
+
+/// the current log file of the unit, the file is kept open and its size is
+/// tracked, so it's not checked on every write.
+struct LogFile {
+    dir: PathBuf,
+    unit: String,
+    file: Option<File>,
+    size: u64,
+}
+
+impl LogFile {
+    fn new(dir: &Path, unit: &str) -> LogFile {
+        LogFile {
+            dir: dir.to_path_buf(),
+            unit: unit.to_string(),
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path(&self.dir, &self.unit, 0))?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn write(&mut self, content: &[u8]) -> io::Result<()> {
+        if self.file.is_none() {
+            self.open()?;
+        }
+
+        if self.size > 0 && self.size + content.len() as u64 > LOG_FILE_MAX {
+            self.file = None;
+            rotate_log(&self.dir, &self.unit)?;
+            self.open()?;
+        }
+
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(content)?;
+            self.size += content.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+struct LogPipe {
+    unit: String,
+    fd: RawFd,
+    pid: Cell<Option<Pid>>,
+    pipes: Weak<LogPipes>,
+    file: Rc<RefCell<LogFile>>,
+    // the incomplete line that has not been written
+    line: RefCell<Vec<u8>>,
+}
+
+impl LogPipe {
+    fn new(unit: &str, fd: RawFd, pipes: Weak<LogPipes>, file: Rc<RefCell<LogFile>>) -> LogPipe {
+        LogPipe {
+            unit: unit.to_string(),
+            fd,
+            pid: Cell::new(None),
+            pipes,
+            file,
+            line: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn tag(&self) -> String {
+        let now = time_util::format_timestamp(SystemTime::now());
+        match self.pid.get() {
+            Some(pid) => format!("{} {}[{}]: ", now, self.unit, pid),
+            None => format!("{} {}: ", now, self.unit),
+        }
+    }
+
+    fn feed(&self, buf: &[u8]) {
+        let mut content = String::new();
+        let mut line = self.line.borrow_mut();
+        for b in buf {
+            if *b != b'\n' {
+                line.push(*b);
+                if line.len() < LOG_LINE_MAX {
+                    continue;
+                }
+            }
+
+            content += &self.tag();
+            content += &String::from_utf8_lossy(&line);
+            content.push('\n');
+            line.clear();
+        }
+
+        if !content.is_empty() {
+            self.write_log(&content);
+        }
+    }
+
+    fn flush(&self) {
+        let line = self.line.take();
+        if !line.is_empty() {
+            let content = format!("{}{}\n", self.tag(), String::from_utf8_lossy(&line));
+            self.write_log(&content);
+        }
+    }
+
+    fn write_log(&self, content: &str) {
+        if let Err(e) = self.file.borrow_mut().write(content.as_bytes()) {
+            log::error!("Failed to write the log file of {}: {}", self.unit, e);
+        }
+    }
+
+    fn close(&self, e: &Events) {
+        self.flush();
+
+        let pipe = match self.pipes.upgrade() {
+            None => return,
+            Some(pipes) => pipes.borrow_mut().remove(&self.fd),
+        };
+
+        if let Some(pipe) = pipe {
+            if let Err(err) = e.del_source(pipe) {
+                log::warn!("Failed to remove the log pipe of {}: {}", self.unit, err);
+            }
+        }
+        fd_util::close(self.fd);
+    }
+}
+
+impl Source for LogPipe {
+    fn fd(&self) -> RawFd {
+        self.fd
+    }
+
+    fn event_type(&self) -> EventType {
+        EventType::Io
+    }
+
+    fn epoll_event(&self) -> u32 {
+        (libc::EPOLLIN) as u32
+    }
+
+    fn priority(&self) -> i8 {
+        0i8
+    }
+
+    fn dispatch(&self, e: &Events) -> i32 {
+        let mut buf = [0u8; 4096];
+        loop {
+            match unistd::read(self.fd, &mut buf) {
+                Ok(0) => {
+                    // all the writers have exited
+                    self.close(e);
+                    return 0;
+                }
+                Ok(n) => self.feed(&buf[..n]),
+                Err(Errno::EINTR) => continue,
+                Err(Errno::EAGAIN) => return 0,
+                Err(err) => {
+                    log::error!("Failed to read the log pipe of {}: {}", self.unit, err);
+                    self.close(e);
+                    return -1;
+                }
+            }
+        }
+    }
+
+    fn token(&self) -> u64 {
+        let data: u64 = unsafe { std::mem::transmute(self) };
+        data
+    }
+}
+
+fn log_path(dir: &Path, unit: &str, index: u32) -> PathBuf {
+    match index {
+        0 => dir.join(format!("{unit}.log")),
+        n => dir.join(format!("{unit}.log.{n}")),
+    }
+}
+
+fn rotate_log(dir: &Path, unit: &str) -> io::Result<()> {
+    for i in (1..LOG_FILE_ROTATE).rev() {
+        let from = log_path(dir, unit, i);
+        if from.exists() {
+            fs::rename(from, log_path(dir, unit, i + 1))?;
+        }
+    }
+
+    fs::rename(log_path(dir, unit, 0), log_path(dir, unit, 1))
+}
+
+/// the unit name comes from the clients, only the valid one is used as the file name
+fn check_unit_name(unit: &str) -> Result<()> {
+    if !unit_name::unit_name_is_valid(unit) {
+        log::error!("Invalid unit name {:?}, refuse to read its log", unit);
+        return Err(Error::InvalidData);
+    }
+    Ok(())
+}
+
+fn cursor_new(ino: u64, offset: u64) -> u64 {
+    (ino << CURSOR_OFFSET_BITS) | (offset & CURSOR_OFFSET_MASK)
+}
+
+/// return true if the file with the inode is the one the cursor points to
+fn cursor_match(cursor: u64, ino: u64) -> bool {
+    cursor >> CURSOR_OFFSET_BITS == cursor_new(ino, 0) >> CURSOR_OFFSET_BITS
+}
+
+/// read the file from the offset, return the content and the inode and the size of the file
+fn read_from(path: &Path, offset: u64) -> io::Result<(Vec<u8>, u64, u64)> {
+    let mut file = File::open(path)?;
+    let ino = file.metadata()?.ino();
+    file.seek(SeekFrom::Start(offset))?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    let end = offset + content.len() as u64;
+    Ok((content, ino, end))
+}
+
+/// return the last lines of the unit log, the rotated log file is not read
+pub(in crate::unit) fn log_tail(unit: &str, lines: usize) -> Vec<String> {
+    if check_unit_name(unit).is_err() {
+        return Vec::new();
+    }
+    tail_log(Path::new(LOG_DIR), unit, lines)
+}
+
+/// return the cursor of the end of the unit log
+pub(in crate::unit) fn log_cursor(unit: &str) -> u64 {
+    if check_unit_name(unit).is_err() {
+        return 0;
+    }
+    end_cursor(Path::new(LOG_DIR), unit)
+}
+
+/// return the content of the unit log after the cursor, and the new cursor.
+pub(in crate::unit) fn log_read(unit: &str, cursor: u64) -> Result<(String, u64)> {
+    check_unit_name(unit)?;
+    read_log(Path::new(LOG_DIR), unit, cursor)
+}
+
+fn tail_log(dir: &Path, unit: &str, lines: usize) -> Vec<String> {
+    let content = match fs::read(log_path(dir, unit, 0)) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    let content = String::from_utf8_lossy(&content);
+    let all: Vec<&str> = content.lines().collect();
+    let skip = all.len().saturating_sub(lines);
+    all[skip..].iter().map(|l| l.to_string()).collect()
+}
+
+fn end_cursor(dir: &Path, unit: &str) -> u64 {
+    fs::metadata(log_path(dir, unit, 0)).map_or(0, |m| cursor_new(m.ino(), m.len()))
+}
+
+/// the cursor points to the file it was taken from, if the file has been rotated,
+/// the rest of it and the newer rotated ones are read before the current one.
+fn read_log(dir: &Path, unit: &str, cursor: u64) -> Result<(String, u64)> {
+    let current = log_path(dir, unit, 0);
+    if !current.exists() {
+        return Ok((String::new(), 0));
+    }
+
+    // from the oldest to the current one, the cursor is in one of them
+    let paths: Vec<PathBuf> = (0..=LOG_FILE_ROTATE)
+        .rev()
+        .map(|i| log_path(dir, unit, i))
+        .collect();
+    let start = paths.iter().position(|p| {
+        fs::metadata(p).map_or(false, |m| cursor != 0 && cursor_match(cursor, m.ino()))
+    });
+    let (start, mut offset) = match start {
+        Some(i) => (i, cursor & CURSOR_OFFSET_MASK),
+        // the file has been rotated away, read all the kept ones
+        None => (0, 0),
+    };
+
+    let mut content = Vec::new();
+    let mut end = 0;
+    for path in &paths[start..] {
+        match read_from(path, offset) {
+            Ok((mut c, ino, size)) => {
+                content.append(&mut c);
+                end = cursor_new(ino, size);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(Error::Io { source: e }),
+        }
+        offset = 0;
+    }
+
+    Ok((String::from_utf8_lossy(&content).to_string(), end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sysmaster-collector-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// return the content of the log lines without the tags
+    fn log_lines(dir: &Path, unit: &str) -> Vec<String> {
+        let tag = format!("{unit}: ");
+        tail_log(dir, unit, usize::MAX)
+            .iter()
+            .map(|l| l.split_once(&tag).unwrap().1.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_log_pipe_split() {
+        let dir = test_dir("split");
+        let unit = "foo.service";
+        let file = Rc::new(RefCell::new(LogFile::new(&dir, unit)));
+        let pipe = LogPipe::new(unit, -1, Weak::new(), file);
+
+        pipe.feed(b"hello\nwor");
+        pipe.feed(b"ld\n");
+        pipe.feed(&[b'a'; LOG_LINE_MAX + 10]);
+        pipe.feed(b"\ntail");
+        pipe.flush();
+
+        assert_eq!(
+            log_lines(&dir, unit),
+            vec![
+                "hello".to_string(),
+                "world".to_string(),
+                "a".repeat(LOG_LINE_MAX),
+                "a".repeat(10),
+                "tail".to_string(),
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_tail() {
+        let dir = test_dir("tail");
+        let unit = "foo.service";
+        assert!(tail_log(&dir, unit, 2).is_empty());
+
+        let mut file = LogFile::new(&dir, unit);
+        for i in 0..5 {
+            file.write(format!("line {i}\n").as_bytes()).unwrap();
+        }
+        assert_eq!(tail_log(&dir, unit, 2), vec!["line 3", "line 4"]);
+        assert_eq!(tail_log(&dir, unit, usize::MAX).len(), 5);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_rotate_read() {
+        let dir = test_dir("rotate");
+        let unit = "foo.service";
+        assert_eq!(read_log(&dir, unit, 0).unwrap(), (String::new(), 0));
+
+        let mut file = LogFile::new(&dir, unit);
+        file.write(b"first\n").unwrap();
+        let (content, cursor) = read_log(&dir, unit, 0).unwrap();
+        assert_eq!(content, "first\n");
+        assert_eq!(cursor, end_cursor(&dir, unit));
+
+        // the file is rotated under the cursor, and the new one grows beyond the cursor
+        let line = format!("{}\n", "x".repeat(999));
+        let mut written = String::new();
+        for _ in 0..1500 {
+            file.write(line.as_bytes()).unwrap();
+            written += &line;
+        }
+        assert!(log_path(&dir, unit, 1).exists());
+        assert!(fs::metadata(log_path(&dir, unit, 0)).unwrap().len() <= LOG_FILE_MAX);
+
+        let (content, cursor) = read_log(&dir, unit, cursor).unwrap();
+        assert_eq!(content.len(), written.len());
+        assert_eq!(content, written);
+        assert_eq!(cursor, end_cursor(&dir, unit));
+
+        // nothing new
+        let (content, cursor) = read_log(&dir, unit, cursor).unwrap();
+        assert!(content.is_empty());
+
+        file.write(b"more\n").unwrap();
+        let (content, _) = read_log(&dir, unit, cursor).unwrap();
+        assert_eq!(content, "more\n");
+
+        // only LOG_FILE_ROTATE files are kept
+        for _ in 0..(LOG_FILE_ROTATE as usize + 1) * 1100 {
+            file.write(line.as_bytes()).unwrap();
+        }
+        assert!(log_path(&dir, unit, LOG_FILE_ROTATE).exists());
+        assert!(!log_path(&dir, unit, LOG_FILE_ROTATE + 1).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_invalid_unit() {
+        assert!(log_read("../../etc/passwd", 0).is_err());
+        assert!(log_read("foo/bar.service", 0).is_err());
+        assert!(log_tail("../foo.service", 10).is_empty());
+        assert_eq!(log_cursor("foo/../bar.service"), 0);
+    }
+}
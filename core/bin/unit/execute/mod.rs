@@ -10,5 +10,8 @@
 // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
 // See the Mulan PSL v2 for more details.
 
+pub(super) use collector::{log_cursor, log_read, log_tail};
 pub(super) use spawn::ExecSpawn;
+mod collector;
+mod namespace;
 mod spawn;
@@ -0,0 +1,958 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! set up the namespaces of the spawned process
+use basic::{fd_util, mount_util};
+use nix::errno::Errno;
+use nix::fcntl::{self, FcntlArg, OFlag};
+use nix::mount::{self, MntFlags, MsFlags};
+use nix::sched::{self, unshare, CloneFlags};
+use nix::sys::socket::{
+    self, AddressFamily, ControlMessage, ControlMessageOwned, MsgFlags, SockFlag, SockType,
+};
+use nix::sys::stat::Mode;
+use nix::sys::wait::{self, WaitStatus};
+use nix::unistd::{self, ForkResult, Gid, Pid, Uid};
+use std::cell::RefCell;
+use std::ffi::{CString, OsStr, OsString};
+use std::fs;
+use std::io::{IoSlice, IoSliceMut};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::io::RawFd;
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use sysmaster::error::*;
+use sysmaster::exec::{ExecNamespace, ProtectHome, ProtectSystem};
+
+/// the inaccessible nodes are bind-mounted over the inaccessible paths
+const INACCESSIBLE_DIR: &str = "/run/sysmaster/inaccessible";
+
+const HOME_PATHS: [&str; 3] = ["/home", "/root", "/run/user"];
+
+/// the API file systems stay writable with ProtectSystem=strict, and are bound into
+/// RootDirectory= with MountAPIVFS=
+const API_VFS_PATHS: [&str; 3] = ["/dev", "/proc", "/sys"];
+
+/// the maximum number of the symlinks followed when resolving a path in RootDirectory=
+const MAX_SYMLINKS: u32 = 32;
+
+/// the network and IPC namespaces and the private temporary directories shared by the
+/// processes of the unit and the units joining its namespaces, each namespace is kept as
+/// a file descriptor queued in the socket pair, so it lives as long as the runtime even
+/// if no process is in it.
+pub(super) struct NamespaceRuntime {
+    net: (RawFd, RawFd),
+    ipc: (RawFd, RawFd),
+    // the private /tmp and /var/tmp on the host, created when a process needs them
+    tmp: RefCell<Option<(PathBuf, PathBuf)>>,
+}
+
+impl NamespaceRuntime {
+    pub(super) fn new() -> Result<NamespaceRuntime> {
+        let net = storage_socket()?;
+        let ipc = match storage_socket() {
+            Ok(v) => v,
+            Err(e) => {
+                fd_util::close(net.0);
+                fd_util::close(net.1);
+                return Err(e);
+            }
+        };
+
+        Ok(NamespaceRuntime {
+            net,
+            ipc,
+            tmp: RefCell::new(None),
+        })
+    }
+
+    /// create the private /tmp and /var/tmp of the runtime on the host if not yet, it is
+    /// done by the manager before forking, so all the processes get the same ones
+    pub(super) fn setup_private_tmp(&self) -> Result<()> {
+        if self.tmp.borrow().is_some() {
+            return Ok(());
+        }
+
+        let tmp = make_private_tmp("/tmp")?;
+        let var_tmp = match make_private_tmp("/var/tmp") {
+            Ok(v) => v,
+            Err(e) => {
+                remove_private_tmp(&tmp);
+                return Err(e);
+            }
+        };
+        *self.tmp.borrow_mut() = Some((tmp, var_tmp));
+        Ok(())
+    }
+
+    /// the private /tmp and /var/tmp of the runtime, which are bound into the processes
+    pub(super) fn private_tmp(&self) -> Option<(PathBuf, PathBuf)> {
+        self.tmp.borrow().clone()
+    }
+}
+
+impl Drop for NamespaceRuntime {
+    fn drop(&mut self) {
+        for fd in [self.net.0, self.net.1, self.ipc.0, self.ipc.1] {
+            fd_util::close(fd);
+        }
+
+        if let Some((tmp, var_tmp)) = self.tmp.take() {
+            remove_private_tmp(&tmp);
+            remove_private_tmp(&var_tmp);
+        }
+    }
+}
+
+/// create the world-writable "tmp" in a directory only accessible by root, so the other
+/// users can not reach it from the host
+fn make_private_tmp(dir: &str) -> Result<PathBuf> {
+    let template = CString::new(format!("{dir}/sysmaster-private-XXXXXX")).unwrap();
+    let ptr = template.into_raw();
+    let ret = unsafe { libc::mkdtemp(ptr) };
+    let template = unsafe { CString::from_raw(ptr) };
+    if ret.is_null() {
+        return Err(Errno::last()).context(NixSnafu);
+    }
+
+    let parent = PathBuf::from(OsStr::from_bytes(template.as_bytes()));
+    let tmp = parent.join("tmp");
+    let ret = fs::create_dir(&tmp)
+        .and_then(|_| fs::set_permissions(&tmp, fs::Permissions::from_mode(0o1777)));
+    if let Err(e) = ret {
+        let _ = fs::remove_dir_all(&parent);
+        return Err(e).context(IoSnafu);
+    }
+
+    Ok(tmp)
+}
+
+fn remove_private_tmp(tmp: &Path) {
+    if let Some(parent) = tmp.parent() {
+        if let Err(e) = fs::remove_dir_all(parent) {
+            log::warn!(
+                "Failed to remove the private temporary directory {:?}: {}",
+                parent,
+                e
+            );
+        }
+    }
+}
+
+fn storage_socket() -> Result<(RawFd, RawFd)> {
+    socket::socketpair(
+        AddressFamily::Unix,
+        SockType::Datagram,
+        None,
+        SockFlag::SOCK_CLOEXEC,
+    )
+    .context(NixSnafu)
+}
+
+/// unshare the network, IPC and UTS namespaces, the network and IPC ones are joined
+/// instead if another process of the runtime has created them. The PID namespace is not
+/// supported, only the children forked after unsharing it would be in it, but the manager
+/// watches the spawned process itself.
+pub(super) fn setup_private_namespaces(
+    ns: &ExecNamespace,
+    runtime: Option<&NamespaceRuntime>,
+) -> Result<()> {
+    if ns.private_network {
+        setup_shared_namespace(runtime.map(|rt| rt.net), CloneFlags::CLONE_NEWNET)?;
+    }
+
+    if ns.private_ipc {
+        setup_shared_namespace(runtime.map(|rt| rt.ipc), CloneFlags::CLONE_NEWIPC)?;
+    }
+
+    if ns.protect_hostname {
+        unshare(CloneFlags::CLONE_NEWUTS).context(NixSnafu)?;
+    }
+
+    Ok(())
+}
+
+fn setup_shared_namespace(storage: Option<(RawFd, RawFd)>, nstype: CloneFlags) -> Result<()> {
+    let storage = match storage {
+        Some(v) => v,
+        None => return create_namespace(nstype).map(fd_util::close),
+    };
+
+    // the record lock is owned by the process, so the processes forked from the manager
+    // do not create the namespace at the same time
+    let mut lock = libc::flock {
+        l_type: libc::F_WRLCK as libc::c_short,
+        l_whence: libc::SEEK_SET as libc::c_short,
+        l_start: 0,
+        l_len: 0,
+        l_pid: 0,
+    };
+    fcntl::fcntl(storage.0, FcntlArg::F_SETLKW(&lock)).context(NixSnafu)?;
+
+    let ret = join_or_create_namespace(storage, nstype);
+
+    lock.l_type = libc::F_UNLCK as libc::c_short;
+    if let Err(e) = fcntl::fcntl(storage.0, FcntlArg::F_SETLK(&lock)) {
+        log::warn!("Failed to unlock the namespace storage: {}", e);
+    }
+    ret
+}
+
+fn join_or_create_namespace(storage: (RawFd, RawFd), nstype: CloneFlags) -> Result<()> {
+    let fd = match recv_fd(storage.0)? {
+        Some(fd) => {
+            let ret = sched::setns(fd, nstype).context(NixSnafu);
+            if let Err(e) = ret {
+                // put it back for the others
+                let _ = send_fd(storage.1, fd);
+                fd_util::close(fd);
+                return Err(e);
+            }
+            fd
+        }
+        None => create_namespace(nstype)?,
+    };
+
+    let ret = send_fd(storage.1, fd);
+    fd_util::close(fd);
+    ret
+}
+
+/// unshare the namespace, and return the file descriptor referring to it
+fn create_namespace(nstype: CloneFlags) -> Result<RawFd> {
+    unshare(nstype).context(NixSnafu)?;
+
+    let path = if nstype == CloneFlags::CLONE_NEWNET {
+        loopback_up()?;
+        "/proc/self/ns/net"
+    } else {
+        "/proc/self/ns/ipc"
+    };
+    fcntl::open(path, OFlag::O_RDONLY | OFlag::O_CLOEXEC, Mode::empty()).context(NixSnafu)
+}
+
+fn recv_fd(socket: RawFd) -> Result<Option<RawFd>> {
+    let mut buf = [0u8; 1];
+    let mut iov = [IoSliceMut::new(&mut buf)];
+    let mut cmsg = nix::cmsg_space!([RawFd; 1]);
+    let msg = match socket::recvmsg::<()>(
+        socket,
+        &mut iov,
+        Some(&mut cmsg),
+        MsgFlags::MSG_DONTWAIT | MsgFlags::MSG_CMSG_CLOEXEC,
+    ) {
+        Ok(msg) => msg,
+        Err(Errno::EAGAIN) => return Ok(None),
+        Err(e) => return Err(e).context(NixSnafu),
+    };
+
+    for c in msg.cmsgs() {
+        if let ControlMessageOwned::ScmRights(fds) = c {
+            return Ok(fds.first().copied());
+        }
+    }
+    Ok(None)
+}
+
+fn send_fd(socket: RawFd, fd: RawFd) -> Result<()> {
+    let fds = [fd];
+    let iov = [IoSlice::new(b"x")];
+    socket::sendmsg::<()>(
+        socket,
+        &iov,
+        &[ControlMessage::ScmRights(&fds)],
+        MsgFlags::MSG_DONTWAIT,
+        None,
+    )
+    .context(NixSnafu)
+    .map(drop)
+}
+
+/// the ifreq with the flags member of the union
+#[repr(C)]
+struct IfReqFlags {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    flags: libc::c_short,
+    pad: [u8; 22],
+}
+
+/// a new network namespace has only the loopback device, which is down
+fn loopback_up() -> Result<()> {
+    let fd = socket::socket(
+        AddressFamily::Inet,
+        SockType::Datagram,
+        SockFlag::SOCK_CLOEXEC,
+        None,
+    )
+    .context(NixSnafu)?;
+
+    let mut req = IfReqFlags {
+        name: [0; libc::IFNAMSIZ],
+        flags: 0,
+        pad: [0; 22],
+    };
+    for (i, c) in b"lo".iter().enumerate() {
+        req.name[i] = *c as libc::c_char;
+    }
+
+    let mut ret = unsafe { libc::ioctl(fd, libc::SIOCGIFFLAGS as _, &mut req) };
+    if ret >= 0 && req.flags & libc::IFF_UP as libc::c_short == 0 {
+        req.flags |= libc::IFF_UP as libc::c_short;
+        ret = unsafe { libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &req) };
+    }
+    let ret = Errno::result(ret).context(NixSnafu);
+    fd_util::close(fd);
+    ret.map(drop)
+}
+
+/// unshare the user namespace which maps root and the user of the process to themselves,
+/// so the process has no privilege on the host. The maps can only be written by a process
+/// privileged in the parent namespace, so it is done by a forked helper.
+pub(super) fn setup_private_users(uid: Uid, gid: Gid) -> Result<()> {
+    let (rfd, wfd) = unistd::pipe2(OFlag::O_CLOEXEC).context(NixSnafu)?;
+    let pid = unistd::getpid();
+
+    match unsafe { unistd::fork() } {
+        Ok(ForkResult::Child) => {
+            fd_util::close(wfd);
+            // wait until the parent has unshared the namespace, EOF means it has failed
+            let mut buf = [0u8; 1];
+            let ret = match unistd::read(rfd, &mut buf) {
+                Ok(1) => write_id_maps(pid, uid, gid),
+                _ => Err(Error::Other {
+                    msg: "the user namespace is not created".to_string(),
+                }),
+            };
+            if let Err(e) = ret {
+                log::error!("Failed to write the user namespace maps: {}", e);
+                process::exit(1);
+            }
+            process::exit(0);
+        }
+        Ok(ForkResult::Parent { child }) => {
+            fd_util::close(rfd);
+            let ret = unshare(CloneFlags::CLONE_NEWUSER).context(NixSnafu);
+            if ret.is_ok() {
+                let _ = unistd::write(wfd, b"x");
+            }
+            fd_util::close(wfd);
+
+            let status = wait::waitpid(child, None).context(NixSnafu)?;
+            ret?;
+            match status {
+                WaitStatus::Exited(_, 0) => Ok(()),
+                _ => Err(Error::Other {
+                    msg: format!("the helper of the user namespace exited with {status:?}"),
+                }),
+            }
+        }
+        Err(e) => {
+            fd_util::close(rfd);
+            fd_util::close(wfd);
+            Err(e).context(NixSnafu)
+        }
+    }
+}
+
+/// map root and the id to themselves
+fn id_map(id: u32) -> String {
+    match id {
+        0 => "0 0 1\n".to_string(),
+        id => format!("0 0 1\n{id} {id} 1\n"),
+    }
+}
+
+fn write_id_maps(pid: Pid, uid: Uid, gid: Gid) -> Result<()> {
+    fs::write(format!("/proc/{pid}/uid_map"), id_map(uid.as_raw())).context(IoSnafu)?;
+    fs::write(format!("/proc/{pid}/gid_map"), id_map(gid.as_raw())).context(IoSnafu)
+}
+
+/// unshare the mount namespace and apply the sandboxing, the mounts do not propagate
+/// to the host. The writable paths stay writable in the read-only paths. With RootDirectory=,
+/// the sandboxing applies to the paths in it, and it is pivoted to as the root at last.
+/// PrivateTmp= binds the directories of the runtime, or mounts the tmpfs without one.
+pub(super) fn setup_namespace(
+    ns: &ExecNamespace,
+    writable: &[PathBuf],
+    private_tmp: Option<(PathBuf, PathBuf)>,
+) -> Result<()> {
+    if !ns.needs_mount_namespace() {
+        return Ok(());
+    }
+
+    unshare(CloneFlags::CLONE_NEWNS).context(NixSnafu)?;
+    mount::mount(
+        None::<&str>,
+        "/",
+        None::<&str>,
+        MsFlags::MS_SLAVE | MsFlags::MS_REC,
+        None::<&str>,
+    )
+    .context(NixSnafu)?;
+
+    let root = match &ns.root_directory {
+        Some(root) => {
+            check_exists(root, false)?;
+            // pivot_root needs the new root to be a mount point
+            mount_util::bind_mount(root, root, true).context(UtilSnafu)?;
+            if ns.mount_api_vfs {
+                mount_api_vfs(root)?;
+            }
+            root.as_path()
+        }
+        None => Path::new("/"),
+    };
+
+    let mut read_only: Vec<(PathBuf, bool)> = Vec::new();
+    match ns.protect_system {
+        ProtectSystem::No => {}
+        ProtectSystem::Yes => read_only.extend(ignore_missing(&["/usr", "/boot", "/efi"])),
+        ProtectSystem::Full => read_only.extend(ignore_missing(&["/usr", "/boot", "/efi", "/etc"])),
+        ProtectSystem::Strict => read_only.push((PathBuf::from("/"), false)),
+    }
+
+    let mut inaccessible = ns.inaccessible_paths.clone();
+    match ns.protect_home {
+        ProtectHome::Yes => inaccessible.extend(ignore_missing(&HOME_PATHS)),
+        ProtectHome::ReadOnly => read_only.extend(ignore_missing(&HOME_PATHS)),
+        ProtectHome::No | ProtectHome::Tmpfs => {}
+    }
+    read_only.extend(ns.read_only_paths.iter().cloned());
+
+    // create the nodes before /run may be made read-only
+    if !inaccessible.is_empty() {
+        make_inaccessible_nodes()?;
+    }
+
+    // bind the writable paths onto the same paths in the root, so that they are excluded
+    // as mount points
+    let mut writable_points = Vec::new();
+    for path in writable.iter().filter(|p| p.is_dir()) {
+        let target = in_root(root, path)?;
+        fs::create_dir_all(&target).context(IoSnafu)?;
+        mount_util::bind_mount(path, &target, false).context(UtilSnafu)?;
+        writable_points.push(target);
+    }
+
+    // bound before the read-only paths, so they are excluded as the writable ones
+    if let (true, Some((tmp, var_tmp))) = (ns.private_tmp, &private_tmp) {
+        for (source, path) in [(tmp, "/tmp"), (var_tmp, "/var/tmp")] {
+            let target = in_root(root, Path::new(path))?;
+            if target.is_dir() {
+                mount_util::bind_mount(source, &target, false).context(UtilSnafu)?;
+                writable_points.push(target);
+            }
+        }
+    }
+
+    for bind in &ns.bind_paths {
+        if !check_exists(&bind.source, bind.ignore_missing)? {
+            continue;
+        }
+
+        let target = in_root(root, &bind.destination)?;
+        log::debug!("Binding {:?} to {:?}", bind.source, target);
+        make_mount_point(&bind.source, &target)?;
+        mount_util::bind_mount(&bind.source, &target, bind.recursive).context(UtilSnafu)?;
+        if bind.read_only {
+            mount_util::bind_remount_read_only(&target, &[]).context(UtilSnafu)?;
+        } else {
+            writable_points.push(target);
+        }
+    }
+
+    for (path, ignore) in &read_only {
+        let target = in_root(root, path)?;
+        if !check_exists(&target, *ignore)? {
+            continue;
+        }
+
+        let mut exclude = writable_points.clone();
+        if path == Path::new("/") {
+            for p in API_VFS_PATHS {
+                exclude.push(in_root(root, Path::new(p))?);
+            }
+        }
+        log::debug!("Making {:?} read-only", target);
+        mount_util::bind_remount_read_only(&target, &exclude).context(UtilSnafu)?;
+    }
+
+    if ns.protect_home == ProtectHome::Tmpfs {
+        for path in HOME_PATHS {
+            let path = in_root(root, Path::new(path))?;
+            if path.exists() {
+                mount_util::mount_tmpfs(&path, 0o755, MsFlags::MS_RDONLY).context(UtilSnafu)?;
+            }
+        }
+    }
+
+    for (path, ignore) in &inaccessible {
+        let target = in_root(root, path)?;
+        if !check_exists(&target, *ignore)? {
+            continue;
+        }
+
+        log::debug!("Making {:?} inaccessible", target);
+        make_inaccessible(&target)?;
+    }
+
+    if ns.private_tmp && private_tmp.is_none() {
+        for path in ["/tmp", "/var/tmp"] {
+            let path = in_root(root, Path::new(path))?;
+            if path.is_dir() {
+                mount_util::mount_tmpfs(&path, 0o1777, MsFlags::empty()).context(UtilSnafu)?;
+            }
+        }
+    }
+
+    if ns.root_directory.is_some() {
+        pivot_root(root)?;
+    }
+
+    Ok(())
+}
+
+/// resolve the path in the root directory as a process chrooted in it sees it. The symlinks
+/// are followed relative to the root, so they can not lead out of it, and the paths going
+/// above the root with ".." are refused. The missing components are kept to be created.
+fn in_root(root: &Path, path: &Path) -> Result<PathBuf> {
+    if root == Path::new("/") {
+        return Ok(path.to_path_buf());
+    }
+
+    let mut resolved = PathBuf::new();
+    let mut rest = components_rev(path);
+    let mut symlinks = 0;
+    while let Some(name) = rest.pop() {
+        if name == ".." {
+            if !resolved.pop() {
+                return Err(Error::Other {
+                    msg: format!("{path:?} escapes the root directory {root:?}"),
+                });
+            }
+            continue;
+        }
+
+        let current = resolved.join(&name);
+        match fs::symlink_metadata(root.join(&current)) {
+            Ok(m) if m.file_type().is_symlink() => {
+                symlinks += 1;
+                if symlinks > MAX_SYMLINKS {
+                    return Err(Errno::ELOOP).context(NixSnafu);
+                }
+                let target = fs::read_link(root.join(&current)).context(IoSnafu)?;
+                if target.is_absolute() {
+                    resolved = PathBuf::new();
+                }
+                rest.extend(components_rev(&target));
+            }
+            Ok(_) => resolved = current,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => resolved = current,
+            Err(e) => return Err(e).context(IoSnafu),
+        }
+    }
+
+    Ok(root.join(resolved))
+}
+
+/// the names in the path in the reverse order, with ".." kept
+fn components_rev(path: &Path) -> Vec<OsString> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => None,
+        })
+        .rev()
+        .collect()
+}
+
+/// bind the API file systems of the host into the root directory
+fn mount_api_vfs(root: &Path) -> Result<()> {
+    for path in API_VFS_PATHS.iter().map(Path::new) {
+        let target = in_root(root, path)?;
+        fs::create_dir_all(&target).context(IoSnafu)?;
+        mount_util::bind_mount(path, &target, true).context(UtilSnafu)?;
+    }
+
+    Ok(())
+}
+
+/// create the directory or file to mount the source on, if it does not exist
+fn make_mount_point(source: &Path, target: &Path) -> Result<()> {
+    if target.exists() {
+        return Ok(());
+    }
+
+    if source.is_dir() {
+        return fs::create_dir_all(target).context(IoSnafu);
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).context(IoSnafu)?;
+    }
+    fs::File::create(target).context(IoSnafu).map(drop)
+}
+
+/// make the root directory the root of the mount namespace, and detach the old one
+fn pivot_root(root: &Path) -> Result<()> {
+    unistd::chdir(root).context(NixSnafu)?;
+    // the old root is stacked on the new one, so it can be detached by unmounting "."
+    unistd::pivot_root(".", ".").context(NixSnafu)?;
+    mount::umount2(".", MntFlags::MNT_DETACH).context(NixSnafu)?;
+    unistd::chdir("/").context(NixSnafu)
+}
+
+fn ignore_missing(paths: &[&str]) -> Vec<(PathBuf, bool)> {
+    paths.iter().map(|p| (PathBuf::from(p), true)).collect()
+}
+
+fn check_exists(path: &Path, ignore_missing: bool) -> Result<bool> {
+    if path.exists() {
+        return Ok(true);
+    }
+
+    if ignore_missing {
+        return Ok(false);
+    }
+
+    Err(Error::NotFound {
+        what: format!("namespace path {path:?}"),
+    })
+}
+
+fn make_inaccessible_nodes() -> Result<()> {
+    let dir = Path::new(INACCESSIBLE_DIR);
+    fs::create_dir_all(dir).context(IoSnafu)?;
+
+    let node = dir.join("dir");
+    if !node.exists() {
+        fs::DirBuilder::new()
+            .mode(0o000)
+            .create(&node)
+            .context(IoSnafu)?;
+    }
+
+    let node = dir.join("reg");
+    if !node.exists() {
+        fs::File::create(&node).context(IoSnafu)?;
+    }
+    fs::set_permissions(&node, fs::Permissions::from_mode(0o000)).context(IoSnafu)
+}
+
+fn make_inaccessible(path: &Path) -> Result<()> {
+    let node = if path.is_dir() { "dir" } else { "reg" };
+    let source = Path::new(INACCESSIBLE_DIR).join(node);
+    mount_util::bind_mount(&source, path, false).context(UtilSnafu)?;
+
+    mount::mount(
+        None::<&str>,
+        path,
+        None::<&str>,
+        MsFlags::MS_BIND
+            | MsFlags::MS_REMOUNT
+            | MsFlags::MS_RDONLY
+            | MsFlags::MS_NOSUID
+            | MsFlags::MS_NODEV
+            | MsFlags::MS_NOEXEC,
+        None::<&str>,
+    )
+    .context(NixSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// run f in a forked child, so the namespaces it unshares leave the test process alone
+    fn in_child(f: impl FnOnce() -> bool) -> bool {
+        match unsafe { libc::fork() } {
+            0 => {
+                let ok = f();
+                unsafe { libc::_exit(if ok { 0 } else { 1 }) };
+            }
+            pid => {
+                let mut status = 0;
+                assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+                libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+            }
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = PathBuf::from(format!("/tmp/sysmaster_test_ns_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn ns_ino(fd: RawFd) -> u64 {
+        nix::sys::stat::fstat(fd).unwrap().st_ino
+    }
+
+    fn self_ns_ino(name: &str) -> u64 {
+        let path = format!("/proc/self/ns/{name}");
+        let fd = fcntl::open(
+            path.as_str(),
+            OFlag::O_RDONLY | OFlag::O_CLOEXEC,
+            Mode::empty(),
+        )
+        .unwrap();
+        let ino = ns_ino(fd);
+        fd_util::close(fd);
+        ino
+    }
+
+    /// the inode of the namespace kept in the storage, which is put back
+    fn stored_ns_ino(storage: (RawFd, RawFd)) -> Option<u64> {
+        let fd = recv_fd(storage.0).unwrap()?;
+        let ino = ns_ino(fd);
+        send_fd(storage.1, fd).unwrap();
+        fd_util::close(fd);
+        Some(ino)
+    }
+
+    #[test]
+    fn test_namespace_storage() {
+        let runtime = NamespaceRuntime::new().unwrap();
+        assert!(recv_fd(runtime.net.0).unwrap().is_none());
+
+        // the fd passed through the socket pair refers to the same namespace
+        let fd = fcntl::open(
+            "/proc/self/ns/net",
+            OFlag::O_RDONLY | OFlag::O_CLOEXEC,
+            Mode::empty(),
+        )
+        .unwrap();
+        send_fd(runtime.net.1, fd).unwrap();
+        assert_eq!(stored_ns_ino(runtime.net), Some(ns_ino(fd)));
+        fd_util::close(fd);
+
+        // it stays queued after being peeked
+        assert_eq!(stored_ns_ino(runtime.net), Some(self_ns_ino("net")));
+        assert!(stored_ns_ino(runtime.ipc).is_none());
+    }
+
+    #[test]
+    fn test_shared_namespaces() {
+        if !unistd::getuid().is_root() {
+            return;
+        }
+
+        let runtime = NamespaceRuntime::new().unwrap();
+        let ns = ExecNamespace {
+            private_network: true,
+            private_ipc: true,
+            ..Default::default()
+        };
+
+        // the first process creates the namespaces, which outlive it in the runtime
+        assert!(in_child(
+            || setup_private_namespaces(&ns, Some(&runtime)).is_ok()
+        ));
+        let net = stored_ns_ino(runtime.net).unwrap();
+        let ipc = stored_ns_ino(runtime.ipc).unwrap();
+        assert_ne!(net, self_ns_ino("net"));
+        assert_ne!(ipc, self_ns_ino("ipc"));
+
+        // the later processes join them
+        assert!(in_child(|| {
+            setup_private_namespaces(&ns, Some(&runtime)).is_ok()
+                && self_ns_ino("net") == net
+                && self_ns_ino("ipc") == ipc
+        }));
+
+        // the process without a runtime gets its own
+        assert!(in_child(|| {
+            setup_private_namespaces(&ns, None).is_ok()
+                && self_ns_ino("net") != net
+                && self_ns_ino("ipc") != ipc
+        }));
+    }
+
+    #[test]
+    fn test_private_users() {
+        assert_eq!(id_map(0), "0 0 1\n");
+        assert_eq!(id_map(1000), "0 0 1\n1000 1000 1\n");
+
+        if !unistd::getuid().is_root() {
+            return;
+        }
+
+        let maps = |name: &str| {
+            fs::read_to_string(format!("/proc/self/{name}"))
+                .unwrap_or_default()
+                .lines()
+                .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+                .collect::<Vec<_>>()
+        };
+        let user = self_ns_ino("user");
+        assert!(in_child(|| {
+            setup_private_users(Uid::from_raw(1000), Gid::from_raw(100)).is_ok()
+                && self_ns_ino("user") != user
+                && maps("uid_map") == ["0 0 1", "1000 1000 1"]
+                && maps("gid_map") == ["0 0 1", "100 100 1"]
+        }));
+    }
+
+    #[test]
+    fn test_in_root() {
+        let root = test_dir("in_root");
+        fs::create_dir_all(root.join("etc")).unwrap();
+        std::os::unix::fs::symlink("/etc", root.join("srv")).unwrap();
+        std::os::unix::fs::symlink("srv/foo", root.join("rel")).unwrap();
+        std::os::unix::fs::symlink("../../..", root.join("up")).unwrap();
+        std::os::unix::fs::symlink("loop", root.join("loop")).unwrap();
+
+        // the symlinks are followed in the root
+        assert_eq!(in_root(&root, Path::new("/srv")).unwrap(), root.join("etc"));
+        assert_eq!(
+            in_root(&root, Path::new("/rel/bar")).unwrap(),
+            root.join("etc/foo/bar")
+        );
+        assert_eq!(
+            in_root(&root, Path::new("/missing/../etc")).unwrap(),
+            root.join("etc")
+        );
+        assert_eq!(
+            in_root(Path::new("/"), Path::new("/srv")).unwrap(),
+            Path::new("/srv")
+        );
+
+        // but never out of it
+        assert!(in_root(&root, Path::new("/up")).is_err());
+        assert!(in_root(&root, Path::new("/../etc")).is_err());
+        assert!(in_root(&root, Path::new("/loop")).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_read_only_paths() {
+        if !unistd::getuid().is_root() {
+            return;
+        }
+
+        let dir = test_dir("read_only");
+        let writable = dir.join("writable");
+        fs::create_dir(&writable).unwrap();
+        let ns = ExecNamespace {
+            read_only_paths: vec![(dir.clone(), false)],
+            ..Default::default()
+        };
+
+        assert!(in_child(|| {
+            setup_namespace(&ns, std::slice::from_ref(&writable), None).is_ok()
+                && fs::write(dir.join("file"), "x").map_err(|e| e.raw_os_error())
+                    == Err(Some(libc::EROFS))
+                && fs::write(writable.join("file"), "x").is_ok()
+        }));
+
+        // the host is not affected
+        assert!(fs::write(dir.join("file"), "x").is_ok());
+        assert!(writable.join("file").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_inaccessible_paths() {
+        if !unistd::getuid().is_root() {
+            return;
+        }
+
+        let dir = test_dir("inaccessible");
+        let file = dir.join("file");
+        let sub = dir.join("sub");
+        fs::write(&file, "secret").unwrap();
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("file"), "secret").unwrap();
+        let ns = ExecNamespace {
+            inaccessible_paths: vec![
+                (file.clone(), false),
+                (sub.clone(), false),
+                (dir.join("missing"), true),
+            ],
+            ..Default::default()
+        };
+
+        assert!(in_child(|| {
+            setup_namespace(&ns, &[], None).is_ok()
+                && fs::read_to_string(&file).map_or(true, |s| s.is_empty())
+                && fs::write(&file, "x").is_err()
+                && !sub.join("file").exists()
+        }));
+
+        // the missing path is an error without "-"
+        let ns = ExecNamespace {
+            inaccessible_paths: vec![(dir.join("missing"), false)],
+            ..Default::default()
+        };
+        assert!(in_child(|| setup_namespace(&ns, &[], None).is_err()));
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "secret");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_private_tmp() {
+        if !unistd::getuid().is_root() {
+            return;
+        }
+
+        let runtime = NamespaceRuntime::new().unwrap();
+        assert!(runtime.private_tmp().is_none());
+        runtime.setup_private_tmp().unwrap();
+        let (tmp, var_tmp) = runtime.private_tmp().unwrap();
+        assert_eq!(
+            fs::metadata(&tmp).unwrap().permissions().mode() & 0o7777,
+            0o1777
+        );
+        assert_eq!(
+            fs::metadata(tmp.parent().unwrap())
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o700
+        );
+
+        // created only once for the runtime
+        runtime.setup_private_tmp().unwrap();
+        assert_eq!(runtime.private_tmp(), Some((tmp.clone(), var_tmp.clone())));
+
+        let dir = test_dir("private_tmp");
+        let ns = ExecNamespace {
+            private_tmp: true,
+            ..Default::default()
+        };
+        assert!(in_child(|| {
+            setup_namespace(&ns, &[], runtime.private_tmp()).is_ok()
+                && !dir.exists()
+                && fs::write("/tmp/shared", "x").is_ok()
+                && fs::write("/var/tmp/shared", "x").is_ok()
+        }));
+        assert!(tmp.join("shared").exists());
+        assert!(var_tmp.join("shared").exists());
+
+        // the later processes of the runtime get the same directories
+        assert!(in_child(|| {
+            setup_namespace(&ns, &[], runtime.private_tmp()).is_ok()
+                && Path::new("/tmp/shared").exists()
+        }));
+
+        // removed with the runtime
+        drop(runtime);
+        assert!(!tmp.exists());
+        assert!(!var_tmp.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
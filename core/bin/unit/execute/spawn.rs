@@ -11,26 +11,74 @@
 // See the Mulan PSL v2 for more details.
 
 use super::super::entry::Unit;
+use super::collector::LogCollector;
+use super::namespace::{
+    setup_namespace, setup_private_namespaces, setup_private_users, NamespaceRuntime,
+};
 use basic::fd_util;
-use nix::fcntl::FcntlArg;
+use event::Events;
+use nix::fcntl::{self, FcntlArg, OFlag};
+use nix::sys::resource::Resource;
 use nix::sys::signal::{pthread_sigmask, SigmaskHow};
 use nix::sys::signalfd::SigSet;
 use nix::sys::stat::Mode;
 use nix::unistd::{self, setresgid, setresuid, ForkResult, Gid, Group, Pid, Uid, User};
-use regex::Regex;
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::rc::Rc;
 use sysmaster::error::*;
-use sysmaster::exec::{ExecCommand, ExecContext, ExecFlags, ExecParameters};
+use sysmaster::exec::{
+    ExecCapabilities, ExecCommand, ExecContext, ExecDirectory, ExecDirectoryType, ExecFlags,
+    ExecInput, ExecOutput, ExecParameters, Rlimit,
+};
 use walkdir::DirEntry;
 use walkdir::WalkDir;
 
-pub(in crate::unit) struct ExecSpawn;
+const TTY_PATH: &str = "/dev/console";
+
+pub(in crate::unit) struct ExecSpawn {
+    log: LogCollector,
+    default_rlimits: RefCell<HashMap<Resource, Rlimit>>,
+    runtimes: RefCell<HashMap<String, Rc<NamespaceRuntime>>>,
+}
 
 impl ExecSpawn {
-    pub(in crate::unit) fn new() -> ExecSpawn {
-        ExecSpawn
+    pub(in crate::unit) fn new(eventr: &Rc<Events>) -> ExecSpawn {
+        ExecSpawn {
+            log: LogCollector::new(eventr),
+            default_rlimits: RefCell::new(HashMap::new()),
+            runtimes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// set the manager-wide resource limits, which are overridden by the ones of the unit
+    pub(in crate::unit) fn set_default_rlimits(&self, rlimits: Vec<(Resource, Rlimit)>) {
+        *self.default_rlimits.borrow_mut() = rlimits.into_iter().collect();
+    }
+
+    /// return the namespace runtime of the unit, which is shared with the units in joins
+    /// if any of them has one
+    fn namespace_runtime(&self, unit: &str, joins: &[String]) -> Result<Rc<NamespaceRuntime>> {
+        let mut runtimes = self.runtimes.borrow_mut();
+        if let Some(runtime) = runtimes.get(unit) {
+            return Ok(Rc::clone(runtime));
+        }
+
+        let runtime = match joins.iter().find_map(|j| runtimes.get(j)) {
+            Some(runtime) => Rc::clone(runtime),
+            None => Rc::new(NamespaceRuntime::new()?),
+        };
+        runtimes.insert(unit.to_string(), Rc::clone(&runtime));
+        Ok(runtime)
+    }
+
+    /// release the namespace runtime of the unit, the namespaces are destroyed when no
+    /// joined unit holds them
+    pub(in crate::unit) fn release_namespace_runtime(&self, unit: &str) {
+        self.runtimes.borrow_mut().remove(unit);
     }
 
     pub(in crate::unit) fn spawn(
@@ -39,12 +87,47 @@ impl ExecSpawn {
         cmdline: &ExecCommand,
         params: &ExecParameters,
         ctx: Rc<ExecContext>,
+        joins: &[String],
     ) -> Result<Pid> {
+        // load the environment files before forking, so a missing or broken file fails the start
+        if let Err(e) = ctx.load_env_from_file() {
+            log::error!("Failed to load environment files of {}: {}", unit.id(), e);
+            return Err(e);
+        }
+
+        // the pipe of the log target is created by the manager, which reads the other end
+        let (stdout, stderr) = stdio_outputs(&ctx);
+        let log_pipe = if stdout == ExecOutput::Log || stderr == ExecOutput::Log {
+            Some(self.log.open_pipe(unit.id())?)
+        } else {
+            None
+        };
+
+        let mut rlimits = self.default_rlimits.borrow().clone();
+        rlimits.extend(ctx.rlimits());
+
+        let ns = ctx.namespace();
+        let runtime = match ns.needs_shared_namespace() {
+            true => Some(self.namespace_runtime(unit.id(), joins)?),
+            false => None,
+        };
+        if let (true, Some(rt)) = (ns.private_tmp, &runtime) {
+            rt.setup_private_tmp()?;
+        }
+
         let ret = unsafe { unistd::fork() };
+        if let Some((_, wfd)) = log_pipe {
+            if !matches!(ret, Ok(ForkResult::Child)) {
+                fd_util::close(wfd);
+            }
+        }
 
         match ret {
             Ok(ForkResult::Parent { child }) => {
                 log::debug!("child pid is :{}", child);
+                if let Some((rfd, _)) = log_pipe {
+                    self.log.set_pipe_pid(rfd, child);
+                }
                 cgroup::cg_attach(child, &unit.cg_path()).context(CgroupSnafu)?;
                 Ok(child)
             }
@@ -53,7 +136,15 @@ impl ExecSpawn {
                 if pthread_sigmask(SigmaskHow::SIG_SETMASK, Some(&set), None).is_err() {
                     log::info!("Failed to reset the sigmask of child process, ignoring.");
                 }
-                exec_child(unit, cmdline, params, ctx);
+                exec_child(
+                    unit,
+                    cmdline,
+                    params,
+                    ctx,
+                    &rlimits,
+                    runtime.as_deref(),
+                    log_pipe.map(|(_, wfd)| wfd),
+                );
                 process::exit(0);
             }
             Err(_e) => Err(Error::SpawnError),
@@ -61,10 +152,101 @@ impl ExecSpawn {
     }
 }
 
+/// return the standard output and error that take effect, the standard output only
+/// inherits the standard input if it is a terminal or a socket, otherwise it goes to the log.
+fn stdio_outputs(ctx: &ExecContext) -> (ExecOutput, ExecOutput) {
+    let stdout = match ctx.std_output() {
+        ExecOutput::Inherit => match ctx.std_input() {
+            ExecInput::Tty | ExecInput::Socket => ExecOutput::Inherit,
+            _ => ExecOutput::Log,
+        },
+        o => o,
+    };
+
+    (stdout, ctx.std_error())
+}
+
+fn open_stdio(path: &Path, flags: OFlag) -> Result<RawFd> {
+    fcntl::open(
+        path,
+        flags | OFlag::O_CLOEXEC,
+        Mode::from_bits_truncate(0o644),
+    )
+    .context(NixSnafu)
+}
+
+fn stdio_socket(params: &ExecParameters) -> Result<RawFd> {
+    match params.fds().first() {
+        Some(fd) => Ok(*fd),
+        None => {
+            log::error!("Standard input or output is socket, but no socket is passed");
+            Err(Error::InvalidData)
+        }
+    }
+}
+
+fn open_input(input: &ExecInput, params: &ExecParameters) -> Result<RawFd> {
+    match input {
+        ExecInput::Null => open_stdio(Path::new("/dev/null"), OFlag::O_RDONLY),
+        ExecInput::Tty => open_stdio(Path::new(TTY_PATH), OFlag::O_RDWR | OFlag::O_NOCTTY),
+        ExecInput::Socket => stdio_socket(params),
+        ExecInput::File(path) => open_stdio(path, OFlag::O_RDONLY),
+    }
+}
+
+fn open_output(
+    output: &ExecOutput,
+    fileno: RawFd,
+    params: &ExecParameters,
+    log_fd: Option<RawFd>,
+) -> Result<RawFd> {
+    match output {
+        // stdout duplicates stdin, and stderr duplicates stdout
+        ExecOutput::Inherit => match fileno {
+            libc::STDERR_FILENO => Ok(libc::STDOUT_FILENO),
+            _ => Ok(libc::STDIN_FILENO),
+        },
+        ExecOutput::Null => open_stdio(Path::new("/dev/null"), OFlag::O_WRONLY),
+        ExecOutput::Tty => open_stdio(Path::new(TTY_PATH), OFlag::O_WRONLY | OFlag::O_NOCTTY),
+        ExecOutput::Log => log_fd.ok_or(Error::InvalidData),
+        ExecOutput::Socket => stdio_socket(params),
+        ExecOutput::File(path) => open_stdio(path, OFlag::O_WRONLY | OFlag::O_CREAT),
+        ExecOutput::Append(path) => {
+            open_stdio(path, OFlag::O_WRONLY | OFlag::O_CREAT | OFlag::O_APPEND)
+        }
+        ExecOutput::Truncate(path) => {
+            open_stdio(path, OFlag::O_WRONLY | OFlag::O_CREAT | OFlag::O_TRUNC)
+        }
+    }
+}
+
+fn dup_stdio(fd: RawFd, fileno: RawFd) -> Result<()> {
+    if fd == fileno {
+        return fd_util::fd_cloexec(fd, false).map_err(|e| e.into());
+    }
+
+    // dup2 clears the close-on-exec flag of the new fd
+    unistd::dup2(fd, fileno).context(NixSnafu)?;
+    Ok(())
+}
+
+fn apply_stdio(ctx: &ExecContext, params: &ExecParameters, log_fd: Option<RawFd>) -> Result<()> {
+    let stdin = open_input(&ctx.std_input(), params)?;
+    dup_stdio(stdin, libc::STDIN_FILENO)?;
+
+    let (stdout, stderr) = stdio_outputs(ctx);
+    let fd = open_output(&stdout, libc::STDOUT_FILENO, params, log_fd)?;
+    dup_stdio(fd, libc::STDOUT_FILENO)?;
+
+    let fd = open_output(&stderr, libc::STDERR_FILENO, params, log_fd)?;
+    dup_stdio(fd, libc::STDERR_FILENO)
+}
+
 fn apply_user_and_group(
     user: Option<User>,
     group: Option<Group>,
     params: &ExecParameters,
+    caps: &ExecCapabilities,
 ) -> Result<()> {
     let user = match user {
         // ExecParameters.add_user() has already assigned valid user if the configuration is correct
@@ -89,9 +271,11 @@ fn apply_user_and_group(
     // Set environment
     params.add_env("LOGNAME", user.name.clone());
     params.add_env("USER", user.name.clone());
-    // Set user
+    // Set user, the ambient capabilities need the permitted ones kept across it
     log::debug!("Setting process user to {}", user.name);
-    setresuid(user.uid, user.uid, user.uid).context(NixSnafu)
+    caps.keep_caps().context(NixSnafu)?;
+    setresuid(user.uid, user.uid, user.uid).context(NixSnafu)?;
+    caps.raise_ambient().context(NixSnafu)
 }
 
 fn apply_working_directory(working_directory: Option<PathBuf>) -> Result<()> {
@@ -115,14 +299,130 @@ fn apply_umask(umask: Option<Mode>) -> Result<()> {
     Ok(())
 }
 
-fn exec_child(unit: &Unit, cmdline: &ExecCommand, params: &ExecParameters, ctx: Rc<ExecContext>) {
+/// pass the managed directories to the process through the environment variables, return
+/// all the directories. They are created by the service once per start, before the first
+/// command is spawned.
+fn export_exec_directories(
+    directories: &HashMap<ExecDirectoryType, ExecDirectory>,
+    params: &ExecParameters,
+) -> Vec<PathBuf> {
+    let mut all = Vec::new();
+    for (dir_type, directory) in directories {
+        if directory.paths.is_empty() {
+            continue;
+        }
+
+        let paths = directory.full_paths(*dir_type);
+        let value = paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join(":");
+        params.add_env(dir_type.env_name(), value);
+        all.extend(paths);
+    }
+
+    all
+}
+
+fn apply_rlimits(rlimits: &HashMap<Resource, Rlimit>) -> Result<()> {
+    for (resource, rlimit) in rlimits {
+        log::debug!("Setting resource limit {:?} to {:?}", resource, rlimit);
+        rlimit.apply(*resource).context(NixSnafu)?;
+    }
+    Ok(())
+}
+
+fn exec_child(
+    unit: &Unit,
+    cmdline: &ExecCommand,
+    params: &ExecParameters,
+    ctx: Rc<ExecContext>,
+    rlimits: &HashMap<Resource, Rlimit>,
+    runtime: Option<&NamespaceRuntime>,
+    log_fd: Option<RawFd>,
+) {
     log::debug!("exec context params: {:?}", ctx.envs());
 
-    if let Err(e) = apply_user_and_group(params.get_user(), params.get_group(), params) {
+    if let Err(e) = apply_stdio(&ctx, params, log_fd) {
+        log::error!(
+            "Failed to set up standard input and output: {}",
+            e.to_string()
+        );
+        return;
+    }
+
+    // raising the hard limits needs the privilege, so do it before changing the user
+    if let Err(e) = apply_rlimits(rlimits) {
+        log::error!("Failed to apply resource limits: {}", e.to_string());
+        return;
+    }
+
+    // a negative nice level and the realtime policies also need the privilege
+    if let Err(e) = ctx.scheduling().apply() {
+        log::error!("Failed to apply the scheduling settings: {}", e.to_string());
+        return;
+    }
+
+    let directories = export_exec_directories(&ctx.directories(), params);
+
+    // the fully privileged command ("+") runs without any sandboxing
+    let sandboxing = cmdline.apply_sandboxing();
+    let ns = ctx.namespace();
+    if sandboxing {
+        if let Err(e) = setup_private_namespaces(&ns, runtime) {
+            log::error!("Failed to set up private namespaces: {}", e.to_string());
+            return;
+        }
+
+        // joining the namespaces owned by the host needs the privilege, so do it before this
+        if ns.private_users {
+            let uid = params.get_user().map_or(Uid::from_raw(0), |u| u.uid);
+            let gid = params.get_group().map_or(Gid::from_raw(0), |g| g.gid);
+            if let Err(e) = setup_private_users(uid, gid) {
+                log::error!("Failed to set up the user namespace: {}", e.to_string());
+                return;
+            }
+        }
+
+        let private_tmp = runtime.and_then(|rt| rt.private_tmp());
+        if let Err(e) = setup_namespace(&ns, &directories, private_tmp) {
+            log::error!("Failed to set up mount namespacing: {}", e.to_string());
+            return;
+        }
+    }
+
+    // dropping from the bounding set needs CAP_SETPCAP, so do it before changing the user
+    let caps = ctx.capabilities();
+    if sandboxing {
+        if let Err(e) = caps.drop_bounding_set() {
+            log::error!(
+                "Failed to drop the capability bounding set: {}",
+                e.to_string()
+            );
+            return;
+        }
+    }
+
+    if !cmdline.apply_credentials() {
+        log::debug!(
+            "command {} runs without changing credentials",
+            cmdline.path()
+        );
+    } else if let Err(e) =
+        apply_user_and_group(params.get_user(), params.get_group(), params, &caps)
+    {
         log::error!("Failed to apply user or group: {}", e.to_string());
         return;
     }
 
+    if sandboxing {
+        if let Err(e) = caps.apply_no_new_privileges() {
+            log::error!("Failed to set no_new_privs: {}", e.to_string());
+            return;
+        }
+    }
+
     if let Err(e) = apply_working_directory(params.get_working_directory()) {
         log::error!("Failed to apply working directory: {}", e.to_string());
         return;
@@ -133,11 +433,6 @@ fn exec_child(unit: &Unit, cmdline: &ExecCommand, params: &ExecParameters, ctx:
         return;
     }
 
-    if let Err(e) = ctx.load_env_from_file() {
-        log::error!("{}", e);
-        return;
-    }
-
     for (key, value) in ctx.envs() {
         params.add_env(&key, value.to_string());
     }
@@ -179,6 +474,15 @@ fn exec_child(unit: &Unit, cmdline: &ExecCommand, params: &ExecParameters, ctx:
     }
 
     log::debug!("exec child envs to execve is: {:?}", envs_cstr);
+
+    // the filter may deny the system calls above, so install it right before execve
+    if sandboxing {
+        if let Err(e) = ctx.syscall_filter().install() {
+            log::error!("Failed to install the system call filter: {}", e);
+            return;
+        }
+    }
+
     match unistd::execve(&cmd, &cstr_args, &envs_cstr) {
         Ok(_) => {
             log::debug!("execv returned Ok()");
@@ -197,35 +501,13 @@ fn build_run_args(
     env: &ExecParameters,
 ) -> (std::ffi::CString, Vec<std::ffi::CString>) {
     let cmd = std::ffi::CString::new(cmdline.path().clone()).unwrap();
-    let exec_name = std::ffi::CString::new(cmdline.path().clone()).unwrap();
+    let exec_name = std::ffi::CString::new(cmdline.argv0().clone()).unwrap();
 
     let mut args = Vec::new();
     args.push(exec_name);
 
-    let var_regex = Regex::new(r"(\$[A-Z_]+)|(\$\{[A-Z_]+\})").unwrap();
-    for arg in cmdline.argv() {
-        let cap = var_regex.captures(arg);
-        if let Some(cap) = cap {
-            let match_result = {
-                if let Some(mat) = cap.get(1) {
-                    Some(mat.as_str())
-                } else {
-                    cap.get(2).map(|mat| mat.as_str())
-                }
-            };
-
-            if let Some(val) = match_result {
-                let v = val.trim_matches('$').trim_matches('{').trim_matches('}');
-                if let Some(target) = env.get_env(v) {
-                    args.push(
-                        std::ffi::CString::new(var_regex.replace(arg, target).to_string()).unwrap(),
-                    );
-                };
-            }
-            continue;
-        }
-
-        args.push(std::ffi::CString::new(arg.as_str()).unwrap())
+    for arg in cmdline.expand_argv(|key| env.get_env(key)) {
+        args.push(std::ffi::CString::new(arg).unwrap());
     }
 
     (cmd, args)
@@ -239,6 +521,13 @@ fn build_environment(_unit: &Unit, ep: &ExecParameters) -> Vec<std::ffi::CString
         envs.push(std::ffi::CString::new(format!("LISTEN_PID={}", nix::unistd::getpid())).unwrap());
 
         envs.push(std::ffi::CString::new(format!("LISTEN_FDS={fds}")).unwrap());
+
+        let fd_names = ep.fd_names();
+        if fd_names.len() == fds {
+            envs.push(
+                std::ffi::CString::new(format!("LISTEN_FDNAMES={}", fd_names.join(":"))).unwrap(),
+            );
+        }
     }
 
     if ep.exec_flags().contains(ExecFlags::SOFT_WATCHDOG) && ep.watchdog_usec() > 0 {
@@ -348,3 +637,81 @@ fn flags_fds(fds: &mut Vec<i32>) -> bool {
 
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sysmaster::exec::ExecNamespace;
+
+    #[test]
+    fn test_namespace_runtime_joins() {
+        let spawn = ExecSpawn::new(&Rc::new(Events::new().unwrap()));
+        let foo = spawn.namespace_runtime("foo.service", &[]).unwrap();
+        let bar = spawn
+            .namespace_runtime("bar.service", &["foo.service".to_string()])
+            .unwrap();
+        let other = spawn.namespace_runtime("other.service", &[]).unwrap();
+        assert!(Rc::ptr_eq(&foo, &bar));
+        assert!(!Rc::ptr_eq(&foo, &other));
+
+        // the units joining the namespaces share the private temporary directories too
+        foo.setup_private_tmp().unwrap();
+        bar.setup_private_tmp().unwrap();
+        let (tmp, _) = foo.private_tmp().unwrap();
+        assert_eq!(bar.private_tmp(), foo.private_tmp());
+        assert!(other.private_tmp().is_none());
+
+        // kept until the last unit releases it
+        drop((foo, bar, other));
+        spawn.release_namespace_runtime("foo.service");
+        assert!(tmp.exists());
+        spawn.release_namespace_runtime("bar.service");
+        assert!(!tmp.exists());
+    }
+
+    #[test]
+    fn test_namespace_runtime_joins_network() {
+        if !unistd::getuid().is_root() {
+            return;
+        }
+
+        let spawn = ExecSpawn::new(&Rc::new(Events::new().unwrap()));
+        let foo = spawn.namespace_runtime("foo.service", &[]).unwrap();
+        let bar = spawn
+            .namespace_runtime("bar.service", &["foo.service".to_string()])
+            .unwrap();
+        let ns = ExecNamespace {
+            private_network: true,
+            ..Default::default()
+        };
+
+        // the child reports the network namespace it is in through the pipe
+        let net_of = |runtime: &NamespaceRuntime| {
+            let (rfd, wfd) = unistd::pipe2(OFlag::O_CLOEXEC).unwrap();
+            match unsafe { unistd::fork() }.unwrap() {
+                ForkResult::Child => {
+                    if setup_private_namespaces(&ns, Some(runtime)).is_ok() {
+                        let link = std::fs::read_link("/proc/self/ns/net").unwrap_or_default();
+                        let _ = unistd::write(wfd, link.to_string_lossy().as_bytes());
+                    }
+                    unsafe { libc::_exit(0) };
+                }
+                ForkResult::Parent { child } => {
+                    fd_util::close(wfd);
+                    let mut buf = [0u8; 64];
+                    let len = unistd::read(rfd, &mut buf).unwrap_or(0);
+                    fd_util::close(rfd);
+                    nix::sys::wait::waitpid(child, None).unwrap();
+                    String::from_utf8_lossy(&buf[..len]).to_string()
+                }
+            }
+        };
+
+        let host = std::fs::read_link("/proc/self/ns/net").unwrap();
+        let net = net_of(&foo);
+        assert!(net.starts_with("net:"));
+        assert_ne!(net, host.to_string_lossy());
+        // the unit joining the namespaces of foo.service gets the same network namespace
+        assert_eq!(net_of(&bar), net);
+    }
+}
@@ -22,7 +22,7 @@
 use super::super::job::{JobAffect, JobConf, JobKind, JobManager};
 use super::datastore::UnitDb;
 use super::entry::{StartLimitResult, Unit, UnitEmergencyAction, UnitX};
-use super::execute::ExecSpawn;
+use super::execute::{self, ExecSpawn};
 use super::notify::NotifyManager;
 use super::rentry::{JobMode, UnitLoadState, UnitRe};
 use super::runtime::UnitRT;
@@ -40,18 +40,22 @@ use basic::proc_cmdline::get_process_cmdline;
 use basic::process_util;
 use basic::show_table::ShowTable;
 use event::Events;
+use nix::sys::resource::Resource;
 use nix::unistd::Pid;
 use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::path::PathBuf;
 use std::rc::Rc;
 use sysmaster::error::*;
-use sysmaster::exec::ExecParameters;
 use sysmaster::exec::{ExecCommand, ExecContext};
+use sysmaster::exec::{ExecParameters, Rlimit};
 use sysmaster::rel::{ReStation, ReStationKind, ReliLastFrame, Reliability};
 use sysmaster::unit::{UmIf, UnitActiveState, UnitDependencyMask, UnitStatus, UnitType};
 use unit_submanager::UnitSubManagers;
 
+/// the number of the log lines shown in the unit status
+const STATUS_LOG_LINES: usize = 10;
+
 //#[derive(Debug)]
 pub(crate) struct UnitManagerX {
     dm: Rc<DataManager>,
@@ -102,6 +106,10 @@ impl UnitManagerX {
         self.data.register_ex();
     }
 
+    pub(crate) fn set_default_rlimits(&self, rlimits: Vec<(Resource, Rlimit)>) {
+        self.data.exec.set_default_rlimits(rlimits);
+    }
+
     pub(crate) fn entry_clear(&self) {
         self.dm.entry_clear();
         self.data.entry_clear();
@@ -144,6 +152,16 @@ impl UnitManagerX {
         self.data.get_all_units()
     }
 
+    pub(crate) fn get_unit_logs(
+        &self,
+        name: &str,
+        lines: u32,
+        follow: bool,
+        cursor: u64,
+    ) -> Result<(String, u64)> {
+        self.data.get_unit_logs(name, lines, follow, cursor)
+    }
+
     pub(crate) fn child_sigchld_enable(&self, enable: bool) -> i32 {
         self.data.sigchld.enable(enable)
     }
@@ -320,6 +338,10 @@ impl UmIf for UnitManager {
     fn has_stop_job(&self, name: &str) -> bool {
         self.has_stop_job(name)
     }
+
+    fn has_start_job(&self, name: &str) -> bool {
+        self.has_start_job(name)
+    }
     /// check the unit that will be triggered by {name} is in active or activating state
     fn relation_active_or_pending(&self, name: &str) -> bool {
         self.relation_active_or_pending(name)
@@ -355,9 +377,10 @@ impl UmIf for UnitManager {
         params: &ExecParameters,
         ctx: Rc<ExecContext>,
     ) -> Result<Pid> {
+        let joins = self.get_dependency_list(unit, UnitRelationAtom::UnitAtomJoinsNameSpaceOf);
         let unit = self.units_get(unit);
         if let Some(u) = unit {
-            self.exec.spawn(&u, cmdline, params, ctx)
+            self.exec.spawn(&u, cmdline, params, ctx, &joins)
         } else {
             Err(Error::SpawnError)
         }
@@ -379,10 +402,17 @@ impl UmIf for UnitManager {
         self.same_unit_with_pid(unit, pid)
     }
 
-    fn collect_socket_fds(&self, name: &str) -> Vec<i32> {
+    fn collect_socket_fds(&self, name: &str) -> Vec<(i32, String)> {
         self.collect_socket_fds(name)
     }
 
+    fn unit_set_socket_fd(&self, name: &str, fd: i32) -> Result<()> {
+        match self.db.units_get(name) {
+            Some(unit) => unit.set_socket_fd(fd),
+            None => Err(Error::UnitActionENoent),
+        }
+    }
+
     fn get_dependency_list(&self, _unit_name: &str, _atom: UnitRelationAtom) -> Vec<String> {
         self.get_dependency_list(_unit_name, _atom)
     }
@@ -437,10 +467,32 @@ impl UmIf for UnitManager {
     fn restart_unit(&self, name: &str) -> Result<()> {
         self.restart_unit(name)
     }
+
+    fn unload_unit(&self, name: &str) -> Result<()> {
+        self.unload_unit(name)
+    }
 }
 
 /// the declaration "pub(self)" is for identification only.
 impl UnitManager {
+    /// unload the unit if it's inactive or failed and has no job
+    fn unload_unit(&self, name: &str) -> Result<()> {
+        let unit = match self.db.units_get(name) {
+            Some(unit) => unit,
+            None => return Err(Error::UnitActionENoent),
+        };
+
+        if !unit.current_active_state().is_inactive_or_failed()
+            || self.jm.has_start_like_job(&unit)
+            || self.jm.has_stop_job(&unit)
+        {
+            return Err(Error::UnitActionEBusy);
+        }
+
+        self.load.unload_unit(&unit);
+        Ok(())
+    }
+
     /// add pid and its correspond unit to
     fn child_watch_pid(&self, id: &str, pid: Pid) {
         self.db.child_add_watch_pid(id, pid)
@@ -561,6 +613,17 @@ impl UnitManager {
         self.jm.has_stop_job(&u)
     }
 
+    /// check if there is already a start job in process
+    fn has_start_job(&self, name: &str) -> bool {
+        let u = if let Some(unit) = self.db.units_get(name) {
+            unit
+        } else {
+            return false;
+        };
+
+        self.jm.has_start_like_job(&u)
+    }
+
     ///
     pub fn unit_emergency_action(&self, action: UnitEmergencyAction, reason: String) {
         if action == UnitEmergencyAction::None {
@@ -635,8 +698,9 @@ impl UnitManager {
         }
     }
 
-    /// return the fds that trigger the unit {name};
-    fn collect_socket_fds(&self, name: &str) -> Vec<i32> {
+    /// return the fds that trigger the unit {name} and their names, the name
+    /// is the socket unit name if FileDescriptorName is not configured;
+    fn collect_socket_fds(&self, name: &str) -> Vec<(i32, String)> {
         let deps = self.db.dep_gets(name, UnitRelations::UnitTriggeredBy);
         let mut fds = Vec::new();
         for dep in deps.iter() {
@@ -644,7 +708,12 @@ impl UnitManager {
                 continue;
             }
 
-            fds.extend(dep.collect_fds())
+            let fd_name = dep.fd_name().unwrap_or_else(|| dep.id().to_string());
+            fds.extend(
+                dep.collect_fds()
+                    .into_iter()
+                    .map(|fd| (fd, fd_name.clone())),
+            )
         }
 
         fds
@@ -857,9 +926,33 @@ impl UnitManager {
             self.get_unit_cgroup_path(unit.clone()),
             self.get_unit_status_pids(unit.clone()),
             error_code,
+            execute::log_tail(name, STATUS_LOG_LINES),
+            unit.get_subunit_result(),
         ))
     }
 
+    /// return the log of the unit and the cursor of its end. If follow is true,
+    /// return the log after the cursor, otherwise return the last lines, 0 for all.
+    pub(self) fn get_unit_logs(
+        &self,
+        name: &str,
+        lines: u32,
+        follow: bool,
+        cursor: u64,
+    ) -> Result<(String, u64)> {
+        if follow {
+            return execute::log_read(name, cursor);
+        }
+
+        let cursor = execute::log_cursor(name);
+        let lines = if lines == 0 {
+            usize::MAX
+        } else {
+            lines as usize
+        };
+        Ok((execute::log_tail(name, lines).join("\n"), cursor))
+    }
+
     pub(self) fn get_all_units(&self) -> Result<String> {
         let mut list_units_table = ShowTable::new();
         list_units_table.add_line(vec!["UNIT", "LOAD", "ACTIVE", "SUB", "DESCRIPTION"]);
@@ -911,7 +1004,7 @@ impl UnitManager {
             db: Rc::clone(&_db),
             rt: Rc::clone(&_rt),
             jm: Rc::clone(&_jm),
-            exec: ExecSpawn::new(),
+            exec: ExecSpawn::new(eventr),
             sigchld: Sigchld::new(eventr, relir, &_db, &_jm),
             notify: NotifyManager::new(eventr, relir, &_rentry, &_db, &_jm),
             sms: UnitSubManagers::new(relir),
@@ -978,6 +1071,10 @@ impl UnitManager {
             );
         }
 
+        if !state.os.is_inactive_or_failed() && state.ns.is_inactive_or_failed() {
+            self.exec.release_namespace_runtime(source);
+        }
+
         if let Err(_e) = self.jm.try_finish(&unitx, state.os, state.ns, state.flags) {
             // debug
         }
@@ -989,7 +1086,7 @@ impl UnitManager {
     }
 
     fn remove_states(&self, _source: &str) {
-        todo!();
+        // the unit is unloaded, nothing to do
     }
 
     fn insert_start_limit_res(&self, source: &str, start_limit_res: &StartLimitResult) {
@@ -1394,6 +1491,23 @@ mod tests {
         assert!(confilict_unit.is_ok());
     }
 
+    #[test]
+    fn test_service_unit_unload() {
+        let dm = init_dm_for_test();
+        let unit_name = String::from("config.service");
+        if dm.2.load_unitx(&unit_name).is_none() {
+            println!("test unit unload, not found unit: {unit_name}");
+            return;
+        }
+
+        assert!(dm.2.unload_unit(&unit_name).is_ok());
+        assert!(dm.2.db.units_get(&unit_name).is_none());
+        assert!(matches!(
+            dm.2.unload_unit(&unit_name),
+            Err(Error::UnitActionENoent)
+        ));
+    }
+
     #[test]
     fn test_units_load() {
         let dm = init_dm_for_test();
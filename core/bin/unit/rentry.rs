@@ -161,6 +161,9 @@ pub(crate) struct UeConfigUnit {
     #[config(deserialize_with = Vec::<String>::deserialize_with)]
     #[config(default = "")]
     pub Conflicts: Vec<String>,
+    #[config(deserialize_with = Vec::<String>::deserialize_with)]
+    #[config(default = "")]
+    pub JoinsNamespaceOf: Vec<String>,
     #[config(default = "")]
     pub ConditionFileNotEmpty: String,
     #[config(default = "")]
@@ -207,6 +210,8 @@ pub struct UeConfigInstall {
     #[config(deserialize_with = Vec::<String>::deserialize_with)]
     #[config(default = "")]
     pub Also: Vec<String>,
+    #[config(default = "")]
+    pub DefaultInstance: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -336,11 +341,21 @@ impl UnitRe {
         self.base.insert(String::from(unit_id), u_base);
     }
 
-    #[allow(dead_code)]
     pub(super) fn base_remove(&self, unit_id: &String) {
         self.base.remove(unit_id);
     }
 
+    /// remove all the entries of the unit when it's unloaded
+    pub(super) fn unit_remove(&self, unit_id: &String) {
+        self.dep_remove(unit_id);
+        self.pps_remove(unit_id);
+        self.child_remove(unit_id);
+        self.cgroup_remove(unit_id);
+        self.conf_remove(unit_id);
+        self.load_remove(unit_id);
+        self.base_remove(unit_id);
+    }
+
     pub(super) fn base_get(&self, unit_id: &String) -> Option<UnitType> {
         let u_base = self.base.get(unit_id);
         u_base.map(|b| b.unit_type)
@@ -357,7 +372,6 @@ impl UnitRe {
         self.load.insert(unit_id.clone(), u_load);
     }
 
-    #[allow(dead_code)]
     pub(super) fn load_remove(&self, unit_id: &String) {
         self.load.remove(unit_id);
     }
@@ -379,7 +393,6 @@ impl UnitRe {
         self.conf.insert(unit_id.clone(), u_conf);
     }
 
-    #[allow(dead_code)]
     pub(super) fn conf_remove(&self, unit_id: &String) {
         self.conf.remove(unit_id);
     }
@@ -396,7 +409,6 @@ impl UnitRe {
         self.cgroup.insert(unit_id.clone(), u_cgroup);
     }
 
-    #[allow(dead_code)]
     pub(super) fn cgroup_remove(&self, unit_id: &String) {
         self.cgroup.remove(unit_id);
     }
@@ -413,7 +425,6 @@ impl UnitRe {
         self.child.insert(unit_id.clone(), u_child);
     }
 
-    #[allow(dead_code)]
     pub(super) fn child_remove(&self, unit_id: &String) {
         self.child.remove(unit_id);
     }
@@ -455,7 +466,6 @@ impl UnitRe {
         self.pps.insert(unit_id.clone(), pps);
     }
 
-    #[allow(dead_code)]
     pub(super) fn pps_remove(&self, unit_id: &String) {
         self.pps.remove(unit_id);
     }
@@ -477,7 +487,6 @@ impl UnitRe {
         self.dep.insert(unit_id.clone(), ud_config);
     }
 
-    #[allow(dead_code)]
     pub(super) fn dep_remove(&self, unit_id: &String) {
         self.dep.remove(unit_id);
     }
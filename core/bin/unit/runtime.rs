@@ -324,8 +324,11 @@ impl UnitRTData {
         self.load_queue.borrow_mut().push_back(unit);
     }
 
-    fn remove_unit(&self, _unit: &UnitX) {
-        todo!();
+    fn remove_unit(&self, unit: &UnitX) {
+        self.load_queue.borrow_mut().retain(|u| u.id() != unit.id());
+        self.target_dep_queue
+            .borrow_mut()
+            .retain(|u| u.id() != unit.id());
     }
 }
 
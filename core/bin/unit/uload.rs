@@ -20,6 +20,7 @@ use crate::unit::runtime::UnitRT;
 use crate::unit::util::UnitFile;
 use crate::utils::table::{TableOp, TableSubscribe};
 use basic::path_lookup::LookupPaths;
+use basic::unit_name;
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 use sysmaster::unit::UnitType;
@@ -50,6 +51,11 @@ impl UnitLoad {
         self.data.load_unit(name)
     }
 
+    /// remove the unit and all its data, the caller makes sure it's not in use
+    pub(super) fn unload_unit(&self, unit: &Rc<UnitX>) {
+        self.data.unload_unit(unit)
+    }
+
     pub(super) fn set_um(&self, um: &Rc<UnitManager>) {
         self.data.set_um(um);
     }
@@ -135,6 +141,19 @@ impl UnitLoadData {
         Some(Rc::clone(&unit))
     }
 
+    pub(self) fn unload_unit(&self, unit: &Rc<UnitX>) {
+        let name = unit.id().to_string();
+        log::debug!("unload unit {}", name);
+
+        // the sub unit removes its own entries first
+        unit.done();
+        // the subscribers of the unit sets remove the dependencies, jobs, pids and queues
+        self.db.unit_remove(&name);
+        self.dm.remove_unit(&name);
+        self.rentry.unit_remove(&name);
+        self.file.remove_unit(&name);
+    }
+
     pub(self) fn set_um(&self, um: &Rc<UnitManager>) {
         self.um.replace(Rc::downgrade(um));
     }
@@ -145,6 +164,12 @@ impl UnitLoadData {
             return None;
         }
 
+        // the template is only used to create its instances
+        if unit_name::unit_name_is_template(name) {
+            log::error!("{} is a template, it can't be loaded directly", name);
+            return None;
+        }
+
         log::info!(
             "begin create obj for type {:?}, name {} by plugin",
             unit_type,
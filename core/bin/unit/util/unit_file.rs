@@ -12,6 +12,7 @@
 
 use basic::path_lookup::LookupPaths;
 use basic::time_util;
+use basic::unit_name;
 use siphasher::sip::SipHasher24;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -46,6 +47,10 @@ impl UnitFile {
     pub fn get_unit_requires_symlink_units(&self, name: &String) -> Vec<PathBuf> {
         self.data.borrow().get_unit_requires_symlink_units(name)
     }
+
+    pub fn remove_unit(&self, name: &str) {
+        self.data.borrow_mut().remove_unit(name)
+    }
 }
 
 #[derive(Debug)]
@@ -92,6 +97,12 @@ impl UnitFileData {
         }
     }
 
+    pub(self) fn remove_unit(&mut self, name: &str) {
+        self.unit_id_fragment.remove(name);
+        self.unit_wants_symlink_units.remove(name);
+        self.unit_requires_symlink_units.remove(name);
+    }
+
     pub(self) fn build_id_map(&mut self, name: String, has_loaded: bool) -> bool {
         if !has_loaded || self.lookup_paths_updated() {
             self.build_id_fragment(&name);
@@ -102,44 +113,36 @@ impl UnitFileData {
     }
 
     fn build_id_fragment(&mut self, name: &String) {
+        // the instance uses the fragment of its template if it has no one of its own,
+        // the drop-ins of both the instance and the template are applied.
+        let template = unit_name::unit_name_to_template(name);
         let mut pathbuf_fragment = Vec::new();
         for v in &self.lookup_path.search_path {
             if let Err(_e) = fs::metadata(v) {
                 continue;
             }
-            let pathd = format!("{v}/{name}.d");
-            let dir = Path::new(&pathd);
-            if dir.is_dir() {
-                for entry in dir.read_dir().unwrap() {
-                    let fragment = entry.unwrap().path();
-                    if fragment.is_file() {
-                        let file_name =
-                            String::from(fragment.file_name().unwrap().to_str().unwrap());
-                        if file_name.starts_with('.') || file_name.ends_with(".toml") {
-                            continue;
-                        }
-                        let path = format!("{}.toml", fragment.to_string_lossy());
-
-                        if let Err(e) = std::fs::copy(fragment, &path) {
-                            log::warn!("copy file content to toml file error: {}", e);
-                        }
-                        pathbuf_fragment.push(Path::new(&path).to_path_buf());
-                    }
-                }
+            pathbuf_fragment.append(&mut build_dropin_fragment(v, name));
+            if let Some(t) = &template {
+                pathbuf_fragment.append(&mut build_dropin_fragment(v, t));
             }
-            let path = if v.ends_with('/') {
-                format!("{v}{name}")
-            } else {
-                format!("{v}/{name}")
-            };
-            let tmp = Path::new(&path);
-            if tmp.exists() && !tmp.is_symlink() {
-                let path = format!("{}.toml", tmp.to_string_lossy());
-                if let Err(e) = std::fs::copy(tmp, &path) {
-                    log::warn!("copy file content to toml file error: {}", e);
+
+            let mut fragment = None;
+            for n in std::iter::once(name).chain(template.iter()) {
+                let path = if v.ends_with('/') {
+                    format!("{v}{n}")
+                } else {
+                    format!("{v}/{n}")
+                };
+                let tmp = PathBuf::from(&path);
+                if tmp.exists() && !tmp.is_symlink() {
+                    fragment = Some(tmp);
+                    break;
                 }
-                let to = Path::new(&path);
-                pathbuf_fragment.push(to.to_path_buf());
+            }
+
+            // the specifiers of the instance are expanded when the fragment is loaded
+            if let Some(path) = fragment {
+                pathbuf_fragment.push(path);
             }
         }
 
@@ -148,19 +151,30 @@ impl UnitFileData {
     }
 
     fn build_id_dropin(&mut self, name: &String, suffix: String) {
+        let template = unit_name::unit_name_to_template(name);
         let mut pathbuf_dropin = Vec::new();
         for v in &self.lookup_path.search_path {
-            let path = format!("{v}/{name}.{suffix}");
-            let dir = Path::new(&path);
-            if dir.is_dir() {
+            for n in std::iter::once(name).chain(template.iter()) {
+                let path = format!("{v}/{n}.{suffix}");
+                let dir = Path::new(&path);
+                if !dir.is_dir() {
+                    continue;
+                }
                 for entry in dir.read_dir().unwrap() {
                     let symlink_unit = entry.unwrap().path();
-                    if symlink_unit.is_symlink() {
-                        if let Ok(abs_path) = symlink_unit.canonicalize() {
-                            let mut file_name = PathBuf::new();
-                            file_name.push(abs_path.file_name().unwrap());
-                            pathbuf_dropin.push(file_name);
-                        }
+                    if !symlink_unit.is_symlink() {
+                        continue;
+                    }
+                    // the instance is linked to its template, use the name of the link
+                    let link_name = symlink_unit.file_name().unwrap().to_string_lossy();
+                    if unit_name::unit_name_to_template(&link_name).is_some() {
+                        pathbuf_dropin.push(PathBuf::from(link_name.to_string()));
+                        continue;
+                    }
+                    if let Ok(abs_path) = symlink_unit.canonicalize() {
+                        let mut file_name = PathBuf::new();
+                        file_name.push(abs_path.file_name().unwrap());
+                        pathbuf_dropin.push(file_name);
                     }
                 }
             }
@@ -203,3 +217,26 @@ impl UnitFileData {
         path_updated
     }
 }
+
+/// return the drop-in fragments of the unit in the directory "{dir}/{unit}.d"
+fn build_dropin_fragment(dir: &str, unit: &str) -> Vec<PathBuf> {
+    let mut ret = Vec::new();
+    let pathd = Path::new(dir).join(format!("{unit}.d"));
+    if !pathd.is_dir() {
+        return ret;
+    }
+
+    for entry in pathd.read_dir().unwrap() {
+        let fragment = entry.unwrap().path();
+        if !fragment.is_file() {
+            continue;
+        }
+        let file_name = String::from(fragment.file_name().unwrap().to_str().unwrap());
+        if file_name.starts_with('.') || file_name.ends_with(".toml") {
+            continue;
+        }
+        ret.push(fragment);
+    }
+
+    ret
+}
@@ -68,7 +68,6 @@ where
         ret
     }
 
-    #[allow(dead_code)]
     pub fn remove(&mut self, k: &K) -> Option<V> {
         let ret = self.data.remove(k);
         if let Some(v) = &ret {
@@ -10,21 +10,146 @@
 // NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
 // See the Mulan PSL v2 for more details.
 
+use super::capability::ExecCapabilities;
+use super::directory::{ExecDirectory, ExecDirectoryType};
+use super::limit::Rlimit;
+use super::namespace::ExecNamespace;
+use super::sched::ExecScheduling;
+use super::seccomp::SyscallFilter;
 use crate::error::*;
+use crate::serialize::DeserializeWith;
+use basic::env_util;
 use bitflags::bitflags;
+use nix::sys::resource::Resource;
 use nix::sys::stat::Mode;
-use nix::unistd::{Group, Uid, User};
+use nix::unistd::{Gid, Group, Uid, User};
+use serde::{de, Deserialize, Deserializer, Serialize};
 
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::{cell::RefCell, collections::HashMap};
 use std::{ffi::CString, path::PathBuf, rc::Rc};
 
+/// the standard input of the spawned process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ExecInput {
+    /// connect to /dev/null
+    #[default]
+    Null,
+    /// connect to the console
+    Tty,
+    /// connect to the socket passed by the socket unit
+    Socket,
+    /// open the file for reading
+    File(PathBuf),
+}
+
+impl ExecInput {
+    /// parse the value of StandardInput
+    pub fn parse(s: &str) -> Result<ExecInput, String> {
+        match s.trim() {
+            "null" => Ok(ExecInput::Null),
+            "tty" => Ok(ExecInput::Tty),
+            "socket" => Ok(ExecInput::Socket),
+            s => match s.strip_prefix("file:") {
+                Some(path) => Ok(ExecInput::File(parse_stdio_path(path)?)),
+                None => Err(format!("invalid standard input: {s}")),
+            },
+        }
+    }
+}
+
+impl DeserializeWith for ExecInput {
+    type Item = Self;
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        ExecInput::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+/// the standard output or error of the spawned process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ExecOutput {
+    /// duplicate the standard input for the standard output, and the standard output
+    /// for the standard error
+    #[default]
+    Inherit,
+    /// connect to /dev/null
+    Null,
+    /// connect to the console
+    Tty,
+    /// capture the output into the log file of the unit
+    Log,
+    /// connect to the socket passed by the socket unit
+    Socket,
+    /// open the file for writing from the beginning, without truncating it
+    File(PathBuf),
+    /// open the file for appending
+    Append(PathBuf),
+    /// truncate the file and open it for writing
+    Truncate(PathBuf),
+}
+
+impl ExecOutput {
+    /// parse the value of StandardOutput and StandardError
+    pub fn parse(s: &str) -> Result<ExecOutput, String> {
+        match s.trim() {
+            "inherit" => Ok(ExecOutput::Inherit),
+            "null" => Ok(ExecOutput::Null),
+            "tty" => Ok(ExecOutput::Tty),
+            "log" | "journal" => Ok(ExecOutput::Log),
+            "socket" => Ok(ExecOutput::Socket),
+            s => {
+                if let Some(path) = s.strip_prefix("file:") {
+                    Ok(ExecOutput::File(parse_stdio_path(path)?))
+                } else if let Some(path) = s.strip_prefix("append:") {
+                    Ok(ExecOutput::Append(parse_stdio_path(path)?))
+                } else if let Some(path) = s.strip_prefix("truncate:") {
+                    Ok(ExecOutput::Truncate(parse_stdio_path(path)?))
+                } else {
+                    Err(format!("invalid standard output: {s}"))
+                }
+            }
+        }
+    }
+}
+
+impl DeserializeWith for ExecOutput {
+    type Item = Self;
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        ExecOutput::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+fn parse_stdio_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if !path.is_absolute() {
+        return Err(format!("{path:?} is not an absolute path"));
+    }
+
+    Ok(path)
+}
+
 /// the exec context that was parse from the unit file.
 /// like parsed from Environment field.
 pub struct ExecContext {
     envs: RefCell<HashMap<String, String>>,
-    env_files: RefCell<Vec<PathBuf>>,
+    env_files: RefCell<Vec<(PathBuf, bool)>>,
+    file_envs: RefCell<HashMap<String, String>>,
+    std_input: RefCell<ExecInput>,
+    std_output: RefCell<ExecOutput>,
+    std_error: RefCell<ExecOutput>,
+    rlimits: RefCell<HashMap<Resource, Rlimit>>,
+    namespace: RefCell<ExecNamespace>,
+    syscall_filter: RefCell<SyscallFilter>,
+    capabilities: RefCell<ExecCapabilities>,
+    directories: RefCell<HashMap<ExecDirectoryType, ExecDirectory>>,
+    scheduling: RefCell<ExecScheduling>,
 }
 
 impl Default for ExecContext {
@@ -39,61 +164,155 @@ impl ExecContext {
         ExecContext {
             envs: RefCell::new(HashMap::new()),
             env_files: RefCell::new(vec![]),
+            file_envs: RefCell::new(HashMap::new()),
+            std_input: RefCell::new(ExecInput::Null),
+            std_output: RefCell::new(ExecOutput::Inherit),
+            std_error: RefCell::new(ExecOutput::Inherit),
+            rlimits: RefCell::new(HashMap::new()),
+            namespace: RefCell::new(ExecNamespace::default()),
+            syscall_filter: RefCell::new(SyscallFilter::default()),
+            capabilities: RefCell::new(ExecCapabilities::default()),
+            directories: RefCell::new(HashMap::new()),
+            scheduling: RefCell::new(ExecScheduling::default()),
         }
     }
 
+    /// set the standard input, output and error of the spawned process
+    pub fn set_stdio(&self, input: ExecInput, output: ExecOutput, error: ExecOutput) {
+        *self.std_input.borrow_mut() = input;
+        *self.std_output.borrow_mut() = output;
+        *self.std_error.borrow_mut() = error;
+    }
+
+    /// return the standard input of the spawned process
+    pub fn std_input(&self) -> ExecInput {
+        self.std_input.borrow().clone()
+    }
+
+    /// return the standard output of the spawned process
+    pub fn std_output(&self) -> ExecOutput {
+        self.std_output.borrow().clone()
+    }
+
+    /// return the standard error of the spawned process
+    pub fn std_error(&self) -> ExecOutput {
+        self.std_error.borrow().clone()
+    }
+
+    /// set the resource limits of the spawned process, replacing the previous ones
+    pub fn set_rlimits(&self, rlimits: Vec<(Resource, Rlimit)>) {
+        *self.rlimits.borrow_mut() = rlimits.into_iter().collect();
+    }
+
+    /// return the resource limits of the spawned process
+    pub fn rlimits(&self) -> HashMap<Resource, Rlimit> {
+        self.rlimits.borrow().clone()
+    }
+
+    /// set the mount namespace settings of the spawned process
+    pub fn set_namespace(&self, namespace: ExecNamespace) {
+        *self.namespace.borrow_mut() = namespace;
+    }
+
+    /// return the mount namespace settings of the spawned process
+    pub fn namespace(&self) -> ExecNamespace {
+        self.namespace.borrow().clone()
+    }
+
+    /// set the seccomp system call filter of the spawned process
+    pub fn set_syscall_filter(&self, filter: SyscallFilter) {
+        *self.syscall_filter.borrow_mut() = filter;
+    }
+
+    /// return the seccomp system call filter of the spawned process
+    pub fn syscall_filter(&self) -> SyscallFilter {
+        self.syscall_filter.borrow().clone()
+    }
+
+    /// set the capability settings of the spawned process
+    pub fn set_capabilities(&self, capabilities: ExecCapabilities) {
+        *self.capabilities.borrow_mut() = capabilities;
+    }
+
+    /// return the capability settings of the spawned process
+    pub fn capabilities(&self) -> ExecCapabilities {
+        self.capabilities.borrow().clone()
+    }
+
+    /// set the managed directories of the type
+    pub fn set_directory(&self, dir_type: ExecDirectoryType, directory: ExecDirectory) {
+        self.directories.borrow_mut().insert(dir_type, directory);
+    }
+
+    /// return all the managed directories of the spawned process
+    pub fn directories(&self) -> HashMap<ExecDirectoryType, ExecDirectory> {
+        self.directories.borrow().clone()
+    }
+
+    /// set the scheduling settings of the spawned process
+    pub fn set_scheduling(&self, scheduling: ExecScheduling) {
+        *self.scheduling.borrow_mut() = scheduling;
+    }
+
+    /// return the scheduling settings of the spawned process
+    pub fn scheduling(&self) -> ExecScheduling {
+        self.scheduling.borrow().clone()
+    }
+
     /// insert to the context with key and value
     pub fn insert_env(&self, key: String, value: String) {
         self.envs.borrow_mut().insert(key, value);
     }
 
-    /// return all the environment with hashMap
+    /// return all the environment with hashMap, the environment loaded from the
+    /// environment files overrides the one with the same key.
     pub fn envs(&self) -> Vec<(String, String)> {
-        let mut tmp = Vec::new();
-
-        for (key, value) in &*self.envs.borrow() {
-            tmp.push((key.to_string(), value.to_string()));
+        let mut envs = self.envs.borrow().clone();
+        for (key, value) in &*self.file_envs.borrow() {
+            envs.insert(key.to_string(), value.to_string());
         }
-        tmp
+
+        envs.into_iter().collect()
     }
 
-    /// insert environment files
+    /// insert environment files, the file prefixed with "-" is ignored if it does not exist
     pub fn insert_envs_files(&self, paths: Vec<String>) {
         for path in paths {
-            self.env_files.borrow_mut().push(PathBuf::from(path));
+            if path.is_empty() {
+                continue;
+            }
+
+            let ignore_missing = path.starts_with('-');
+            let path = PathBuf::from(path.trim_start_matches('-'));
+            self.env_files.borrow_mut().push((path, ignore_missing));
         }
     }
 
     /// load envirenment from file
     pub fn load_env_from_file(&self) -> Result<(), Error> {
-        for path in &*self.env_files.borrow() {
-            if path.starts_with("-") {
-                log::info!("ignore environment file; {:?}", path);
-                continue;
-            }
+        let mut file_envs = HashMap::new();
 
-            if !path.exists() || !path.is_absolute() {
-                continue;
+        for (path, ignore_missing) in &*self.env_files.borrow() {
+            if !path.is_absolute() {
+                return Err(Error::InvalidData);
             }
 
-            let f = File::open(path)?;
-
-            for line in io::BufReader::new(f).lines().flatten() {
-                if line.trim().starts_with('#') {
-                    continue;
-                }
-
-                let content: Vec<&str> = line.split('=').map(|s| s.trim()).collect();
-                if content.len() != 2 {
+            if !path.exists() {
+                if *ignore_missing {
+                    log::debug!("ignore nonexistent environment file: {:?}", path);
                     continue;
                 }
+                return Err(Error::NotFound {
+                    what: format!("environment file {path:?}"),
+                });
+            }
 
-                self.envs
-                    .borrow_mut()
-                    .insert(content[0].to_string(), content[1].to_string());
+            for (key, value) in env_util::parse_env_file(path).context(UtilSnafu)? {
+                file_envs.insert(key, value);
             }
         }
 
+        *self.file_envs.borrow_mut() = file_envs;
         Ok(())
     }
 }
@@ -102,6 +321,7 @@ impl ExecContext {
 pub struct ExecParameters {
     environment: Rc<EnvData>,
     fds: Vec<i32>,
+    fd_names: Vec<String>,
     notify_sock: Option<PathBuf>,
     working_directory: Option<PathBuf>,
     user: Option<User>,
@@ -153,6 +373,7 @@ impl ExecParameters {
         ExecParameters {
             environment: Rc::new(EnvData::new()),
             fds: Vec::new(),
+            fd_names: Vec::new(),
             notify_sock: None,
             working_directory: None,
             user: None,
@@ -188,6 +409,16 @@ impl ExecParameters {
         self.fds.to_vec()
     }
 
+    /// insert the names of the fds, which are passed in LISTEN_FDNAMES
+    pub fn insert_fd_names(&mut self, fd_names: Vec<String>) {
+        self.fd_names = fd_names
+    }
+
+    /// return the names of the fds that will be passed to child
+    pub fn fd_names(&self) -> Vec<String> {
+        self.fd_names.to_vec()
+    }
+
     /// set the NOTIFY_SOCKET value
     pub fn set_notify_sock(&mut self, notify_sock: PathBuf) {
         self.notify_sock = Some(notify_sock)
@@ -253,6 +484,26 @@ impl ExecParameters {
         self.user.clone()
     }
 
+    /// set the dynamic user allocated for the unit, whose primary group has the same id.
+    /// The user is not in the user database, so it is built without NSS.
+    pub fn set_dynamic_user(&mut self, name: &str, uid: u32) {
+        self.user = Some(User {
+            name: name.to_string(),
+            passwd: CString::new("x").unwrap(),
+            uid: Uid::from_raw(uid),
+            gid: Gid::from_raw(uid),
+            gecos: CString::new("Dynamic User").unwrap(),
+            dir: PathBuf::from("/"),
+            shell: PathBuf::from("/usr/sbin/nologin"),
+        });
+        self.group = Some(Group {
+            name: name.to_string(),
+            passwd: CString::new("x").unwrap(),
+            gid: Gid::from_raw(uid),
+            mem: Vec::new(),
+        });
+    }
+
     /// add Group
     pub fn add_group(&mut self, group_str: String) -> Result<()> {
         // add_user should be called before add_group
@@ -348,7 +599,8 @@ mod tests {
         unistd::{Gid, Uid},
     };
 
-    use super::ExecParameters;
+    use super::{ExecInput, ExecOutput, ExecParameters};
+    use std::path::PathBuf;
 
     #[test]
     fn test_add_working_directory() {
@@ -422,6 +674,15 @@ mod tests {
         assert!(params.add_group("wwwwyyyyyffffff".to_string()).is_err());
     }
 
+    #[test]
+    fn test_set_dynamic_user() {
+        let mut params = ExecParameters::new();
+        params.set_dynamic_user("foo", 61200);
+        assert_eq!(params.get_user().unwrap().uid, Uid::from_raw(61200));
+        assert_eq!(params.get_user().unwrap().gid, Gid::from_raw(61200));
+        assert_eq!(params.get_group().unwrap().name, "foo");
+    }
+
     #[test]
     fn test_add_umask() {
         let mut params = ExecParameters::new();
@@ -438,4 +699,30 @@ mod tests {
         assert!(params.add_umask("0011".to_string()).is_ok());
         assert_eq!(params.get_umask().unwrap(), Mode::from_bits(9).unwrap());
     }
+
+    #[test]
+    fn test_parse_stdio() {
+        assert_eq!(ExecInput::parse("null"), Ok(ExecInput::Null));
+        assert_eq!(ExecInput::parse("socket"), Ok(ExecInput::Socket));
+        assert_eq!(
+            ExecInput::parse("file:/dev/zero"),
+            Ok(ExecInput::File(PathBuf::from("/dev/zero")))
+        );
+        assert!(ExecInput::parse("file:dev/zero").is_err());
+        assert!(ExecInput::parse("log").is_err());
+
+        assert_eq!(ExecOutput::parse("inherit"), Ok(ExecOutput::Inherit));
+        assert_eq!(ExecOutput::parse("journal"), Ok(ExecOutput::Log));
+        assert_eq!(ExecOutput::parse(" log "), Ok(ExecOutput::Log));
+        assert_eq!(
+            ExecOutput::parse("append:/var/log/foo.log"),
+            Ok(ExecOutput::Append(PathBuf::from("/var/log/foo.log")))
+        );
+        assert_eq!(
+            ExecOutput::parse("truncate:/tmp/foo"),
+            Ok(ExecOutput::Truncate(PathBuf::from("/tmp/foo")))
+        );
+        assert!(ExecOutput::parse("file:").is_err());
+        assert!(ExecOutput::parse("kmsg").is_err());
+    }
 }
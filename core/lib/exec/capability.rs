@@ -0,0 +1,295 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the capabilities of the spawned process, configured by CapabilityBoundingSet=,
+//! AmbientCapabilities= and NoNewPrivileges=
+use nix::errno::Errno;
+use std::fs;
+
+/// the capability names indexed by the capability number
+const CAPABILITY_NAMES: [&str; 41] = [
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_DAC_READ_SEARCH",
+    "CAP_FOWNER",
+    "CAP_FSETID",
+    "CAP_KILL",
+    "CAP_SETGID",
+    "CAP_SETUID",
+    "CAP_SETPCAP",
+    "CAP_LINUX_IMMUTABLE",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_NET_BROADCAST",
+    "CAP_NET_ADMIN",
+    "CAP_NET_RAW",
+    "CAP_IPC_LOCK",
+    "CAP_IPC_OWNER",
+    "CAP_SYS_MODULE",
+    "CAP_SYS_RAWIO",
+    "CAP_SYS_CHROOT",
+    "CAP_SYS_PTRACE",
+    "CAP_SYS_PACCT",
+    "CAP_SYS_ADMIN",
+    "CAP_SYS_BOOT",
+    "CAP_SYS_NICE",
+    "CAP_SYS_RESOURCE",
+    "CAP_SYS_TIME",
+    "CAP_SYS_TTY_CONFIG",
+    "CAP_MKNOD",
+    "CAP_LEASE",
+    "CAP_AUDIT_WRITE",
+    "CAP_AUDIT_CONTROL",
+    "CAP_SETFCAP",
+    "CAP_MAC_OVERRIDE",
+    "CAP_MAC_ADMIN",
+    "CAP_SYSLOG",
+    "CAP_WAKE_ALARM",
+    "CAP_BLOCK_SUSPEND",
+    "CAP_AUDIT_READ",
+    "CAP_PERFMON",
+    "CAP_BPF",
+    "CAP_CHECKPOINT_RESTORE",
+];
+
+/// the mask of all the known capabilities
+const CAP_ALL: u64 = (1 << CAPABILITY_NAMES.len()) - 1;
+
+const CAP_LAST_CAP_PATH: &str = "/proc/sys/kernel/cap_last_cap";
+
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+#[repr(C)]
+struct CapUserHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct CapUserData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+/// the capability settings of the spawned process
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecCapabilities {
+    /// CapabilityBoundingSet=, the bounding set is not changed if None
+    pub bounding_set: Option<u64>,
+    /// AmbientCapabilities=, masked by the bounding set
+    pub ambient_set: u64,
+    /// NoNewPrivileges=
+    pub no_new_privileges: bool,
+}
+
+impl ExecCapabilities {
+    /// parse a capability list, the names in one entry are divided by whitespace.
+    /// An entry prefixed with "~" removes the capabilities from the set, which starts
+    /// with all the capabilities if the first entry is prefixed with "~".
+    /// Return None if the list is empty.
+    pub fn parse_set(entries: &[String]) -> Result<Option<u64>, String> {
+        let mut set = None;
+        for entry in entries.iter().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let (invert, list) = match entry.strip_prefix('~') {
+                Some(list) => (true, list),
+                None => (false, entry),
+            };
+
+            let mut mask = 0;
+            for name in list.split_whitespace() {
+                let cap = capability_from_name(name)
+                    .ok_or_else(|| format!("invalid capability: {name}"))?;
+                mask |= 1 << cap;
+            }
+
+            set = Some(if invert {
+                set.unwrap_or(CAP_ALL) & !mask
+            } else {
+                set.unwrap_or(0) | mask
+            });
+        }
+
+        Ok(set)
+    }
+
+    /// drop the capabilities out of the bounding set, which needs CAP_SETPCAP
+    pub fn drop_bounding_set(&self) -> nix::Result<()> {
+        let bounding_set = match self.bounding_set {
+            None => return Ok(()),
+            Some(set) => set,
+        };
+
+        for cap in 0..=cap_last_cap() {
+            if cap < 64 && bounding_set & (1 << cap) != 0 {
+                continue;
+            }
+
+            let ret = unsafe { libc::prctl(libc::PR_CAPBSET_DROP, cap as libc::c_ulong, 0, 0, 0) };
+            match Errno::result(ret) {
+                // the kernel does not know the capability
+                Ok(_) | Err(Errno::EINVAL) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// keep the permitted capabilities when changing the user, so that the ambient
+    /// capabilities can be raised after it
+    pub fn keep_caps(&self) -> nix::Result<()> {
+        if self.ambient_set() == 0 {
+            return Ok(());
+        }
+
+        let ret = unsafe { libc::prctl(libc::PR_SET_KEEPCAPS, 1, 0, 0, 0) };
+        Errno::result(ret).map(drop)
+    }
+
+    /// raise the ambient capabilities after changing the user, the permitted, effective
+    /// and inheritable sets are reduced to the ambient set.
+    pub fn raise_ambient(&self) -> nix::Result<()> {
+        let ambient_set = self.ambient_set();
+        if ambient_set == 0 {
+            return Ok(());
+        }
+
+        let mut header = CapUserHeader {
+            version: LINUX_CAPABILITY_VERSION_3,
+            pid: 0,
+        };
+        let mut data = [CapUserData::default(); 2];
+        for (i, d) in data.iter_mut().enumerate() {
+            let v = (ambient_set >> (32 * i)) as u32;
+            *d = CapUserData {
+                effective: v,
+                permitted: v,
+                inheritable: v,
+            };
+        }
+        let ret = unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_mut_ptr()) };
+        Errno::result(ret)?;
+
+        for cap in 0..CAPABILITY_NAMES.len() {
+            if ambient_set & (1 << cap) == 0 {
+                continue;
+            }
+
+            let ret = unsafe {
+                libc::prctl(
+                    libc::PR_CAP_AMBIENT,
+                    libc::PR_CAP_AMBIENT_RAISE as libc::c_ulong,
+                    cap as libc::c_ulong,
+                    0,
+                    0,
+                )
+            };
+            Errno::result(ret)?;
+        }
+
+        let ret = unsafe { libc::prctl(libc::PR_SET_KEEPCAPS, 0, 0, 0, 0) };
+        Errno::result(ret).map(drop)
+    }
+
+    /// set no_new_privs if NoNewPrivileges is configured
+    pub fn apply_no_new_privileges(&self) -> nix::Result<()> {
+        if !self.no_new_privileges {
+            return Ok(());
+        }
+
+        let ret = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+        Errno::result(ret).map(drop)
+    }
+
+    fn ambient_set(&self) -> u64 {
+        self.ambient_set & self.bounding_set.unwrap_or(CAP_ALL)
+    }
+}
+
+/// return the capability number of the name, which is case insensitive
+pub fn capability_from_name(name: &str) -> Option<u32> {
+    CAPABILITY_NAMES
+        .iter()
+        .position(|n| n.eq_ignore_ascii_case(name))
+        .map(|cap| cap as u32)
+}
+
+fn cap_last_cap() -> u32 {
+    fs::read_to_string(CAP_LAST_CAP_PATH)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .unwrap_or(CAPABILITY_NAMES.len() as u32 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capability_from_name, ExecCapabilities, CAP_ALL};
+    use std::fs;
+
+    fn parse(entries: &[&str]) -> Result<Option<u64>, String> {
+        let entries: Vec<String> = entries.iter().map(|s| s.to_string()).collect();
+        ExecCapabilities::parse_set(&entries)
+    }
+
+    #[test]
+    fn test_parse_set() {
+        assert_eq!(capability_from_name("cap_net_bind_service"), Some(10));
+        assert_eq!(capability_from_name("CAP_FOO"), None);
+
+        assert_eq!(parse(&[]), Ok(None));
+        assert_eq!(
+            parse(&["CAP_CHOWN CAP_KILL", "CAP_NET_ADMIN"]),
+            Ok(Some(1 | 1 << 5 | 1 << 12))
+        );
+        assert_eq!(parse(&["~CAP_SYS_ADMIN"]), Ok(Some(CAP_ALL & !(1 << 21))));
+        assert_eq!(parse(&["CAP_CHOWN CAP_KILL", "~CAP_KILL"]), Ok(Some(1)));
+        assert!(parse(&["CAP_FOO"]).is_err());
+    }
+
+    #[test]
+    fn test_raise_ambient() {
+        if !nix::unistd::getuid().is_root() {
+            return;
+        }
+
+        let caps = ExecCapabilities {
+            bounding_set: Some(1 << 10),
+            ambient_set: 1 << 10 | 1 << 21,
+            no_new_privileges: true,
+        };
+
+        match unsafe { libc::fork() } {
+            0 => {
+                let nobody = nix::unistd::Uid::from_raw(65534);
+                let ret = caps
+                    .drop_bounding_set()
+                    .and_then(|_| caps.keep_caps())
+                    .and_then(|_| nix::unistd::setresuid(nobody, nobody, nobody))
+                    .and_then(|_| caps.raise_ambient())
+                    .and_then(|_| caps.apply_no_new_privileges());
+                let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+                let ok = ret.is_ok()
+                    && status.contains("CapAmb:\t0000000000000400")
+                    && status.contains("CapBnd:\t0000000000000400")
+                    && status.contains("NoNewPrivs:\t1");
+                unsafe { libc::_exit(if ok { 0 } else { 1 }) };
+            }
+            pid => {
+                let mut status = 0;
+                assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+                assert!(libc::WIFEXITED(status));
+                assert_eq!(libc::WEXITSTATUS(status), 0);
+            }
+        }
+    }
+}
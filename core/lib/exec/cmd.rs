@@ -11,24 +11,46 @@
 // See the Mulan PSL v2 for more details.
 
 use crate::serialize::DeserializeWith;
-use regex::Regex;
+use bitflags::bitflags;
 use serde::{
     de::{self, Unexpected},
     Deserialize, Deserializer, Serialize,
 };
 use std::{collections::VecDeque, path::Path};
 
+bitflags! {
+    /// the prefix flags of the exec command, like "-/bin/true"
+    #[derive(Serialize, Deserialize)]
+    pub struct ExecCommandFlags: u8 {
+        /// "-": the failure of the command is ignored
+        const IGNORE_FAILURE = 1 << 0;
+        /// "+": the command is executed with full privileges
+        const FULLY_PRIVILEGED = 1 << 1;
+        /// "!": the command is executed without changing the credentials
+        const NO_SETUID = 1 << 2;
+        /// ":": the environment variables in the command are not expanded
+        const NO_ENV_EXPAND = 1 << 3;
+    }
+}
+
 /// the exec command that was parsed from the unit file
 #[derive(PartialEq, Clone, Eq, Debug, Serialize, Deserialize)]
 pub struct ExecCommand {
     path: String,
     argv: Vec<String>,
+    argv0: Option<String>,
+    flags: ExecCommandFlags,
 }
 
 impl ExecCommand {
     /// create a new instance of the command
     pub fn new(path: String, argv: Vec<String>) -> ExecCommand {
-        ExecCommand { path, argv }
+        ExecCommand {
+            path,
+            argv,
+            argv0: None,
+            flags: ExecCommandFlags::empty(),
+        }
     }
 
     /// return the path of the command
@@ -40,53 +62,394 @@ impl ExecCommand {
     pub fn argv(&self) -> Vec<&String> {
         self.argv.iter().collect::<Vec<_>>()
     }
-}
 
-impl DeserializeWith for ExecCommand {
-    type Item = VecDeque<Self>;
-    fn deserialize_with<'de, D>(de: D) -> Result<Self::Item, D::Error>
+    /// return the argv[0] passed to the command, which is the path if not overridden by "@"
+    pub fn argv0(&self) -> &String {
+        self.argv0.as_ref().unwrap_or(&self.path)
+    }
+
+    /// return the prefix flags of the command
+    pub fn flags(&self) -> ExecCommandFlags {
+        self.flags
+    }
+
+    /// return the arguments with the environment variables expanded. "$NAME" as a separate
+    /// argument is split into multiple arguments by whitespace, "${NAME}" is replaced in place.
+    pub fn expand_argv<F>(&self, get_env: F) -> Vec<String>
     where
-        D: Deserializer<'de>,
+        F: Fn(&str) -> Option<String>,
     {
-        let s = String::deserialize(de)?;
+        if self.flags.contains(ExecCommandFlags::NO_ENV_EXPAND) {
+            return self.argv.clone();
+        }
 
-        let mut commands = VecDeque::new();
+        let mut args = Vec::new();
+        for arg in &self.argv {
+            if let Some(name) = arg.strip_prefix('$') {
+                if !name.is_empty() && env_name_len(name) == name.len() {
+                    if let Some(value) = get_env(name) {
+                        args.extend(value.split_whitespace().map(|v| v.to_string()));
+                    }
+                    continue;
+                }
+            }
+
+            args.push(expand_env_word(arg, &get_env));
+        }
+        args
+    }
+
+    /// whether the credentials should be changed before executing the command
+    pub fn apply_credentials(&self) -> bool {
+        !self
+            .flags
+            .intersects(ExecCommandFlags::FULLY_PRIVILEGED | ExecCommandFlags::NO_SETUID)
+    }
 
-        for cmd in s.trim().split_terminator(';') {
-            if cmd.is_empty() {
+    /// whether the sandboxing like the namespaces, the capability bounding set,
+    /// no_new_privs and the system call filter applies to the command
+    pub fn apply_sandboxing(&self) -> bool {
+        !self.flags.contains(ExecCommandFlags::FULLY_PRIVILEGED)
+    }
+}
+
+/// return the length of the environment variable name at the beginning of s
+fn env_name_len(s: &str) -> usize {
+    s.char_indices()
+        .take_while(|(i, c)| *c == '_' || c.is_ascii_alphabetic() || (*i > 0 && c.is_ascii_digit()))
+        .count()
+}
+
+/// replace "$NAME", "${NAME}" and "$$" in the word, unset variables are replaced by ""
+fn expand_env_word<F>(word: &str, get_env: &F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut ret = String::new();
+    let mut rest = word;
+
+    while let Some(pos) = rest.find('$') {
+        ret.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        if let Some(r) = rest.strip_prefix('$') {
+            ret.push('$');
+            rest = r;
+            continue;
+        }
+
+        if let Some(r) = rest.strip_prefix('{') {
+            if let Some(end) = r.find('}') {
+                ret.push_str(&get_env(&r[..end]).unwrap_or_default());
+                rest = &r[end + 1..];
                 continue;
             }
+            ret.push('$');
+            continue;
+        }
 
-            #[allow(clippy::trim_split_whitespace)]
-            let mut command: Vec<String> = Vec::new();
-            let re = Regex::new(r"'([^']*)'|\S+").unwrap();
-            for cap in re.captures_iter(cmd) {
-                if let Some(s) = cap.get(1) {
-                    command.push(s.as_str().to_string());
-                    continue;
+        let len = env_name_len(rest);
+        if len == 0 {
+            ret.push('$');
+            continue;
+        }
+        ret.push_str(&get_env(&rest[..len]).unwrap_or_default());
+        rest = &rest[len..];
+    }
+
+    ret.push_str(rest);
+    ret
+}
+
+/// split the command line into commands by the unquoted ";", and split each command into
+/// words by whitespace. Single and double quotes are removed, and a backslash outside of
+/// the single quotes escapes the next character.
+fn split_commands(s: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut commands = Vec::new();
+    let mut command = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(v) => word.push(v),
+                        None => return Err(format!("unterminated single quote in {s}")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(v) if v == '"' || v == '\\' => word.push(v),
+                            Some(v) => {
+                                word.push('\\');
+                                word.push(v);
+                            }
+                            None => return Err(format!("unterminated double quote in {s}")),
+                        },
+                        Some(v) => word.push(v),
+                        None => return Err(format!("unterminated double quote in {s}")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(v) => {
+                    in_word = true;
+                    word.push(v);
+                }
+                None => return Err(format!("trailing backslash in {s}")),
+            },
+            ';' => {
+                if in_word {
+                    command.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                if !command.is_empty() {
+                    commands.push(std::mem::take(&mut command));
+                }
+            }
+            v if v.is_whitespace() => {
+                if in_word {
+                    command.push(std::mem::take(&mut word));
+                    in_word = false;
                 }
+            }
+            v => {
+                in_word = true;
+                word.push(v);
+            }
+        }
+    }
+
+    if in_word {
+        command.push(word);
+    }
+    if !command.is_empty() {
+        commands.push(command);
+    }
+
+    Ok(commands)
+}
+
+/// split the prefixes "-", "@", "+", "!" and ":" from the command path
+fn parse_command_prefix(cmd: &str) -> Option<(ExecCommandFlags, bool, &str)> {
+    let mut flags = ExecCommandFlags::empty();
+    let mut override_argv0 = false;
+    let mut rest = cmd;
 
-                if let Some(s) = cap.get(0) {
-                    command.push(s.as_str().to_string());
+    loop {
+        let flag = match rest.chars().next() {
+            Some('-') => ExecCommandFlags::IGNORE_FAILURE,
+            Some('+') => ExecCommandFlags::FULLY_PRIVILEGED,
+            Some('!') => {
+                // "!!" is accepted for compatibility, treat it as "!"
+                if rest.starts_with("!!") && !flags.contains(ExecCommandFlags::NO_SETUID) {
+                    rest = &rest[1..];
+                }
+                ExecCommandFlags::NO_SETUID
+            }
+            Some(':') => ExecCommandFlags::NO_ENV_EXPAND,
+            Some('@') => {
+                if override_argv0 {
+                    return None;
                 }
+                override_argv0 = true;
+                rest = &rest[1..];
+                continue;
             }
+            _ => break,
+        };
+
+        if flags.contains(flag) {
+            return None;
+        }
+        flags |= flag;
+        rest = &rest[1..];
+    }
+
+    // "+" and "!" can not be used together
+    if flags.contains(ExecCommandFlags::FULLY_PRIVILEGED | ExecCommandFlags::NO_SETUID) {
+        return None;
+    }
+
+    Some((flags, override_argv0, rest))
+}
+
+impl DeserializeWith for ExecCommand {
+    type Item = VecDeque<Self>;
+    fn deserialize_with<'de, D>(de: D) -> Result<Self::Item, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+
+        let mut commands = VecDeque::new();
 
+        for mut command in split_commands(&s).map_err(de::Error::custom)? {
             // get the command and leave the command args
             let exec_cmd = command.remove(0);
-            let path = Path::new(&exec_cmd);
+            let (flags, override_argv0, exec_path) = match parse_command_prefix(&exec_cmd) {
+                Some(v) => v,
+                None => {
+                    return Err(de::Error::invalid_value(
+                        Unexpected::Str(&exec_cmd),
+                        &"valid command prefixes",
+                    ));
+                }
+            };
+            let path = Path::new(exec_path);
 
             if path.is_absolute() && !path.exists() {
                 return Err(de::Error::invalid_value(
-                    Unexpected::Str(&exec_cmd),
+                    Unexpected::Str(exec_path),
                     &"no exist absolute path",
                 ));
             }
 
+            let argv0 = if override_argv0 {
+                if command.is_empty() {
+                    return Err(de::Error::invalid_value(
+                        Unexpected::Str(&exec_cmd),
+                        &"argv[0] following the command with the \"@\" prefix",
+                    ));
+                }
+                Some(command.remove(0))
+            } else {
+                None
+            };
+
             let cmd = path.to_str().unwrap().to_string();
-            let new_command = ExecCommand::new(cmd, command);
+            let mut new_command = ExecCommand::new(cmd, command);
+            new_command.argv0 = argv0;
+            new_command.flags = flags;
             commands.push_back(new_command);
         }
 
         Ok(commands)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_command_prefix, split_commands, ExecCommand, ExecCommandFlags};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_command_prefix() {
+        assert_eq!(
+            parse_command_prefix("/bin/true"),
+            Some((ExecCommandFlags::empty(), false, "/bin/true"))
+        );
+        assert_eq!(
+            parse_command_prefix("-/bin/true"),
+            Some((ExecCommandFlags::IGNORE_FAILURE, false, "/bin/true"))
+        );
+        assert_eq!(
+            parse_command_prefix("@-:/bin/true"),
+            Some((
+                ExecCommandFlags::IGNORE_FAILURE | ExecCommandFlags::NO_ENV_EXPAND,
+                true,
+                "/bin/true"
+            ))
+        );
+        assert_eq!(
+            parse_command_prefix("+/bin/true"),
+            Some((ExecCommandFlags::FULLY_PRIVILEGED, false, "/bin/true"))
+        );
+        assert_eq!(
+            parse_command_prefix("!!/bin/true"),
+            Some((ExecCommandFlags::NO_SETUID, false, "/bin/true"))
+        );
+        assert_eq!(parse_command_prefix("--/bin/true"), None);
+        assert_eq!(parse_command_prefix("@@/bin/true"), None);
+        assert_eq!(parse_command_prefix("+!/bin/true"), None);
+    }
+
+    #[test]
+    fn test_command_privileges() {
+        let mut cmd = ExecCommand::new("/bin/true".to_string(), Vec::new());
+        assert!(cmd.apply_credentials());
+        assert!(cmd.apply_sandboxing());
+
+        cmd.flags = ExecCommandFlags::NO_SETUID;
+        assert!(!cmd.apply_credentials());
+        assert!(cmd.apply_sandboxing());
+
+        cmd.flags = ExecCommandFlags::FULLY_PRIVILEGED;
+        assert!(!cmd.apply_credentials());
+        assert!(!cmd.apply_sandboxing());
+    }
+
+    #[test]
+    fn test_split_commands() {
+        assert_eq!(
+            split_commands("/bin/echo 'a b' \"c \\\" d\" e\\ f").unwrap(),
+            vec![vec!["/bin/echo", "a b", "c \" d", "e f"]]
+        );
+        assert_eq!(
+            split_commands("/bin/echo a; /bin/echo 'b;c';/bin/echo d\\;").unwrap(),
+            vec![
+                vec!["/bin/echo", "a"],
+                vec!["/bin/echo", "b;c"],
+                vec!["/bin/echo", "d;"]
+            ]
+        );
+        assert_eq!(
+            split_commands("/bin/echo ''").unwrap(),
+            vec![vec!["/bin/echo", ""]]
+        );
+        assert!(split_commands("/bin/echo 'a").is_err());
+        assert!(split_commands("/bin/echo \"a").is_err());
+    }
+
+    #[test]
+    fn test_expand_argv() {
+        let mut envs = HashMap::new();
+        envs.insert("MAINPID", "100");
+        envs.insert("OPTS", "-a  -b");
+        let get_env = |key: &str| envs.get(key).map(|v| v.to_string());
+
+        let argv = vec![
+            "$MAINPID",
+            "$OPTS",
+            "${OPTS}",
+            "--pid=${MAINPID}",
+            "--pid=$MAINPID.",
+            "$UNSET",
+            "x${UNSET}y",
+            "$$MAINPID",
+            "100$",
+        ];
+        let cmd = ExecCommand::new(
+            "/bin/kill".to_string(),
+            argv.iter().map(|v| v.to_string()).collect(),
+        );
+        assert_eq!(
+            cmd.expand_argv(get_env),
+            vec![
+                "100",
+                "-a",
+                "-b",
+                "-a  -b",
+                "--pid=100",
+                "--pid=100.",
+                "xy",
+                "$MAINPID",
+                "100$"
+            ]
+        );
+
+        let mut cmd = cmd;
+        cmd.flags = ExecCommandFlags::NO_ENV_EXPAND;
+        assert_eq!(cmd.expand_argv(get_env), argv);
+    }
+}
@@ -0,0 +1,252 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the directories managed for the spawned process, configured by RuntimeDirectory=,
+//! StateDirectory=, CacheDirectory=, LogsDirectory= and ConfigurationDirectory=
+use crate::error::*;
+use nix::dir::{Dir, Type};
+use nix::errno::Errno;
+use nix::fcntl::OFlag;
+use nix::sys::stat::Mode;
+use nix::unistd::{self, FchownatFlags, Gid, Uid};
+use std::fs;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Component, Path, PathBuf};
+
+/// the type of the managed directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecDirectoryType {
+    /// RuntimeDirectory=, under /run
+    Runtime,
+    /// StateDirectory=, under /var/lib
+    State,
+    /// CacheDirectory=, under /var/cache
+    Cache,
+    /// LogsDirectory=, under /var/log
+    Logs,
+    /// ConfigurationDirectory=, under /etc
+    Configuration,
+}
+
+impl ExecDirectoryType {
+    /// the directory under which the managed directories are created
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            ExecDirectoryType::Runtime => "/run",
+            ExecDirectoryType::State => "/var/lib",
+            ExecDirectoryType::Cache => "/var/cache",
+            ExecDirectoryType::Logs => "/var/log",
+            ExecDirectoryType::Configuration => "/etc",
+        }
+    }
+
+    /// the environment variable that passes the directories to the spawned process
+    pub fn env_name(&self) -> &'static str {
+        match self {
+            ExecDirectoryType::Runtime => "RUNTIME_DIRECTORY",
+            ExecDirectoryType::State => "STATE_DIRECTORY",
+            ExecDirectoryType::Cache => "CACHE_DIRECTORY",
+            ExecDirectoryType::Logs => "LOGS_DIRECTORY",
+            ExecDirectoryType::Configuration => "CONFIGURATION_DIRECTORY",
+        }
+    }
+}
+
+/// the managed directories of one type
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecDirectory {
+    /// the paths relative to the prefix of the type
+    pub paths: Vec<PathBuf>,
+    /// the access mode of the innermost directories
+    pub mode: u32,
+}
+
+impl ExecDirectory {
+    /// parse the relative paths and the octal mode, ".." is not allowed in the paths
+    pub fn parse(paths: &[String], mode: &str) -> Result<ExecDirectory, String> {
+        let mode = u32::from_str_radix(mode.trim(), 8)
+            .ok()
+            .filter(|m| *m <= 0o7777)
+            .ok_or_else(|| format!("invalid directory mode: {mode}"))?;
+
+        let mut parsed = Vec::new();
+        for path in paths.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let p = PathBuf::from(path);
+            if !p.components().all(|c| matches!(c, Component::Normal(_))) {
+                return Err(format!("{path:?} is not a valid relative directory"));
+            }
+            parsed.push(p);
+        }
+
+        Ok(ExecDirectory {
+            paths: parsed,
+            mode,
+        })
+    }
+
+    /// return the absolute paths of the directories
+    pub fn full_paths(&self, dir_type: ExecDirectoryType) -> Vec<PathBuf> {
+        self.paths
+            .iter()
+            .map(|p| Path::new(dir_type.prefix()).join(p))
+            .collect()
+    }
+
+    /// create the directories with the mode. The innermost directories are owned by the
+    /// user except the configuration directories, the ownership is changed recursively if
+    /// the directory belonged to another user.
+    pub fn setup(&self, dir_type: ExecDirectoryType, uid: Uid, gid: Gid) -> Result<()> {
+        for path in self.full_paths(dir_type) {
+            fs::create_dir_all(&path).context(IoSnafu)?;
+            fs::set_permissions(&path, fs::Permissions::from_mode(self.mode)).context(IoSnafu)?;
+
+            if dir_type == ExecDirectoryType::Configuration {
+                continue;
+            }
+
+            let metadata = fs::metadata(&path).context(IoSnafu)?;
+            if metadata.uid() != uid.as_raw() || metadata.gid() != gid.as_raw() {
+                chown_recursive(&path, uid, gid)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// remove the directories and their contents
+    pub fn remove(&self, dir_type: ExecDirectoryType) {
+        for path in self.full_paths(dir_type) {
+            if let Err(e) = fs::remove_dir_all(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove directory {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+}
+
+fn chown_recursive(path: &Path, uid: Uid, gid: Gid) -> Result<()> {
+    // the directory itself must not be a symlink either
+    let dir = Dir::open(path, DIR_FLAGS, Mode::empty()).context(NixSnafu)?;
+    chown_dir(dir, uid, gid)
+}
+
+const DIR_FLAGS: OFlag = OFlag::O_RDONLY
+    .union(OFlag::O_DIRECTORY)
+    .union(OFlag::O_NOFOLLOW)
+    .union(OFlag::O_CLOEXEC);
+
+/// change the ownership of the opened directory and everything below it. The entries are
+/// resolved relative to the directory fd and symlinks are never followed, so an entry
+/// replaced by a symlink during the walk can not redirect the chown out of the directory.
+fn chown_dir(mut dir: Dir, uid: Uid, gid: Gid) -> Result<()> {
+    let fd = dir.as_raw_fd();
+    unistd::fchown(fd, Some(uid), Some(gid)).context(NixSnafu)?;
+
+    let mut entries = Vec::new();
+    for entry in dir.iter() {
+        let entry = entry.context(NixSnafu)?;
+        let name = entry.file_name();
+        if name.to_bytes() != b"." && name.to_bytes() != b".." {
+            entries.push((name.to_owned(), entry.file_type()));
+        }
+    }
+
+    for (name, file_type) in entries {
+        if matches!(file_type, Some(Type::Directory) | None) {
+            match Dir::openat(fd, name.as_c_str(), DIR_FLAGS, Mode::empty()) {
+                Ok(child) => {
+                    chown_dir(child, uid, gid)?;
+                    continue;
+                }
+                // not a directory (any more), chown the entry itself below
+                Err(Errno::ENOTDIR) | Err(Errno::ELOOP) => {}
+                Err(e) => return Err(e).context(NixSnafu),
+            }
+        }
+
+        unistd::fchownat(
+            Some(fd),
+            name.as_c_str(),
+            Some(uid),
+            Some(gid),
+            FchownatFlags::NoFollowSymlink,
+        )
+        .context(NixSnafu)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chown_recursive, ExecDirectory, ExecDirectoryType};
+    use nix::unistd::{Gid, Uid};
+    use std::fs;
+    use std::os::unix::fs::{symlink, MetadataExt};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_directory() {
+        let paths = vec!["foo".to_string(), "bar/baz".to_string()];
+        let dir = ExecDirectory::parse(&paths, "0750").unwrap();
+        assert_eq!(dir.mode, 0o750);
+        assert_eq!(
+            dir.full_paths(ExecDirectoryType::State),
+            vec![
+                PathBuf::from("/var/lib/foo"),
+                PathBuf::from("/var/lib/bar/baz")
+            ]
+        );
+
+        assert!(ExecDirectory::parse(&["/foo".to_string()], "0755").is_err());
+        assert!(ExecDirectory::parse(&["foo/../bar".to_string()], "0755").is_err());
+        assert!(ExecDirectory::parse(&["./foo".to_string()], "0755").is_err());
+        assert!(ExecDirectory::parse(&[], "0855").is_err());
+    }
+
+    #[test]
+    fn test_chown_recursive() {
+        if !nix::unistd::getuid().is_root() {
+            return;
+        }
+
+        let dir = std::env::temp_dir().join(format!("sysmaster-chown-{}", std::process::id()));
+        let outside = dir.join("outside");
+        let top = dir.join("top");
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(top.join("a/b")).unwrap();
+        fs::write(top.join("a/b/file"), "").unwrap();
+        fs::write(outside.join("file"), "").unwrap();
+        symlink(outside.join("file"), top.join("a/link")).unwrap();
+        symlink(&outside, top.join("a/dirlink")).unwrap();
+
+        let nobody = Uid::from_raw(65534);
+        chown_recursive(&top, nobody, Gid::from_raw(65534)).unwrap();
+
+        for path in ["", "a", "a/b", "a/b/file", "a/link", "a/dirlink"] {
+            let metadata = fs::symlink_metadata(top.join(path)).unwrap();
+            assert_eq!(metadata.uid(), 65534);
+            assert_eq!(metadata.gid(), 65534);
+        }
+        // the targets of the symlinks are left alone
+        assert_eq!(fs::metadata(&outside).unwrap().uid(), 0);
+        assert_eq!(fs::metadata(outside.join("file")).unwrap().uid(), 0);
+
+        // a symlink in place of the directory itself is refused
+        assert!(chown_recursive(&top.join("a/dirlink"), nobody, Gid::from_raw(65534)).is_err());
+        assert_eq!(fs::metadata(&outside).unwrap().uid(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the resource limits of the spawned process, configured by Limit*=
+use crate::serialize::DeserializeWith;
+use nix::sys::resource::{self, Resource};
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+/// the rlimit value that means no limit
+pub const RLIM_INFINITY: u64 = libc::RLIM_INFINITY;
+
+/// the soft and hard limit of a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rlimit {
+    /// the soft limit
+    pub soft: u64,
+    /// the hard limit, which is not less than the soft limit
+    pub hard: u64,
+}
+
+/// the value in the unit file may be a toml integer or string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RlimitValue {
+    Int(i64),
+    Str(String),
+}
+
+impl RlimitValue {
+    fn to_str(&self) -> String {
+        match self {
+            RlimitValue::Int(v) => v.to_string(),
+            RlimitValue::Str(s) => s.to_string(),
+        }
+    }
+}
+
+impl Rlimit {
+    /// parse "value" or "soft:hard", a single value sets both the soft and the hard limit.
+    /// The value is "infinity" or a number, which may be followed by a K, M, G, T, P or E
+    /// suffix to the base of 1024.
+    pub fn parse(s: &str) -> Result<Rlimit, String> {
+        Rlimit::parse_with(s, parse_rlim_value)
+    }
+
+    /// parse the value of LimitNICE, the number with a sign is a nice level between -20 and
+    /// 19 which is converted to the rlimit value 20 - nice, others are raw rlimit values.
+    pub fn parse_nice(s: &str) -> Result<Rlimit, String> {
+        Rlimit::parse_with(s, parse_nice_value)
+    }
+
+    fn parse_with(s: &str, f: fn(&str) -> Result<u64, String>) -> Result<Rlimit, String> {
+        let s = s.trim();
+        let (soft, hard) = match s.split_once(':') {
+            Some((soft, hard)) => (f(soft)?, f(hard)?),
+            None => {
+                let v = f(s)?;
+                (v, v)
+            }
+        };
+
+        if soft > hard {
+            return Err(format!(
+                "the soft limit is greater than the hard limit: {s}"
+            ));
+        }
+
+        Ok(Rlimit { soft, hard })
+    }
+
+    /// set the limit of the resource for the current process
+    pub fn apply(&self, resource: Resource) -> nix::Result<()> {
+        resource::setrlimit(resource, self.soft, self.hard)
+    }
+
+    /// deserialize the value of LimitNICE
+    pub fn deserialize_nice<'de, D>(de: D) -> Result<Rlimit, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = RlimitValue::deserialize(de)?;
+        Rlimit::parse_nice(&v.to_str()).map_err(de::Error::custom)
+    }
+}
+
+impl DeserializeWith for Rlimit {
+    type Item = Self;
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v = RlimitValue::deserialize(de)?;
+        Rlimit::parse(&v.to_str()).map_err(de::Error::custom)
+    }
+}
+
+fn parse_rlim_value(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if s == "infinity" {
+        return Ok(RLIM_INFINITY);
+    }
+
+    let (num, shift) = match s.char_indices().last() {
+        Some((i, c)) if !c.is_ascii_digit() => {
+            let shift = match c {
+                'K' => 10,
+                'M' => 20,
+                'G' => 30,
+                'T' => 40,
+                'P' => 50,
+                'E' => 60,
+                _ => return Err(format!("invalid resource limit: {s}")),
+            };
+            (&s[..i], shift)
+        }
+        _ => (s, 0),
+    };
+
+    let v = num
+        .parse::<u64>()
+        .map_err(|_| format!("invalid resource limit: {s}"))?;
+    let v = v
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("resource limit is out of range: {s}"))?;
+
+    if v == RLIM_INFINITY {
+        return Err(format!("resource limit is out of range: {s}"));
+    }
+
+    Ok(v)
+}
+
+fn parse_nice_value(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if !s.starts_with('+') && !s.starts_with('-') {
+        return parse_rlim_value(s);
+    }
+
+    match s.parse::<i64>() {
+        Ok(nice) if (-20..20).contains(&nice) => Ok((20 - nice) as u64),
+        _ => Err(format!("invalid nice level: {s}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rlimit, RLIM_INFINITY};
+
+    #[test]
+    fn test_parse_rlimit() {
+        assert_eq!(
+            Rlimit::parse("1024"),
+            Ok(Rlimit {
+                soft: 1024,
+                hard: 1024
+            })
+        );
+        assert_eq!(
+            Rlimit::parse("1024:4096"),
+            Ok(Rlimit {
+                soft: 1024,
+                hard: 4096
+            })
+        );
+        assert_eq!(
+            Rlimit::parse("8M:infinity"),
+            Ok(Rlimit {
+                soft: 8 << 20,
+                hard: RLIM_INFINITY
+            })
+        );
+        assert_eq!(
+            Rlimit::parse("infinity"),
+            Ok(Rlimit {
+                soft: RLIM_INFINITY,
+                hard: RLIM_INFINITY
+            })
+        );
+        assert!(Rlimit::parse("4096:1024").is_err());
+        assert!(Rlimit::parse("-1").is_err());
+        assert!(Rlimit::parse("10X").is_err());
+        assert!(Rlimit::parse("").is_err());
+        assert!(Rlimit::parse("16E").is_err());
+    }
+
+    #[test]
+    fn test_parse_nice() {
+        assert_eq!(Rlimit::parse_nice("-20").unwrap().soft, 40);
+        assert_eq!(Rlimit::parse_nice("+19").unwrap().soft, 1);
+        assert_eq!(Rlimit::parse_nice("30").unwrap().soft, 30);
+        assert!(Rlimit::parse_nice("-21").is_err());
+        assert!(Rlimit::parse_nice("+20").is_err());
+    }
+}
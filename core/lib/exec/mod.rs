@@ -12,6 +12,20 @@
 
 //! execute module
 mod base;
+mod capability;
 mod cmd;
-pub use base::{ExecContext, ExecFlags, ExecParameters};
-pub use cmd::ExecCommand;
+mod directory;
+mod limit;
+mod namespace;
+mod sched;
+mod seccomp;
+pub use base::{ExecContext, ExecFlags, ExecInput, ExecOutput, ExecParameters};
+pub use capability::ExecCapabilities;
+pub use cmd::{ExecCommand, ExecCommandFlags};
+pub use directory::{ExecDirectory, ExecDirectoryType};
+pub use limit::{Rlimit, RLIM_INFINITY};
+pub use namespace::{BindPath, ExecNamespace, ProtectHome, ProtectSystem};
+pub use sched::{
+    apply_cpu_affinity, parse_cpu_set, CpuSchedulingPolicy, ExecScheduling, IoSchedulingClass,
+};
+pub use seccomp::SyscallFilter;
@@ -0,0 +1,284 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the namespace sandboxing of the spawned process
+use crate::serialize::DeserializeWith;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::path::PathBuf;
+
+/// the value in the unit file may be a toml boolean or string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BoolOrString {
+    Bool(bool),
+    Str(String),
+}
+
+impl BoolOrString {
+    fn into_string(self) -> String {
+        match self {
+            BoolOrString::Bool(true) => "yes".to_string(),
+            BoolOrString::Bool(false) => "no".to_string(),
+            BoolOrString::Str(s) => s,
+        }
+    }
+}
+
+/// which parts of the file system hierarchy are read-only for the spawned process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ProtectSystem {
+    /// not protected
+    #[default]
+    No,
+    /// /usr, /boot and /efi are read-only
+    Yes,
+    /// /etc is read-only too
+    Full,
+    /// the entire file system hierarchy is read-only, except /dev, /proc and /sys
+    Strict,
+}
+
+impl ProtectSystem {
+    /// parse the value of ProtectSystem
+    pub fn parse(s: &str) -> Result<ProtectSystem, String> {
+        match s.trim() {
+            "no" | "false" => Ok(ProtectSystem::No),
+            "yes" | "true" => Ok(ProtectSystem::Yes),
+            "full" => Ok(ProtectSystem::Full),
+            "strict" => Ok(ProtectSystem::Strict),
+            s => Err(format!("invalid ProtectSystem: {s}")),
+        }
+    }
+}
+
+impl DeserializeWith for ProtectSystem {
+    type Item = Self;
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = BoolOrString::deserialize(de)?.into_string();
+        ProtectSystem::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+/// how /home, /root and /run/user are protected for the spawned process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ProtectHome {
+    /// not protected
+    #[default]
+    No,
+    /// inaccessible
+    Yes,
+    /// read-only
+    ReadOnly,
+    /// replaced by empty read-only tmpfs
+    Tmpfs,
+}
+
+impl ProtectHome {
+    /// parse the value of ProtectHome
+    pub fn parse(s: &str) -> Result<ProtectHome, String> {
+        match s.trim() {
+            "no" | "false" => Ok(ProtectHome::No),
+            "yes" | "true" => Ok(ProtectHome::Yes),
+            "read-only" => Ok(ProtectHome::ReadOnly),
+            "tmpfs" => Ok(ProtectHome::Tmpfs),
+            s => Err(format!("invalid ProtectHome: {s}")),
+        }
+    }
+}
+
+impl DeserializeWith for ProtectHome {
+    type Item = Self;
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = BoolOrString::deserialize(de)?.into_string();
+        ProtectHome::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+/// a host path bind-mounted into the mount namespace of the spawned process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindPath {
+    /// the path on the host
+    pub source: PathBuf,
+    /// the path in the namespace, which is relative to RootDirectory=
+    pub destination: PathBuf,
+    /// BindReadOnlyPaths=
+    pub read_only: bool,
+    /// whether the mounts under the source are bound too, "rbind" or "norbind"
+    pub recursive: bool,
+    /// the source prefixed with "-" is ignored if it does not exist
+    pub ignore_missing: bool,
+}
+
+/// the namespace settings of the spawned process
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecNamespace {
+    /// ProtectSystem=
+    pub protect_system: ProtectSystem,
+    /// ProtectHome=
+    pub protect_home: ProtectHome,
+    /// PrivateTmp=, mount private /tmp and /var/tmp
+    pub private_tmp: bool,
+    /// ReadOnlyPaths=, the path prefixed with "-" is ignored if it does not exist
+    pub read_only_paths: Vec<(PathBuf, bool)>,
+    /// InaccessiblePaths=, the path prefixed with "-" is ignored if it does not exist
+    pub inaccessible_paths: Vec<(PathBuf, bool)>,
+    /// PrivateNetwork=, run in a network namespace with only the loopback device
+    pub private_network: bool,
+    /// PrivateIPC=, run in an IPC namespace
+    pub private_ipc: bool,
+    /// ProtectHostname=, run in a UTS namespace, changing the hostname does not affect the host
+    pub protect_hostname: bool,
+    /// PrivateUsers=, run in a user namespace which only maps root and the user of the process
+    pub private_users: bool,
+    /// RootDirectory=, the root directory pivoted to
+    pub root_directory: Option<PathBuf>,
+    /// MountAPIVFS=, mount /proc, /sys and /dev in RootDirectory=
+    pub mount_api_vfs: bool,
+    /// BindPaths= and BindReadOnlyPaths=
+    pub bind_paths: Vec<BindPath>,
+}
+
+impl ExecNamespace {
+    /// parse the paths of ReadOnlyPaths and InaccessiblePaths, which must be absolute
+    pub fn parse_paths(paths: &[String]) -> Result<Vec<(PathBuf, bool)>, String> {
+        let mut parsed = Vec::new();
+        for path in paths {
+            if path.is_empty() {
+                continue;
+            }
+
+            let ignore_missing = path.starts_with('-');
+            let p = PathBuf::from(path.trim_start_matches('-'));
+            if !p.is_absolute() {
+                return Err(format!("{p:?} is not an absolute path"));
+            }
+            parsed.push((p, ignore_missing));
+        }
+
+        Ok(parsed)
+    }
+
+    /// parse the entries of BindPaths and BindReadOnlyPaths, like "[-]SOURCE[:DESTINATION[:OPTIONS]]",
+    /// the destination is the same as the source if it is omitted
+    pub fn parse_bind_paths(paths: &[String], read_only: bool) -> Result<Vec<BindPath>, String> {
+        let mut parsed = Vec::new();
+        for path in paths {
+            if path.is_empty() {
+                continue;
+            }
+
+            let ignore_missing = path.starts_with('-');
+            let mut fields = path.trim_start_matches('-').splitn(3, ':');
+            let source = PathBuf::from(fields.next().unwrap_or_default());
+            let destination = fields.next().map_or_else(|| source.clone(), PathBuf::from);
+            let recursive = match fields.next() {
+                None | Some("rbind") => true,
+                Some("norbind") => false,
+                Some(o) => return Err(format!("invalid bind option {o} in {path}")),
+            };
+
+            if !source.is_absolute() || !destination.is_absolute() {
+                return Err(format!("{path} is not an absolute path"));
+            }
+            parsed.push(BindPath {
+                source,
+                destination,
+                read_only,
+                recursive,
+                ignore_missing,
+            });
+        }
+
+        Ok(parsed)
+    }
+
+    /// whether the spawned process needs its own mount namespace
+    pub fn needs_mount_namespace(&self) -> bool {
+        self.protect_system != ProtectSystem::No
+            || self.protect_home != ProtectHome::No
+            || self.private_tmp
+            || !self.read_only_paths.is_empty()
+            || !self.inaccessible_paths.is_empty()
+            || self.root_directory.is_some()
+            || !self.bind_paths.is_empty()
+    }
+
+    /// whether the namespaces or the private temporary directories are shared by the
+    /// processes of the unit and the units configured by JoinsNamespaceOf=
+    pub fn needs_shared_namespace(&self) -> bool {
+        self.private_network || self.private_ipc || self.private_tmp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BindPath, ExecNamespace, ProtectHome, ProtectSystem};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_protect() {
+        assert_eq!(ProtectSystem::parse("strict"), Ok(ProtectSystem::Strict));
+        assert_eq!(ProtectSystem::parse("true"), Ok(ProtectSystem::Yes));
+        assert!(ProtectSystem::parse("read-only").is_err());
+        assert_eq!(ProtectHome::parse("read-only"), Ok(ProtectHome::ReadOnly));
+        assert_eq!(ProtectHome::parse("no"), Ok(ProtectHome::No));
+        assert!(ProtectHome::parse("full").is_err());
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        let paths = vec!["/etc".to_string(), "-/opt/foo".to_string()];
+        assert_eq!(
+            ExecNamespace::parse_paths(&paths),
+            Ok(vec![
+                (PathBuf::from("/etc"), false),
+                (PathBuf::from("/opt/foo"), true)
+            ])
+        );
+        assert!(ExecNamespace::parse_paths(&["etc".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_bind_paths() {
+        let paths = vec![
+            "/srv/data".to_string(),
+            "-/opt/foo:/foo:norbind".to_string(),
+        ];
+        assert_eq!(
+            ExecNamespace::parse_bind_paths(&paths, true),
+            Ok(vec![
+                BindPath {
+                    source: PathBuf::from("/srv/data"),
+                    destination: PathBuf::from("/srv/data"),
+                    read_only: true,
+                    recursive: true,
+                    ignore_missing: false,
+                },
+                BindPath {
+                    source: PathBuf::from("/opt/foo"),
+                    destination: PathBuf::from("/foo"),
+                    read_only: true,
+                    recursive: false,
+                    ignore_missing: true,
+                },
+            ])
+        );
+        assert!(ExecNamespace::parse_bind_paths(&["/a:b".to_string()], false).is_err());
+        assert!(ExecNamespace::parse_bind_paths(&["/a:/b:ro".to_string()], false).is_err());
+    }
+}
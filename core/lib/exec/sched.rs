@@ -0,0 +1,284 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the scheduling of the spawned process, configured by Nice=, CPUSchedulingPolicy=,
+//! CPUSchedulingPriority=, CPUAffinity=, IOSchedulingClass=, IOSchedulingPriority= and
+//! OOMScoreAdjust=
+use crate::error::*;
+use nix::errno::Errno;
+use nix::sched::{self, CpuSet};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const OOM_SCORE_ADJ_PATH: &str = "/proc/self/oom_score_adj";
+
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+/// the cpu scheduling policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CpuSchedulingPolicy {
+    /// SCHED_OTHER
+    #[serde(alias = "other")]
+    Other,
+    /// SCHED_BATCH
+    #[serde(alias = "batch")]
+    Batch,
+    /// SCHED_IDLE
+    #[serde(alias = "idle")]
+    Idle,
+    /// SCHED_FIFO
+    #[serde(alias = "fifo")]
+    Fifo,
+    /// SCHED_RR
+    #[serde(alias = "rr")]
+    Rr,
+}
+
+impl CpuSchedulingPolicy {
+    fn to_raw(self) -> libc::c_int {
+        match self {
+            CpuSchedulingPolicy::Other => libc::SCHED_OTHER,
+            CpuSchedulingPolicy::Batch => libc::SCHED_BATCH,
+            CpuSchedulingPolicy::Idle => libc::SCHED_IDLE,
+            CpuSchedulingPolicy::Fifo => libc::SCHED_FIFO,
+            CpuSchedulingPolicy::Rr => libc::SCHED_RR,
+        }
+    }
+
+    fn is_realtime(self) -> bool {
+        matches!(self, CpuSchedulingPolicy::Fifo | CpuSchedulingPolicy::Rr)
+    }
+}
+
+/// the I/O scheduling class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IoSchedulingClass {
+    /// IOPRIO_CLASS_NONE
+    #[serde(alias = "none")]
+    None,
+    /// IOPRIO_CLASS_RT
+    #[serde(alias = "realtime")]
+    Realtime,
+    /// IOPRIO_CLASS_BE
+    #[serde(alias = "best-effort")]
+    BestEffort,
+    /// IOPRIO_CLASS_IDLE
+    #[serde(alias = "idle")]
+    Idle,
+}
+
+/// the scheduling settings of the spawned process
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecScheduling {
+    /// Nice=, between -20 and 19
+    pub nice: Option<i32>,
+    /// CPUSchedulingPolicy=
+    pub policy: Option<CpuSchedulingPolicy>,
+    /// CPUSchedulingPriority=, between 1 and 99 for the realtime policies
+    pub priority: Option<i32>,
+    /// CPUAffinity=, the cpu indexes
+    pub cpu_affinity: Vec<usize>,
+    /// IOSchedulingClass=
+    pub io_class: Option<IoSchedulingClass>,
+    /// IOSchedulingPriority=, between 0 and 7
+    pub io_priority: Option<i32>,
+    /// OOMScoreAdjust=, between -1000 and 1000
+    pub oom_score_adjust: Option<i32>,
+}
+
+impl ExecScheduling {
+    /// check the ranges of the values
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(nice) = self.nice {
+            if !(-20..20).contains(&nice) {
+                return Err(format!("invalid Nice: {nice}"));
+            }
+        }
+
+        if let Some(priority) = self.priority {
+            let range = match self.policy {
+                Some(p) if p.is_realtime() => 1..=99,
+                _ => 0..=0,
+            };
+            if !range.contains(&priority) {
+                return Err(format!(
+                    "invalid CPUSchedulingPriority {priority} for {:?}",
+                    self.policy
+                ));
+            }
+        }
+
+        if let Some(priority) = self.io_priority {
+            if !(0..8).contains(&priority) {
+                return Err(format!("invalid IOSchedulingPriority: {priority}"));
+            }
+        }
+
+        if let Some(adjust) = self.oom_score_adjust {
+            if !(-1000..=1000).contains(&adjust) {
+                return Err(format!("invalid OOMScoreAdjust: {adjust}"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// apply the settings to the current process, raising them needs the privilege
+    pub fn apply(&self) -> Result<()> {
+        if let Some(adjust) = self.oom_score_adjust {
+            fs::write(OOM_SCORE_ADJ_PATH, adjust.to_string()).context(IoSnafu)?;
+        }
+
+        if let Some(nice) = self.nice {
+            let ret = unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) };
+            Errno::result(ret).context(NixSnafu)?;
+        }
+
+        if let Some(policy) = self.policy {
+            let param = libc::sched_param {
+                sched_priority: match policy.is_realtime() {
+                    true => self.priority.unwrap_or(1),
+                    false => 0,
+                },
+            };
+            let ret = unsafe { libc::sched_setscheduler(0, policy.to_raw(), &param) };
+            Errno::result(ret).context(NixSnafu)?;
+        }
+
+        if !self.cpu_affinity.is_empty() {
+            apply_cpu_affinity(&self.cpu_affinity).context(NixSnafu)?;
+        }
+
+        self.apply_io_priority().context(NixSnafu)
+    }
+
+    fn apply_io_priority(&self) -> nix::Result<()> {
+        // the priority alone implies the best-effort class
+        let class = match (self.io_class, self.io_priority) {
+            (Some(class), _) => class,
+            (None, Some(_)) => IoSchedulingClass::BestEffort,
+            (None, None) => return Ok(()),
+        };
+
+        let (class, data) = match class {
+            IoSchedulingClass::None => (0, 0),
+            IoSchedulingClass::Realtime => (1, self.io_priority.unwrap_or(4)),
+            IoSchedulingClass::BestEffort => (2, self.io_priority.unwrap_or(4)),
+            IoSchedulingClass::Idle => (3, 0),
+        };
+        let ioprio = (class << IOPRIO_CLASS_SHIFT) | data;
+        let ret = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) };
+        Errno::result(ret).map(drop)
+    }
+}
+
+/// parse the cpu indexes and ranges divided by whitespace or ",", like "0-3 8"
+pub fn parse_cpu_set(s: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for item in s
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|i| !i.is_empty())
+    {
+        let parse = |v: &str| {
+            v.parse::<usize>()
+                .ok()
+                .filter(|cpu| *cpu < CpuSet::count())
+                .ok_or_else(|| format!("invalid cpu: {item}"))
+        };
+
+        let (start, end) = match item.split_once('-') {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => (parse(item)?, parse(item)?),
+        };
+        if start > end {
+            return Err(format!("invalid cpu range: {item}"));
+        }
+
+        for cpu in start..=end {
+            if !cpus.contains(&cpu) {
+                cpus.push(cpu);
+            }
+        }
+    }
+
+    cpus.sort_unstable();
+    Ok(cpus)
+}
+
+/// set the cpu affinity of the current process, which is inherited by the children
+pub fn apply_cpu_affinity(cpus: &[usize]) -> nix::Result<()> {
+    let mut cpu_set = CpuSet::new();
+    for cpu in cpus {
+        cpu_set.set(*cpu)?;
+    }
+
+    sched::sched_setaffinity(Pid::from_raw(0), &cpu_set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_cpu_set, CpuSchedulingPolicy, ExecScheduling};
+
+    #[test]
+    fn test_parse_cpu_set() {
+        assert_eq!(parse_cpu_set("0-3 8"), Ok(vec![0, 1, 2, 3, 8]));
+        assert_eq!(parse_cpu_set("2,1 1-2"), Ok(vec![1, 2]));
+        assert_eq!(parse_cpu_set(""), Ok(vec![]));
+        assert!(parse_cpu_set("3-1").is_err());
+        assert!(parse_cpu_set("a").is_err());
+        assert!(parse_cpu_set("0-").is_err());
+        assert!(parse_cpu_set("100000").is_err());
+    }
+
+    #[test]
+    fn test_validate_scheduling() {
+        let sched = ExecScheduling {
+            nice: Some(-5),
+            policy: Some(CpuSchedulingPolicy::Fifo),
+            priority: Some(50),
+            io_priority: Some(7),
+            oom_score_adjust: Some(-1000),
+            ..Default::default()
+        };
+        assert!(sched.validate().is_ok());
+
+        let invalid = [
+            ExecScheduling {
+                nice: Some(20),
+                ..Default::default()
+            },
+            ExecScheduling {
+                policy: Some(CpuSchedulingPolicy::Batch),
+                priority: Some(10),
+                ..Default::default()
+            },
+            ExecScheduling {
+                policy: Some(CpuSchedulingPolicy::Rr),
+                priority: Some(0),
+                ..Default::default()
+            },
+            ExecScheduling {
+                io_priority: Some(8),
+                ..Default::default()
+            },
+            ExecScheduling {
+                oom_score_adjust: Some(1001),
+                ..Default::default()
+            },
+        ];
+        for sched in invalid {
+            assert!(sched.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,1212 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the seccomp system call filter of the spawned process, configured by SystemCallFilter=,
+//! SystemCallErrorNumber= and SystemCallArchitectures=
+use nix::errno::Errno;
+use std::collections::BTreeSet;
+
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_DATA: u32 = 0x0000_ffff;
+
+/// BPF_LD | BPF_W | BPF_ABS
+const BPF_LD_W_ABS: u16 = 0x20;
+/// BPF_JMP | BPF_JEQ | BPF_K
+const BPF_JMP_JEQ_K: u16 = 0x15;
+/// BPF_JMP | BPF_JGE | BPF_K
+const BPF_JMP_JGE_K: u16 = 0x35;
+/// BPF_RET | BPF_K
+const BPF_RET_K: u16 = 0x06;
+const BPF_MAXINSNS: usize = 4096;
+
+/// the offsets of nr and arch in struct seccomp_data
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+
+#[cfg(target_arch = "x86_64")]
+const NATIVE_ARCH: Option<(&str, u32)> = Some(("x86-64", 0xc000_003e));
+#[cfg(target_arch = "aarch64")]
+const NATIVE_ARCH: Option<(&str, u32)> = Some(("arm64", 0xc000_00b7));
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const NATIVE_ARCH: Option<(&str, u32)> = None;
+
+/// the system calls of the x32 abi share the audit arch with x86-64
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: Option<u32> = Some(0x4000_0000);
+#[cfg(not(target_arch = "x86_64"))]
+const X32_SYSCALL_BIT: Option<u32> = None;
+
+macro_rules! syscall_table {
+    ($($name:ident),* $(,)?) => {
+        &[$((stringify!($name), libc::$name as i64)),*]
+    };
+}
+
+/// the system calls known on all the supported architectures
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const SYSCALLS: &[(&str, i64)] = syscall_table![
+    SYS_accept,
+    SYS_accept4,
+    SYS_acct,
+    SYS_add_key,
+    SYS_adjtimex,
+    SYS_bind,
+    SYS_bpf,
+    SYS_brk,
+    SYS_capget,
+    SYS_capset,
+    SYS_chdir,
+    SYS_chroot,
+    SYS_clock_adjtime,
+    SYS_clock_getres,
+    SYS_clock_gettime,
+    SYS_clock_nanosleep,
+    SYS_clock_settime,
+    SYS_clone,
+    SYS_clone3,
+    SYS_close,
+    SYS_close_range,
+    SYS_connect,
+    SYS_copy_file_range,
+    SYS_delete_module,
+    SYS_dup,
+    SYS_dup3,
+    SYS_epoll_create1,
+    SYS_epoll_ctl,
+    SYS_epoll_pwait,
+    SYS_epoll_pwait2,
+    SYS_eventfd2,
+    SYS_execve,
+    SYS_execveat,
+    SYS_exit,
+    SYS_exit_group,
+    SYS_faccessat,
+    SYS_faccessat2,
+    SYS_fadvise64,
+    SYS_fallocate,
+    SYS_fanotify_init,
+    SYS_fanotify_mark,
+    SYS_fchdir,
+    SYS_fchmod,
+    SYS_fchmodat,
+    SYS_fchown,
+    SYS_fchownat,
+    SYS_fcntl,
+    SYS_fdatasync,
+    SYS_fgetxattr,
+    SYS_finit_module,
+    SYS_flistxattr,
+    SYS_flock,
+    SYS_fremovexattr,
+    SYS_fsconfig,
+    SYS_fsetxattr,
+    SYS_fsmount,
+    SYS_fsopen,
+    SYS_fspick,
+    SYS_fstat,
+    SYS_fstatfs,
+    SYS_fsync,
+    SYS_ftruncate,
+    SYS_futex,
+    SYS_get_mempolicy,
+    SYS_get_robust_list,
+    SYS_getcpu,
+    SYS_getcwd,
+    SYS_getdents64,
+    SYS_getegid,
+    SYS_geteuid,
+    SYS_getgid,
+    SYS_getgroups,
+    SYS_getitimer,
+    SYS_getpeername,
+    SYS_getpgid,
+    SYS_getpid,
+    SYS_getppid,
+    SYS_getpriority,
+    SYS_getrandom,
+    SYS_getresgid,
+    SYS_getresuid,
+    SYS_getrusage,
+    SYS_getsid,
+    SYS_getsockname,
+    SYS_getsockopt,
+    SYS_gettid,
+    SYS_gettimeofday,
+    SYS_getuid,
+    SYS_getxattr,
+    SYS_init_module,
+    SYS_inotify_add_watch,
+    SYS_inotify_init1,
+    SYS_inotify_rm_watch,
+    SYS_io_cancel,
+    SYS_io_destroy,
+    SYS_io_getevents,
+    SYS_io_setup,
+    SYS_io_submit,
+    SYS_io_uring_enter,
+    SYS_io_uring_register,
+    SYS_io_uring_setup,
+    SYS_ioctl,
+    SYS_ioprio_get,
+    SYS_ioprio_set,
+    SYS_kcmp,
+    SYS_kexec_load,
+    SYS_keyctl,
+    SYS_kill,
+    SYS_landlock_add_rule,
+    SYS_landlock_create_ruleset,
+    SYS_landlock_restrict_self,
+    SYS_lgetxattr,
+    SYS_linkat,
+    SYS_listen,
+    SYS_listxattr,
+    SYS_llistxattr,
+    SYS_lookup_dcookie,
+    SYS_lremovexattr,
+    SYS_lseek,
+    SYS_lsetxattr,
+    SYS_madvise,
+    SYS_mbind,
+    SYS_membarrier,
+    SYS_memfd_create,
+    SYS_migrate_pages,
+    SYS_mincore,
+    SYS_mkdirat,
+    SYS_mknodat,
+    SYS_mlock,
+    SYS_mlock2,
+    SYS_mlockall,
+    SYS_mmap,
+    SYS_mount,
+    SYS_mount_setattr,
+    SYS_move_mount,
+    SYS_move_pages,
+    SYS_mprotect,
+    SYS_mq_getsetattr,
+    SYS_mq_notify,
+    SYS_mq_open,
+    SYS_mq_timedreceive,
+    SYS_mq_timedsend,
+    SYS_mq_unlink,
+    SYS_mremap,
+    SYS_msgctl,
+    SYS_msgget,
+    SYS_msgrcv,
+    SYS_msgsnd,
+    SYS_msync,
+    SYS_munlock,
+    SYS_munlockall,
+    SYS_munmap,
+    SYS_name_to_handle_at,
+    SYS_nanosleep,
+    SYS_newfstatat,
+    SYS_nfsservctl,
+    SYS_open_by_handle_at,
+    SYS_open_tree,
+    SYS_openat,
+    SYS_openat2,
+    SYS_perf_event_open,
+    SYS_personality,
+    SYS_pidfd_getfd,
+    SYS_pidfd_open,
+    SYS_pidfd_send_signal,
+    SYS_pipe2,
+    SYS_pivot_root,
+    SYS_pkey_alloc,
+    SYS_pkey_free,
+    SYS_pkey_mprotect,
+    SYS_ppoll,
+    SYS_prctl,
+    SYS_pread64,
+    SYS_preadv,
+    SYS_preadv2,
+    SYS_prlimit64,
+    SYS_process_madvise,
+    SYS_process_vm_readv,
+    SYS_process_vm_writev,
+    SYS_pselect6,
+    SYS_ptrace,
+    SYS_pwrite64,
+    SYS_pwritev,
+    SYS_pwritev2,
+    SYS_quotactl,
+    SYS_read,
+    SYS_readahead,
+    SYS_readlinkat,
+    SYS_readv,
+    SYS_reboot,
+    SYS_recvfrom,
+    SYS_recvmmsg,
+    SYS_recvmsg,
+    SYS_remap_file_pages,
+    SYS_removexattr,
+    SYS_renameat2,
+    SYS_request_key,
+    SYS_restart_syscall,
+    SYS_rseq,
+    SYS_rt_sigaction,
+    SYS_rt_sigpending,
+    SYS_rt_sigprocmask,
+    SYS_rt_sigqueueinfo,
+    SYS_rt_sigreturn,
+    SYS_rt_sigsuspend,
+    SYS_rt_sigtimedwait,
+    SYS_rt_tgsigqueueinfo,
+    SYS_sched_get_priority_max,
+    SYS_sched_get_priority_min,
+    SYS_sched_getaffinity,
+    SYS_sched_getattr,
+    SYS_sched_getparam,
+    SYS_sched_getscheduler,
+    SYS_sched_rr_get_interval,
+    SYS_sched_setaffinity,
+    SYS_sched_setattr,
+    SYS_sched_setparam,
+    SYS_sched_setscheduler,
+    SYS_sched_yield,
+    SYS_seccomp,
+    SYS_semctl,
+    SYS_semget,
+    SYS_semop,
+    SYS_semtimedop,
+    SYS_sendfile,
+    SYS_sendmmsg,
+    SYS_sendmsg,
+    SYS_sendto,
+    SYS_set_mempolicy,
+    SYS_set_robust_list,
+    SYS_set_tid_address,
+    SYS_setdomainname,
+    SYS_setfsgid,
+    SYS_setfsuid,
+    SYS_setgid,
+    SYS_setgroups,
+    SYS_sethostname,
+    SYS_setitimer,
+    SYS_setns,
+    SYS_setpgid,
+    SYS_setpriority,
+    SYS_setregid,
+    SYS_setresgid,
+    SYS_setresuid,
+    SYS_setreuid,
+    SYS_setsid,
+    SYS_setsockopt,
+    SYS_settimeofday,
+    SYS_setuid,
+    SYS_setxattr,
+    SYS_shmat,
+    SYS_shmctl,
+    SYS_shmdt,
+    SYS_shmget,
+    SYS_shutdown,
+    SYS_sigaltstack,
+    SYS_signalfd4,
+    SYS_socket,
+    SYS_socketpair,
+    SYS_splice,
+    SYS_statfs,
+    SYS_statx,
+    SYS_swapoff,
+    SYS_swapon,
+    SYS_symlinkat,
+    SYS_sync,
+    SYS_syncfs,
+    SYS_sysinfo,
+    SYS_syslog,
+    SYS_tee,
+    SYS_tgkill,
+    SYS_timer_create,
+    SYS_timer_delete,
+    SYS_timer_getoverrun,
+    SYS_timer_gettime,
+    SYS_timer_settime,
+    SYS_timerfd_create,
+    SYS_timerfd_gettime,
+    SYS_timerfd_settime,
+    SYS_times,
+    SYS_tkill,
+    SYS_truncate,
+    SYS_umask,
+    SYS_umount2,
+    SYS_uname,
+    SYS_unlinkat,
+    SYS_unshare,
+    SYS_userfaultfd,
+    SYS_utimensat,
+    SYS_vhangup,
+    SYS_vmsplice,
+    SYS_wait4,
+    SYS_waitid,
+    SYS_write,
+    SYS_writev,
+];
+
+#[cfg(target_arch = "x86_64")]
+const ARCH_SYSCALLS: &[(&str, i64)] = syscall_table![
+    SYS__sysctl,
+    SYS_access,
+    SYS_afs_syscall,
+    SYS_alarm,
+    SYS_arch_prctl,
+    SYS_chmod,
+    SYS_chown,
+    SYS_creat,
+    SYS_dup2,
+    SYS_epoll_create,
+    SYS_epoll_ctl_old,
+    SYS_epoll_wait,
+    SYS_epoll_wait_old,
+    SYS_eventfd,
+    SYS_fork,
+    SYS_futimesat,
+    SYS_get_thread_area,
+    SYS_getdents,
+    SYS_getpgrp,
+    SYS_getpmsg,
+    SYS_inotify_init,
+    SYS_ioperm,
+    SYS_iopl,
+    SYS_lchown,
+    SYS_link,
+    SYS_lstat,
+    SYS_mkdir,
+    SYS_mknod,
+    SYS_modify_ldt,
+    SYS_open,
+    SYS_pause,
+    SYS_pipe,
+    SYS_poll,
+    SYS_putpmsg,
+    SYS_readlink,
+    SYS_rename,
+    SYS_rmdir,
+    SYS_security,
+    SYS_select,
+    SYS_set_thread_area,
+    SYS_signalfd,
+    SYS_stat,
+    SYS_symlink,
+    SYS_sysfs,
+    SYS_time,
+    SYS_tuxcall,
+    SYS_unlink,
+    SYS_uselib,
+    SYS_ustat,
+    SYS_utime,
+    SYS_utimes,
+    SYS_vfork,
+    SYS_vserver,
+    SYS_getrlimit,
+    SYS_setrlimit,
+    SYS_renameat,
+    SYS_sync_file_range,
+    SYS_kexec_file_load,
+];
+
+/// not all libc versions define these on aarch64
+#[cfg(target_arch = "aarch64")]
+const ARCH_SYSCALLS: &[(&str, i64)] = &[
+    ("SYS_renameat", 38),
+    ("SYS_sync_file_range", 84),
+    ("SYS_getrlimit", 163),
+    ("SYS_setrlimit", 164),
+    ("SYS_kexec_file_load", 294),
+];
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const SYSCALLS: &[(&str, i64)] = &[];
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const ARCH_SYSCALLS: &[(&str, i64)] = &[];
+
+/// the system call groups, the members unknown on the architecture are skipped
+const SYSCALL_GROUPS: &[(&str, &[&str])] = &[
+    (
+        "@default",
+        &[
+            "arch_prctl",
+            "brk",
+            "cacheflush",
+            "clock_getres",
+            "clock_gettime",
+            "clock_nanosleep",
+            "execve",
+            "exit",
+            "exit_group",
+            "futex",
+            "get_robust_list",
+            "get_thread_area",
+            "getegid",
+            "geteuid",
+            "getgid",
+            "getgroups",
+            "getpgid",
+            "getpgrp",
+            "getpid",
+            "getppid",
+            "getrandom",
+            "getresgid",
+            "getresuid",
+            "getrlimit",
+            "getsid",
+            "gettid",
+            "gettimeofday",
+            "getuid",
+            "membarrier",
+            "mmap",
+            "mprotect",
+            "munmap",
+            "nanosleep",
+            "pause",
+            "prlimit64",
+            "restart_syscall",
+            "rseq",
+            "rt_sigreturn",
+            "sched_getaffinity",
+            "sched_yield",
+            "set_robust_list",
+            "set_thread_area",
+            "set_tid_address",
+            "time",
+        ],
+    ),
+    (
+        "@aio",
+        &[
+            "io_cancel",
+            "io_destroy",
+            "io_getevents",
+            "io_pgetevents",
+            "io_setup",
+            "io_submit",
+            "io_uring_enter",
+            "io_uring_register",
+            "io_uring_setup",
+        ],
+    ),
+    (
+        "@basic-io",
+        &[
+            "close",
+            "close_range",
+            "dup",
+            "dup2",
+            "dup3",
+            "lseek",
+            "pread64",
+            "preadv",
+            "preadv2",
+            "pwrite64",
+            "pwritev",
+            "pwritev2",
+            "read",
+            "readv",
+            "write",
+            "writev",
+        ],
+    ),
+    ("@chown", &["chown", "fchown", "fchownat", "lchown"]),
+    (
+        "@clock",
+        &["adjtimex", "clock_adjtime", "clock_settime", "settimeofday"],
+    ),
+    ("@cpu-emulation", &["modify_ldt"]),
+    (
+        "@debug",
+        &["lookup_dcookie", "perf_event_open", "pidfd_getfd", "ptrace"],
+    ),
+    (
+        "@file-system",
+        &[
+            "access",
+            "chdir",
+            "chmod",
+            "close",
+            "creat",
+            "faccessat",
+            "faccessat2",
+            "fallocate",
+            "fchdir",
+            "fchmod",
+            "fchmodat",
+            "fcntl",
+            "fgetxattr",
+            "flistxattr",
+            "fremovexattr",
+            "fsetxattr",
+            "fstat",
+            "fstatfs",
+            "ftruncate",
+            "futimesat",
+            "getcwd",
+            "getdents",
+            "getdents64",
+            "getxattr",
+            "inotify_add_watch",
+            "inotify_init",
+            "inotify_init1",
+            "inotify_rm_watch",
+            "lgetxattr",
+            "link",
+            "linkat",
+            "listxattr",
+            "llistxattr",
+            "lremovexattr",
+            "lsetxattr",
+            "lstat",
+            "mkdir",
+            "mkdirat",
+            "mknod",
+            "mknodat",
+            "newfstatat",
+            "open",
+            "openat",
+            "openat2",
+            "readlink",
+            "readlinkat",
+            "removexattr",
+            "rename",
+            "renameat",
+            "renameat2",
+            "rmdir",
+            "setxattr",
+            "stat",
+            "statfs",
+            "statx",
+            "symlink",
+            "symlinkat",
+            "truncate",
+            "unlink",
+            "unlinkat",
+            "utime",
+            "utimensat",
+            "utimes",
+        ],
+    ),
+    (
+        "@io-event",
+        &[
+            "epoll_create",
+            "epoll_create1",
+            "epoll_ctl",
+            "epoll_ctl_old",
+            "epoll_pwait",
+            "epoll_pwait2",
+            "epoll_wait",
+            "epoll_wait_old",
+            "eventfd",
+            "eventfd2",
+            "poll",
+            "ppoll",
+            "pselect6",
+            "select",
+        ],
+    ),
+    (
+        "@ipc",
+        &[
+            "memfd_create",
+            "mq_getsetattr",
+            "mq_notify",
+            "mq_open",
+            "mq_timedreceive",
+            "mq_timedsend",
+            "mq_unlink",
+            "msgctl",
+            "msgget",
+            "msgrcv",
+            "msgsnd",
+            "pipe",
+            "pipe2",
+            "process_vm_readv",
+            "process_vm_writev",
+            "semctl",
+            "semget",
+            "semop",
+            "semtimedop",
+            "shmat",
+            "shmctl",
+            "shmdt",
+            "shmget",
+        ],
+    ),
+    ("@keyring", &["add_key", "keyctl", "request_key"]),
+    (
+        "@memlock",
+        &["mlock", "mlock2", "mlockall", "munlock", "munlockall"],
+    ),
+    ("@module", &["delete_module", "finit_module", "init_module"]),
+    (
+        "@mount",
+        &[
+            "chroot",
+            "fsconfig",
+            "fsmount",
+            "fsopen",
+            "fspick",
+            "mount",
+            "mount_setattr",
+            "move_mount",
+            "open_tree",
+            "pivot_root",
+            "umount2",
+        ],
+    ),
+    (
+        "@network-io",
+        &[
+            "accept",
+            "accept4",
+            "bind",
+            "connect",
+            "getpeername",
+            "getsockname",
+            "getsockopt",
+            "listen",
+            "recvfrom",
+            "recvmmsg",
+            "recvmsg",
+            "sendmmsg",
+            "sendmsg",
+            "sendto",
+            "setsockopt",
+            "shutdown",
+            "socket",
+            "socketpair",
+        ],
+    ),
+    (
+        "@obsolete",
+        &[
+            "_sysctl",
+            "afs_syscall",
+            "getpmsg",
+            "putpmsg",
+            "security",
+            "sysfs",
+            "tuxcall",
+            "uselib",
+            "ustat",
+            "vserver",
+        ],
+    ),
+    ("@pkey", &["pkey_alloc", "pkey_free", "pkey_mprotect"]),
+    (
+        "@privileged",
+        &[
+            "@chown",
+            "@clock",
+            "@module",
+            "@raw-io",
+            "@reboot",
+            "@swap",
+            "_sysctl",
+            "acct",
+            "bpf",
+            "capset",
+            "chroot",
+            "fanotify_init",
+            "fanotify_mark",
+            "nfsservctl",
+            "open_by_handle_at",
+            "pivot_root",
+            "quotactl",
+            "setdomainname",
+            "setfsuid",
+            "setgroups",
+            "sethostname",
+            "setresuid",
+            "setreuid",
+            "setuid",
+            "vhangup",
+        ],
+    ),
+    (
+        "@process",
+        &[
+            "capget",
+            "clone",
+            "clone3",
+            "execveat",
+            "fork",
+            "getrusage",
+            "kill",
+            "pidfd_open",
+            "pidfd_send_signal",
+            "prctl",
+            "rt_sigqueueinfo",
+            "rt_tgsigqueueinfo",
+            "setns",
+            "tgkill",
+            "times",
+            "tkill",
+            "unshare",
+            "vfork",
+            "wait4",
+            "waitid",
+        ],
+    ),
+    ("@raw-io", &["ioperm", "iopl"]),
+    ("@reboot", &["kexec_file_load", "kexec_load", "reboot"]),
+    (
+        "@resources",
+        &[
+            "ioprio_set",
+            "mbind",
+            "migrate_pages",
+            "move_pages",
+            "sched_setaffinity",
+            "sched_setattr",
+            "sched_setparam",
+            "sched_setscheduler",
+            "set_mempolicy",
+            "setpriority",
+            "setrlimit",
+        ],
+    ),
+    (
+        "@sandbox",
+        &[
+            "landlock_add_rule",
+            "landlock_create_ruleset",
+            "landlock_restrict_self",
+            "seccomp",
+        ],
+    ),
+    (
+        "@setuid",
+        &[
+            "setgid",
+            "setgroups",
+            "setregid",
+            "setresgid",
+            "setresuid",
+            "setreuid",
+            "setuid",
+        ],
+    ),
+    (
+        "@signal",
+        &[
+            "rt_sigaction",
+            "rt_sigpending",
+            "rt_sigprocmask",
+            "rt_sigsuspend",
+            "rt_sigtimedwait",
+            "sigaltstack",
+            "signalfd",
+            "signalfd4",
+        ],
+    ),
+    ("@swap", &["swapoff", "swapon"]),
+    (
+        "@sync",
+        &[
+            "fdatasync",
+            "fsync",
+            "msync",
+            "sync",
+            "sync_file_range",
+            "syncfs",
+        ],
+    ),
+    (
+        "@system-service",
+        &[
+            "@aio",
+            "@basic-io",
+            "@chown",
+            "@default",
+            "@file-system",
+            "@io-event",
+            "@ipc",
+            "@keyring",
+            "@memlock",
+            "@network-io",
+            "@process",
+            "@resources",
+            "@setuid",
+            "@signal",
+            "@sync",
+            "@timer",
+            "capget",
+            "capset",
+            "copy_file_range",
+            "fadvise64",
+            "flock",
+            "get_mempolicy",
+            "getcpu",
+            "getpriority",
+            "ioctl",
+            "ioprio_get",
+            "kcmp",
+            "madvise",
+            "mremap",
+            "name_to_handle_at",
+            "personality",
+            "readahead",
+            "remap_file_pages",
+            "sched_get_priority_max",
+            "sched_get_priority_min",
+            "sched_getattr",
+            "sched_getparam",
+            "sched_getscheduler",
+            "sched_rr_get_interval",
+            "sendfile",
+            "setfsgid",
+            "setfsuid",
+            "setpgid",
+            "setsid",
+            "splice",
+            "sysinfo",
+            "tee",
+            "umask",
+            "uname",
+            "userfaultfd",
+            "vmsplice",
+        ],
+    ),
+    (
+        "@timer",
+        &[
+            "alarm",
+            "getitimer",
+            "setitimer",
+            "timer_create",
+            "timer_delete",
+            "timer_getoverrun",
+            "timer_gettime",
+            "timer_settime",
+            "timerfd_create",
+            "timerfd_gettime",
+            "timerfd_settime",
+            "times",
+        ],
+    ),
+];
+
+/// return the number of the system call on the native architecture
+pub fn syscall_number(name: &str) -> Option<i64> {
+    SYSCALLS
+        .iter()
+        .chain(ARCH_SYSCALLS.iter())
+        .find(|(n, _)| n.strip_prefix("SYS_") == Some(name))
+        .map(|(_, nr)| *nr)
+}
+
+/// the seccomp filter of the spawned process
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyscallFilter {
+    /// true if only the system calls are allowed, false if they are denied
+    pub allow_list: bool,
+    /// the system calls with the groups expanded
+    pub syscalls: BTreeSet<String>,
+    /// the errno returned by the denied system calls, the process is killed if None
+    pub errno: Option<i32>,
+    /// SystemCallArchitectures=native, deny the system calls of other architectures
+    pub native_only: bool,
+}
+
+impl SyscallFilter {
+    /// parse SystemCallFilter, SystemCallErrorNumber and SystemCallArchitectures.
+    /// The filter is an allow-list unless the first entry is prefixed with "~", the later
+    /// entries with the opposite prefix remove the system calls from the list.
+    pub fn parse(filter: &[String], errno: &str, archs: &[String]) -> Result<Self, String> {
+        let mut sf = SyscallFilter {
+            errno: parse_errno(errno)?,
+            ..Default::default()
+        };
+
+        for (i, entry) in filter
+            .iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .enumerate()
+        {
+            let (invert, list) = match entry.strip_prefix('~') {
+                Some(list) => (true, list),
+                None => (false, entry),
+            };
+
+            if i == 0 {
+                sf.allow_list = !invert;
+                // the process can not even call execve without the default group
+                if sf.allow_list {
+                    sf.syscalls.extend(expand("@default")?);
+                }
+            }
+
+            for name in list.split_whitespace() {
+                let syscalls = expand(name)?;
+                if invert != sf.allow_list {
+                    sf.syscalls.extend(syscalls);
+                } else {
+                    sf.syscalls.retain(|s| !syscalls.contains(s));
+                }
+            }
+        }
+
+        for arch in archs.iter().flat_map(|s| s.split_whitespace()) {
+            match NATIVE_ARCH {
+                Some((name, _)) if arch == "native" || arch == name => sf.native_only = true,
+                _ => return Err(format!("unsupported system call architecture: {arch}")),
+            }
+        }
+
+        Ok(sf)
+    }
+
+    /// whether there is no filter to install
+    pub fn is_empty(&self) -> bool {
+        !self.allow_list && self.syscalls.is_empty() && !self.native_only
+    }
+
+    fn deny_action(&self) -> u32 {
+        match self.errno {
+            Some(errno) => SECCOMP_RET_ERRNO | (errno as u32 & SECCOMP_RET_DATA),
+            None => SECCOMP_RET_KILL_PROCESS,
+        }
+    }
+
+    /// build the bpf program, the system calls of other architectures are always denied
+    /// because the filter only knows the native system call numbers.
+    pub fn build(&self) -> Result<Vec<libc::sock_filter>, String> {
+        let arch = match NATIVE_ARCH {
+            Some((_, arch)) => arch,
+            None => return Err("seccomp is not supported on this architecture".to_string()),
+        };
+
+        let deny = self.deny_action();
+        let (matched, unmatched) = if self.allow_list {
+            (SECCOMP_RET_ALLOW, deny)
+        } else {
+            (deny, SECCOMP_RET_ALLOW)
+        };
+
+        let mut prog = vec![
+            bpf_stmt(BPF_LD_W_ABS, SECCOMP_DATA_ARCH),
+            bpf_jump(BPF_JMP_JEQ_K, arch, 1, 0),
+            bpf_stmt(BPF_RET_K, deny),
+            bpf_stmt(BPF_LD_W_ABS, SECCOMP_DATA_NR),
+        ];
+
+        if let Some(bit) = X32_SYSCALL_BIT {
+            prog.push(bpf_jump(BPF_JMP_JGE_K, bit, 0, 1));
+            prog.push(bpf_stmt(BPF_RET_K, deny));
+        }
+
+        let nrs: BTreeSet<u32> = self
+            .syscalls
+            .iter()
+            .filter_map(|s| syscall_number(s))
+            .map(|nr| nr as u32)
+            .collect();
+        for nr in nrs {
+            prog.push(bpf_jump(BPF_JMP_JEQ_K, nr, 0, 1));
+            prog.push(bpf_stmt(BPF_RET_K, matched));
+        }
+        prog.push(bpf_stmt(BPF_RET_K, unmatched));
+
+        if prog.len() > BPF_MAXINSNS {
+            return Err("too many system calls in the filter".to_string());
+        }
+
+        Ok(prog)
+    }
+
+    /// install the filter to the current process, no_new_privs is set if the process does
+    /// not have CAP_SYS_ADMIN.
+    pub fn install(&self) -> nix::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let prog = self.build().map_err(|_| Errno::ENOTSUP)?;
+        let fprog = libc::sock_fprog {
+            len: prog.len() as libc::c_ushort,
+            filter: prog.as_ptr() as *mut libc::sock_filter,
+        };
+
+        let set_filter = || {
+            let ret = unsafe {
+                libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &fprog as *const libc::sock_fprog,
+                )
+            };
+            Errno::result(ret).map(drop)
+        };
+
+        match set_filter() {
+            Err(Errno::EACCES) => {
+                let ret = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+                Errno::result(ret)?;
+                set_filter()
+            }
+            r => r,
+        }
+    }
+}
+
+fn expand(name: &str) -> Result<BTreeSet<String>, String> {
+    let mut syscalls = BTreeSet::new();
+    if !name.starts_with('@') {
+        match syscall_number(name) {
+            Some(_) => {
+                syscalls.insert(name.to_string());
+            }
+            None => log::warn!("Unknown system call {}, ignoring", name),
+        }
+        return Ok(syscalls);
+    }
+
+    let members = SYSCALL_GROUPS
+        .iter()
+        .find(|(group, _)| *group == name)
+        .map(|(_, members)| *members)
+        .ok_or_else(|| format!("unknown system call group: {name}"))?;
+    for member in members {
+        if member.starts_with('@') {
+            syscalls.extend(expand(member)?);
+        } else if syscall_number(member).is_some() {
+            syscalls.insert(member.to_string());
+        }
+    }
+
+    Ok(syscalls)
+}
+
+/// parse SystemCallErrorNumber, an errno name or number, "" or "kill" means killing the process
+fn parse_errno(s: &str) -> Result<Option<i32>, String> {
+    let s = s.trim();
+    if s.is_empty() || s == "kill" {
+        return Ok(None);
+    }
+
+    if let Ok(errno) = s.parse::<i32>() {
+        if (1..4096).contains(&errno) {
+            return Ok(Some(errno));
+        }
+        return Err(format!("invalid SystemCallErrorNumber: {s}"));
+    }
+
+    (1..4096)
+        .find(|e| format!("{:?}", Errno::from_i32(*e)) == s)
+        .map(Some)
+        .ok_or_else(|| format!("invalid SystemCallErrorNumber: {s}"))
+}
+
+fn bpf_stmt(code: u16, k: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+fn bpf_jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_errno, SyscallFilter, BPF_RET_K, SECCOMP_RET_ERRNO};
+
+    fn filter(entries: &[&str]) -> SyscallFilter {
+        let entries: Vec<String> = entries.iter().map(|s| s.to_string()).collect();
+        SyscallFilter::parse(&entries, "", &[]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let sf = filter(&["@system-service", "~@resources"]);
+        assert!(sf.allow_list);
+        assert!(sf.syscalls.contains("execve"));
+        assert!(sf.syscalls.contains("read"));
+        assert!(!sf.syscalls.contains("setpriority"));
+        assert!(!sf.syscalls.contains("mount"));
+
+        let sf = filter(&["~@mount @reboot", "umount2"]);
+        assert!(!sf.allow_list);
+        assert!(sf.syscalls.contains("mount"));
+        assert!(sf.syscalls.contains("reboot"));
+        assert!(!sf.syscalls.contains("umount2"));
+
+        assert!(filter(&[]).is_empty());
+        assert!(SyscallFilter::parse(&["@foo".to_string()], "", &[]).is_err());
+        assert!(
+            SyscallFilter::parse(&[], "", &["native".to_string()])
+                .unwrap()
+                .native_only
+        );
+    }
+
+    #[test]
+    fn test_parse_errno() {
+        assert_eq!(parse_errno(""), Ok(None));
+        assert_eq!(parse_errno("kill"), Ok(None));
+        assert_eq!(parse_errno("EPERM"), Ok(Some(libc::EPERM)));
+        assert_eq!(parse_errno("13"), Ok(Some(13)));
+        assert!(parse_errno("EFOO").is_err());
+        assert!(parse_errno("0").is_err());
+    }
+
+    #[test]
+    fn test_build_arch_check() {
+        let sf = SyscallFilter {
+            errno: Some(libc::EPERM),
+            ..filter(&["~getcwd"])
+        };
+        assert!(!sf.native_only);
+        let deny = SECCOMP_RET_ERRNO | libc::EPERM as u32;
+
+        // the filter can not be bypassed with the system calls of other architectures
+        let prog = sf.build().unwrap();
+        assert_eq!(prog[2].code, BPF_RET_K);
+        assert_eq!(prog[2].k, deny);
+        // nor with the x32 system calls
+        if cfg!(target_arch = "x86_64") {
+            assert_eq!(prog[5].code, BPF_RET_K);
+            assert_eq!(prog[5].k, deny);
+        }
+    }
+
+    #[test]
+    fn test_install_filter() {
+        let sf = SyscallFilter {
+            errno: Some(libc::EACCES),
+            ..filter(&["~getcwd"])
+        };
+
+        match unsafe { libc::fork() } {
+            0 => {
+                let mut buf = [0u8; 256];
+                let ret = sf.install().map(|_| unsafe {
+                    libc::syscall(libc::SYS_getcwd, buf.as_mut_ptr(), buf.len())
+                });
+                let denied =
+                    ret == Ok(-1) && nix::errno::Errno::last() == nix::errno::Errno::EACCES;
+                unsafe { libc::_exit(if denied { 0 } else { 1 }) };
+            }
+            pid => {
+                let mut status = 0;
+                assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+                assert!(libc::WIFEXITED(status));
+                assert_eq!(libc::WEXITSTATUS(status), 0);
+            }
+        }
+    }
+}
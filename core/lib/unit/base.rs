@@ -121,6 +121,17 @@ pub trait SubUnit: ReStation + UnitMngUtil {
         Vec::new()
     }
 
+    /// the name of the fds returned by collect_fds, which is passed in LISTEN_FDNAMES
+    fn fd_name(&self) -> Option<String> {
+        None
+    }
+
+    /// pass the connection fd accepted by the socket to the unit,
+    /// a negative fd releases the one passed before.
+    fn set_socket_fd(&self, _fd: i32) -> Result<()> {
+        Err(Error::UnitActionEOpNotSupp)
+    }
+
     ///Get the the unit state
     ///
     /// Every sub unit  can define self states and map to [`UnitActiveState`]
@@ -130,6 +141,11 @@ pub trait SubUnit: ReStation + UnitMngUtil {
     /// Return the state of subunit, i.e. (mounted for .mount, running for .service, listening for .socket)
     fn get_subunit_state(&self) -> String;
 
+    /// Get the result of the last run of the sub unit, None if it succeeded
+    fn get_subunit_result(&self) -> Option<String> {
+        None
+    }
+
     ///
     fn attach_unit(&self, unit: Rc<dyn UnitBase>);
 
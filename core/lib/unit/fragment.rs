@@ -0,0 +1,99 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+use crate::error::*;
+use basic::unit_name;
+use confique::Partial;
+use std::{fs, io, path::Path};
+
+/// load the unit fragment in "path" as a config layer of the unit "name",
+/// the unit specifiers are expanded in the parsed string values, so the
+/// comments and the keys are kept as they are and no escaping is needed.
+/// A missing fragment is an empty layer.
+pub fn unit_fragment_load<P: Partial>(name: &str, path: &Path) -> Result<P> {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(P::empty()),
+        Err(e) => return Err(Error::Io { source: e }),
+    };
+
+    let mut value = toml::from_str::<toml::Value>(&content).map_err(|e| Error::Parse {
+        source: Box::new(e),
+    })?;
+    specifier_expand(name, &mut value);
+
+    value.try_into().map_err(|e| Error::Parse {
+        source: Box::new(e),
+    })
+}
+
+fn specifier_expand(name: &str, value: &mut toml::Value) {
+    match value {
+        toml::Value::String(s) => *s = unit_name::unit_specifier_expand(name, s),
+        toml::Value::Array(array) => array.iter_mut().for_each(|v| specifier_expand(name, v)),
+        toml::Value::Table(table) => table
+            .iter_mut()
+            .for_each(|(_, v)| specifier_expand(name, v)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+#[allow(non_snake_case)]
+mod tests {
+    use super::*;
+    use confique::Config;
+
+    #[derive(Config, Debug)]
+    struct TestConfig {
+        #[config(nested)]
+        Service: TestSection,
+    }
+
+    #[derive(Config, Debug)]
+    struct TestSection {
+        #[config(default = "")]
+        PIDFile: String,
+        ExecStart: Option<Vec<String>>,
+    }
+
+    #[test]
+    fn test_unit_fragment_load() {
+        let dir = std::env::temp_dir().join(format!("sysmaster-fragment-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("foo@.service");
+        fs::write(
+            &path,
+            "# the pid file of %i \"quoted\"\n\
+             [Service]\n\
+             PIDFile = \"%t/%p-%i.pid\"\n\
+             ExecStart = [\"/bin/echo %i\"]\n",
+        )
+        .unwrap();
+
+        // the instance contains the characters to be escaped in toml
+        let partial = unit_fragment_load(r#"foo@a"b\c.service"#, &path).unwrap();
+        let config = TestConfig::builder().preloaded(partial).load().unwrap();
+        assert_eq!(config.Service.PIDFile, r#"/run/foo-a"b\c.pid"#);
+        assert_eq!(
+            config.Service.ExecStart,
+            Some(vec![r#"/bin/echo a"b\c"#.to_string()])
+        );
+
+        // missing fragment is an empty layer
+        let partial = unit_fragment_load("foo.service", &dir.join("none")).unwrap();
+        let config = TestConfig::builder().preloaded(partial).load().unwrap();
+        assert!(config.Service.PIDFile.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -13,11 +13,15 @@
 //!
 pub use base::{SubUnit, UnitBase};
 pub use deps::{UnitDependencyMask, UnitRelationAtom, UnitRelations, UnitType};
+pub use fragment::unit_fragment_load;
 pub use kill::{KillContext, KillMode, KillOperation};
+pub use ratelimit::RateLimit;
 pub use state::{UnitActiveState, UnitNotifyFlags, UnitStatus};
 pub use umif::{UmIf, UnitManagerObj, UnitMngUtil};
 mod base;
 mod deps;
+mod fragment;
 mod kill;
+mod ratelimit;
 mod state;
 mod umif;
@@ -0,0 +1,85 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+use std::time::Instant;
+
+/// limit the number of the events in an interval
+pub struct RateLimit {
+    interval: u64,
+    burst: u32,
+    begin: Option<Instant>,
+    nums: u32,
+}
+
+impl RateLimit {
+    /// create a rate limit allowing {burst} events in {interval} seconds,
+    /// it is disabled if either of them is 0
+    pub fn new(interval: u64, burst: u32) -> Self {
+        RateLimit {
+            interval,
+            burst,
+            begin: None,
+            nums: 0,
+        }
+    }
+
+    /// count a new event, return false if the limit is hit
+    pub fn ratelimit_below(&mut self) -> bool {
+        if !self.ratelimit_enabled() {
+            return true;
+        }
+
+        let now = Instant::now();
+        if self.begin.is_none() || now.duration_since(self.begin.unwrap()).as_secs() > self.interval
+        {
+            self.begin = Some(now);
+            self.nums = 1;
+            return true;
+        }
+
+        if self.nums < self.burst {
+            self.nums += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn ratelimit_enabled(&self) -> bool {
+        if self.interval > 0 && self.burst > 0 {
+            return true;
+        }
+
+        false
+    }
+
+    /// reset the interval and burst
+    pub fn init_from_config(&mut self, interval: u64, burst: u32) {
+        self.interval = interval;
+        self.burst = burst;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimit;
+    #[test]
+    fn test_ratelimit() {
+        let mut tmp = RateLimit::new(0, 0);
+        assert!(tmp.ratelimit_below());
+
+        let mut tmp2 = RateLimit::new(3, 2);
+        assert!(tmp2.ratelimit_below());
+        assert!(tmp2.ratelimit_below());
+        assert!(!tmp2.ratelimit_below());
+    }
+}
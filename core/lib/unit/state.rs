@@ -121,6 +121,8 @@ pub struct UnitStatus {
     cgroup_path: String,
     pid: String,
     error_code: i32,
+    logs: Vec<String>,
+    result: Option<String>,
 }
 
 impl UnitStatus {
@@ -135,6 +137,8 @@ impl UnitStatus {
         cgroup_path: String,
         pid: String,
         error_code: i32,
+        logs: Vec<String>,
+        result: Option<String>,
     ) -> Self {
         Self {
             name,
@@ -145,6 +149,8 @@ impl UnitStatus {
             cgroup_path,
             pid,
             error_code,
+            logs,
+            result,
         }
     }
 }
@@ -152,7 +158,10 @@ impl UnitStatus {
 impl std::fmt::Display for UnitStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut status_table = ShowTable::new();
-        let full_active_state = self.active_state.to_string() + "(" + &self.sub_state + ")";
+        let mut full_active_state = self.active_state.to_string() + "(" + &self.sub_state + ")";
+        if let Some(result) = &self.result {
+            full_active_state += &format!(" (Result: {result})");
+        }
         status_table.add_line(vec!["Loaded:", &self.load_state]);
         status_table.add_line(vec!["Active:", &full_active_state]);
         status_table.add_line(vec!["CGroup:", &self.cgroup_path]);
@@ -163,7 +172,11 @@ impl std::fmt::Display for UnitStatus {
             None => "● ".to_string() + &self.name + "\n",
             Some(str) => "● ".to_string() + &self.name + " - " + str + "\n",
         };
-        write!(f, "{}", first_line + &status_table.to_string())
+        write!(f, "{}", first_line + &status_table.to_string())?;
+        if !self.logs.is_empty() {
+            write!(f, "\n\n{}", self.logs.join("\n"))?;
+        }
+        Ok(())
     }
 }
 
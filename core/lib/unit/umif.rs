@@ -92,6 +92,11 @@ pub trait UmIf {
         false
     }
 
+    /// check if there is already a start job in process
+    fn has_start_job(&self, _name: &str) -> bool {
+        false
+    }
+
     /// check the unit that will be triggered by {name} is in active or activating state
     fn relation_active_or_pending(&self, _name: &str) -> bool {
         false
@@ -149,11 +154,16 @@ pub trait UmIf {
         false
     }
 
-    /// return the fds that trigger the unit {name};
-    fn collect_socket_fds(&self, _name: &str) -> Vec<i32> {
+    /// return the fds that trigger the unit {name} and their names;
+    fn collect_socket_fds(&self, _name: &str) -> Vec<(i32, String)> {
         Vec::new()
     }
 
+    /// pass the connection fd accepted by the socket to the unit {name}
+    fn unit_set_socket_fd(&self, _name: &str, _fd: i32) -> Result<()> {
+        Err(Error::UnitActionEOpNotSupp)
+    }
+
     /// get all unit in sysmaster
     fn units_get_all(&self, _unit_type: Option<UnitType>) -> Vec<String> {
         Vec::new()
@@ -173,6 +183,11 @@ pub trait UmIf {
     fn restart_unit(&self, _unit_name: &str) -> Result<()> {
         Ok(())
     }
+
+    /// unload the unit which is inactive or failed and has no job
+    fn unload_unit(&self, _unit_name: &str) -> Result<()> {
+        Err(Error::UnitActionEOpNotSupp)
+    }
 }
 
 /// the trait used for attach UnitManager to sub unit
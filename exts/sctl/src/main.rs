@@ -14,13 +14,15 @@
 
 use clap::Parser;
 use cmdproto::proto::{
-    abi::{sys_comm, unit_comm, CommandRequest},
+    abi::{sys_comm, unit_comm, CommandRequest, CommandResponse},
     mngr_comm, unit_file, ProstClientStream,
 };
 use std::io::Write;
 use std::{
     net::{SocketAddr, TcpStream},
     process::{ExitCode, Termination},
+    thread,
+    time::Duration,
 };
 
 /// parse program arguments
@@ -70,6 +72,21 @@ enum SubCmd {
         units: Vec<String>,
     },
 
+    /// [unit] show the log of the unit
+    #[clap(display_order = 6)]
+    Logs {
+        #[clap(required = true)]
+        unit: String,
+
+        /// keep showing the new log lines
+        #[clap(short, long, required = false)]
+        follow: bool,
+
+        /// number of the last lines to show, 0 for all
+        #[clap(short = 'n', long, default_value_t = 0)]
+        lines: u32,
+    },
+
     /// [manager] list all units
     ListUnits {},
 
@@ -141,6 +158,8 @@ fn generate_command_request(args: Args) -> Option<CommandRequest> {
 
         SubCmd::Reload { units } => CommandRequest::new_unitcomm(unit_comm::Action::Reload, units),
 
+        SubCmd::Logs { unit, lines, .. } => CommandRequest::new_logcomm(unit, lines, false, 0),
+
         SubCmd::Mask { unit_file } => {
             CommandRequest::new_unitfile(unit_file::Action::Mask, unit_file)
         }
@@ -205,19 +224,9 @@ impl Termination for Result {
     }
 }
 
-fn main() -> Result {
-    let args = Args::parse();
-
-    let command_request = match generate_command_request(args) {
-        None => {
-            return Result::Failure(
-                "This command is currently not supported".to_string(),
-                nix::Error::ENOTSUP as u32,
-            );
-        }
-        Some(v) => v,
-    };
-
+fn execute_command(
+    command_request: CommandRequest,
+) -> std::result::Result<CommandResponse, Result> {
     let addrs = [
         SocketAddr::from(([127, 0, 0, 1], 9526)),
         SocketAddr::from(([127, 0, 0, 1], 9527)),
@@ -226,19 +235,74 @@ fn main() -> Result {
     let stream = match TcpStream::connect(&addrs[..]) {
         Err(e) => {
             let err_msg = format!("Failed to connect to sysmaster: {}", e);
-            return Result::Failure(err_msg, e.raw_os_error().unwrap() as u32);
+            return Err(Result::Failure(err_msg, e.raw_os_error().unwrap() as u32));
         }
         Ok(v) => v,
     };
 
     let mut client = ProstClientStream::new(stream);
 
-    let data = match client.execute(command_request) {
+    match client.execute(command_request) {
         Err(e) => {
             let err_msg = format!("Failed to execute the given command: {}", e);
             // don't want to map all kinds of errors the message is clear, just return 1.
-            return Result::Failure(err_msg, 1);
+            Err(Result::Failure(err_msg, 1))
+        }
+        Ok(v) => Ok(v),
+    }
+}
+
+/// show the last lines of the unit log, then poll the new lines every second
+fn follow_logs(unit: &str, lines: u32) -> Result {
+    let mut request = CommandRequest::new_logcomm(unit.to_string(), lines, false, 0);
+    let mut first = true;
+    loop {
+        let data = match execute_command(request) {
+            Err(r) => return r,
+            Ok(v) => v,
+        };
+
+        if data.error_code != 0 {
+            return Result::Failure(data.message, data.error_code);
+        }
+
+        if first && !data.message.is_empty() {
+            let _ = writeln!(std::io::stdout(), "{}", data.message);
+        } else {
+            let _ = write!(std::io::stdout(), "{}", data.message);
+        }
+        let _ = std::io::stdout().flush();
+
+        first = false;
+        request = CommandRequest::new_logcomm(unit.to_string(), 0, true, data.cursor);
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn main() -> Result {
+    let args = Args::parse();
+
+    if let SubCmd::Logs {
+        unit,
+        follow: true,
+        lines,
+    } = &args.subcmd
+    {
+        return follow_logs(unit, *lines);
+    }
+
+    let command_request = match generate_command_request(args) {
+        None => {
+            return Result::Failure(
+                "This command is currently not supported".to_string(),
+                nix::Error::ENOTSUP as u32,
+            );
         }
+        Some(v) => v,
+    };
+
+    let data = match execute_command(command_request) {
+        Err(r) => return r,
         Ok(v) => v,
     };
 
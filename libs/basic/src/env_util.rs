@@ -0,0 +1,252 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the utils of the environment variables
+//!
+use crate::error::*;
+use std::fs;
+use std::path::Path;
+
+/// return true if the name is a valid environment variable name
+pub fn env_name_is_valid(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum EnvParseState {
+    PreKey,
+    Key,
+    PreValue,
+    Value,
+    ValueEscape,
+    SingleQuote,
+    DoubleQuote,
+    DoubleQuoteEscape,
+    Comment,
+}
+
+fn push_env(envs: &mut Vec<(String, String)>, key: &str, value: &str) {
+    let key = key.trim();
+    let key = match key.strip_prefix("export") {
+        Some(k) if k.starts_with(char::is_whitespace) => k.trim_start(),
+        _ => key,
+    };
+
+    if !env_name_is_valid(key) {
+        log::warn!("invalid environment variable name {}, ignoring", key);
+        return;
+    }
+
+    envs.push((key.to_string(), value.to_string()));
+}
+
+/// parse the content of the environment file, the format of each assignment is
+/// "[export] KEY=VALUE". Lines beginning with "#" or ";" are comments. The value can be
+/// quoted with single or double quotes and span multiple lines, a backslash at the end
+/// of the line continues the value on the next line.
+pub fn parse_env_content(content: &str) -> Result<Vec<(String, String)>> {
+    let mut envs = Vec::new();
+    let mut state = EnvParseState::PreKey;
+    let mut key = String::new();
+    let mut value = String::new();
+    // the length of the value without the trailing unquoted whitespace
+    let mut value_len = 0;
+    let mut chars = content.chars();
+    let mut pending = None;
+
+    loop {
+        let c = match pending.take().or_else(|| chars.next()) {
+            None => break,
+            Some(c) => c,
+        };
+
+        match state {
+            EnvParseState::PreKey => {
+                if c == '#' || c == ';' {
+                    state = EnvParseState::Comment;
+                } else if !c.is_whitespace() {
+                    key.push(c);
+                    state = EnvParseState::Key;
+                }
+            }
+            EnvParseState::Key => {
+                if c == '=' {
+                    value.clear();
+                    value_len = 0;
+                    state = EnvParseState::PreValue;
+                } else if c == '\n' {
+                    log::warn!("invalid line {} in environment file, ignoring", key);
+                    key.clear();
+                    state = EnvParseState::PreKey;
+                } else {
+                    key.push(c);
+                }
+            }
+            EnvParseState::PreValue => {
+                if c == '\n' {
+                    push_env(&mut envs, &key, "");
+                    key.clear();
+                    state = EnvParseState::PreKey;
+                } else if !c.is_whitespace() {
+                    pending = Some(c);
+                    state = EnvParseState::Value;
+                }
+            }
+            EnvParseState::Value => match c {
+                '\n' => {
+                    push_env(&mut envs, &key, &value[..value_len]);
+                    key.clear();
+                    state = EnvParseState::PreKey;
+                }
+                '\\' => state = EnvParseState::ValueEscape,
+                '\'' => state = EnvParseState::SingleQuote,
+                '"' => state = EnvParseState::DoubleQuote,
+                c => {
+                    value.push(c);
+                    if !c.is_whitespace() {
+                        value_len = value.len();
+                    }
+                }
+            },
+            EnvParseState::ValueEscape => {
+                // backslash + newline continues the value on the next line
+                if c != '\n' {
+                    value.push(c);
+                    value_len = value.len();
+                }
+                state = EnvParseState::Value;
+            }
+            EnvParseState::SingleQuote => {
+                if c == '\'' {
+                    value_len = value.len();
+                    state = EnvParseState::Value;
+                } else {
+                    value.push(c);
+                }
+            }
+            EnvParseState::DoubleQuote => match c {
+                '"' => {
+                    value_len = value.len();
+                    state = EnvParseState::Value;
+                }
+                '\\' => state = EnvParseState::DoubleQuoteEscape,
+                c => value.push(c),
+            },
+            EnvParseState::DoubleQuoteEscape => {
+                match c {
+                    '\n' => {}
+                    '"' | '\\' | '`' | '$' => value.push(c),
+                    c => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                }
+                state = EnvParseState::DoubleQuote;
+            }
+            EnvParseState::Comment => {
+                if c == '\n' {
+                    state = EnvParseState::PreKey;
+                }
+            }
+        }
+    }
+
+    match state {
+        EnvParseState::PreValue | EnvParseState::Value | EnvParseState::ValueEscape => {
+            push_env(&mut envs, &key, &value[..value_len]);
+        }
+        EnvParseState::SingleQuote
+        | EnvParseState::DoubleQuote
+        | EnvParseState::DoubleQuoteEscape => {
+            return Err(Error::Invalid {
+                what: format!("unterminated quoted value of {}", key.trim()),
+            });
+        }
+        EnvParseState::Key => {
+            log::warn!("invalid line {} in environment file, ignoring", key);
+        }
+        EnvParseState::PreKey | EnvParseState::Comment => {}
+    }
+
+    Ok(envs)
+}
+
+/// read and parse the environment file
+pub fn parse_env_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = fs::read_to_string(path).context(IoSnafu)?;
+    parse_env_content(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envs(content: &str) -> Vec<(String, String)> {
+        parse_env_content(content).unwrap()
+    }
+
+    fn env(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_env_name_is_valid() {
+        assert!(env_name_is_valid("FOO"));
+        assert!(env_name_is_valid("_foo_1"));
+        assert!(!env_name_is_valid(""));
+        assert!(!env_name_is_valid("1FOO"));
+        assert!(!env_name_is_valid("FOO BAR"));
+        assert!(!env_name_is_valid("FOO-BAR"));
+    }
+
+    #[test]
+    fn test_parse_env_content() {
+        assert_eq!(
+            envs("A=1\nB = 2 \n# C=3\n; D=4\n\nexport E=5\n"),
+            vec![env("A", "1"), env("B", "2"), env("E", "5")]
+        );
+        assert_eq!(
+            envs("URL=http://host/path?a=1&b=2"),
+            vec![env("URL", "http://host/path?a=1&b=2")]
+        );
+        assert_eq!(
+            envs("A='a  \"b\" $c'\nB=\"a \\\"b\\\" \\$c \\d\"\nC=\"x\"y  "),
+            vec![
+                env("A", "a  \"b\" $c"),
+                env("B", "a \"b\" $c \\d"),
+                env("C", "xy")
+            ]
+        );
+        assert_eq!(
+            envs("A=one \\\ntwo\nB='multi\nline'\nC=\"x\\\ny\"\nD=a\\ b\nE="),
+            vec![
+                env("A", "one two"),
+                env("B", "multi\nline"),
+                env("C", "xy"),
+                env("D", "a b"),
+                env("E", "")
+            ]
+        );
+        assert_eq!(
+            envs("garbage\n1A=1\nexportF=6\nG=7"),
+            vec![env("exportF", "6"), env("G", "7")]
+        );
+        assert!(parse_env_content("A='unterminated").is_err());
+        assert!(parse_env_content("A=\"unterminated").is_err());
+    }
+}
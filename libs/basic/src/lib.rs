@@ -15,6 +15,7 @@ pub mod condition;
 pub mod conf_parser;
 pub mod devnum_util;
 pub mod env_cargo;
+pub mod env_util;
 pub mod error;
 pub mod fd_util;
 pub mod file_util;
@@ -33,5 +34,6 @@ pub mod socket_util;
 pub mod special;
 pub mod string;
 pub mod time_util;
+pub mod unit_name;
 pub mod user_group_util;
 pub use error::*;
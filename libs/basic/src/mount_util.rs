@@ -14,8 +14,11 @@
 use crate::error::*;
 use nix::{
     fcntl::AtFlags,
+    mount::{self, MsFlags},
     sys::stat::{fstatat, SFlag},
+    sys::statvfs::{statvfs, FsFlags},
 };
+use std::path::{Path, PathBuf};
 
 ///
 pub fn mount_point_fd_valid(fd: i32, file_name: &str, flags: AtFlags) -> Result<bool> {
@@ -40,3 +43,128 @@ pub fn mount_point_fd_valid(fd: i32, file_name: &str, flags: AtFlags) -> Result<
 
     Ok(f_stat.st_dev != d_stat.st_dev)
 }
+
+/// unescape the octal escapes like "\040" in /proc/self/mountinfo
+fn mountinfo_unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() {
+            let octal = std::str::from_utf8(&bytes[i + 1..i + 4]).unwrap_or_default();
+            if let Ok(c) = u8::from_str_radix(octal, 8) {
+                out.push(c);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// return the mount points at or below the prefix, in the order of /proc/self/mountinfo
+pub fn mount_points_under(prefix: &Path) -> Result<Vec<PathBuf>> {
+    let content = std::fs::read_to_string("/proc/self/mountinfo").context(IoSnafu)?;
+    let mut points = Vec::new();
+    for line in content.lines() {
+        // the fifth field is the mount point
+        let point = match line.split_whitespace().nth(4) {
+            Some(p) => PathBuf::from(mountinfo_unescape(p)),
+            None => continue,
+        };
+
+        if point.starts_with(prefix) && !points.contains(&point) {
+            points.push(point);
+        }
+    }
+
+    Ok(points)
+}
+
+/// bind mount the source onto the target
+pub fn bind_mount(source: &Path, target: &Path, recursive: bool) -> Result<()> {
+    let mut flags = MsFlags::MS_BIND;
+    if recursive {
+        flags |= MsFlags::MS_REC;
+    }
+
+    mount::mount(Some(source), target, None::<&str>, flags, None::<&str>).context(NixSnafu)
+}
+
+/// the per-mount flags that must be kept when remounting a bind mount
+fn mount_flags_of(path: &Path) -> Result<MsFlags> {
+    let fs_flags = statvfs(path).context(NixSnafu)?.flags();
+    let mut flags = MsFlags::empty();
+    for (f, m) in [
+        (FsFlags::ST_NOSUID, MsFlags::MS_NOSUID),
+        (FsFlags::ST_NODEV, MsFlags::MS_NODEV),
+        (FsFlags::ST_NOEXEC, MsFlags::MS_NOEXEC),
+        (FsFlags::ST_NOATIME, MsFlags::MS_NOATIME),
+        (FsFlags::ST_NODIRATIME, MsFlags::MS_NODIRATIME),
+        (FsFlags::ST_RELATIME, MsFlags::MS_RELATIME),
+    ] {
+        if fs_flags.contains(f) {
+            flags |= m;
+        }
+    }
+
+    Ok(flags)
+}
+
+/// bind mount the path onto itself, then remount it and the mount points below it read-only.
+/// The mount points at or below the excluded paths are left untouched.
+pub fn bind_remount_read_only(path: &Path, exclude: &[PathBuf]) -> Result<()> {
+    bind_mount(path, path, true)?;
+
+    for point in mount_points_under(path)? {
+        if exclude.iter().any(|e| point.starts_with(e)) {
+            continue;
+        }
+
+        let flags = MsFlags::MS_BIND | MsFlags::MS_REMOUNT | MsFlags::MS_RDONLY;
+        let flags = match mount_flags_of(&point) {
+            Ok(f) => flags | f,
+            // the mount point may be hidden by the mounts over it
+            Err(_) => continue,
+        };
+        mount::mount(None::<&str>, &point, None::<&str>, flags, None::<&str>).context(NixSnafu)?;
+    }
+
+    Ok(())
+}
+
+/// mount an empty tmpfs on the path
+pub fn mount_tmpfs(path: &Path, mode: u32, flags: MsFlags) -> Result<()> {
+    mount::mount(
+        Some("tmpfs"),
+        path,
+        Some("tmpfs"),
+        flags | MsFlags::MS_NOSUID | MsFlags::MS_NODEV,
+        Some(format!("mode={mode:o}").as_str()),
+    )
+    .context(NixSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mount_points_under, mountinfo_unescape};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_mountinfo_unescape() {
+        assert_eq!(mountinfo_unescape("/mnt/a\\040b"), "/mnt/a b");
+        assert_eq!(mountinfo_unescape("/mnt/a\\134b"), "/mnt/a\\b");
+        assert_eq!(mountinfo_unescape("/mnt/ab\\"), "/mnt/ab\\");
+        assert_eq!(mountinfo_unescape("/"), "/");
+    }
+
+    #[test]
+    fn test_mount_points_under() {
+        let points = mount_points_under(Path::new("/")).unwrap();
+        assert!(points.contains(&PathBuf::from("/")));
+        assert!(points.contains(&PathBuf::from("/proc")));
+    }
+}
@@ -14,9 +14,10 @@
 use crate::error::*;
 use nix::{
     errno::Errno,
+    libc,
     sys::socket::{self, sockopt, AddressFamily},
 };
-use std::{os::unix::prelude::RawFd, path::Path};
+use std::{ffi::OsString, mem, os::unix::prelude::RawFd, path::Path};
 
 ///
 pub fn ipv6_is_supported() -> bool {
@@ -88,3 +89,147 @@ pub fn set_keepalive_probes(fd: RawFd, v: u32) -> Result<()> {
 pub fn set_broadcast_state(fd: RawFd, v: bool) -> Result<()> {
     socket::setsockopt(fd, sockopt::Broadcast, &v).context(NixSnafu)
 }
+
+/// Set SO_REUSEPORT, allow multiple sockets to bind to the same port
+pub fn set_reuse_port(fd: RawFd, v: bool) -> Result<()> {
+    socket::setsockopt(fd, sockopt::ReusePort, &v).context(NixSnafu)
+}
+
+/// Allow binding to an address which is nonlocal or does not exist yet
+pub fn set_free_bind(fd: RawFd, family: AddressFamily, v: bool) -> Result<()> {
+    match family {
+        AddressFamily::Inet => socket::setsockopt(fd, sockopt::IpFreebind, &v).context(NixSnafu),
+        AddressFamily::Inet6 => setsockopt_int(
+            fd,
+            libc::IPPROTO_IPV6,
+            libc::IPV6_FREEBIND,
+            v as libc::c_int,
+        ),
+        _ => Err(Error::Nix {
+            source: Errno::EAFNOSUPPORT,
+        }),
+    }
+}
+
+/// Allow binding to a foreign address for transparent proxying
+pub fn set_transparent(fd: RawFd, family: AddressFamily, v: bool) -> Result<()> {
+    match family {
+        AddressFamily::Inet => socket::setsockopt(fd, sockopt::IpTransparent, &v).context(NixSnafu),
+        AddressFamily::Inet6 => setsockopt_int(
+            fd,
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TRANSPARENT,
+            v as libc::c_int,
+        ),
+        _ => Err(Error::Nix {
+            source: Errno::EAFNOSUPPORT,
+        }),
+    }
+}
+
+/// Restrict the IPv6 socket to IPv6 communication only
+pub fn set_bind_ipv6_only(fd: RawFd, v: bool) -> Result<()> {
+    socket::setsockopt(fd, sockopt::Ipv6V6Only, &v).context(NixSnafu)
+}
+
+/// Bind the socket to the network interface
+pub fn set_bind_to_device(fd: RawFd, ifname: &str) -> Result<()> {
+    socket::setsockopt(fd, sockopt::BindToDevice, &OsString::from(ifname)).context(NixSnafu)
+}
+
+/// Set IP_TOS for IPv4 or IPV6_TCLASS for IPv6
+pub fn set_ip_tos(fd: RawFd, family: AddressFamily, v: i32) -> Result<()> {
+    match family {
+        AddressFamily::Inet => setsockopt_int(fd, libc::IPPROTO_IP, libc::IP_TOS, v),
+        AddressFamily::Inet6 => setsockopt_int(fd, libc::IPPROTO_IPV6, libc::IPV6_TCLASS, v),
+        _ => Err(Error::Nix {
+            source: Errno::EAFNOSUPPORT,
+        }),
+    }
+}
+
+/// Set IP_TTL for IPv4 or IPV6_UNICAST_HOPS for IPv6
+pub fn set_ip_ttl(fd: RawFd, family: AddressFamily, v: i32) -> Result<()> {
+    match family {
+        AddressFamily::Inet => socket::setsockopt(fd, sockopt::Ipv4Ttl, &v).context(NixSnafu),
+        AddressFamily::Inet6 => socket::setsockopt(fd, sockopt::Ipv6Ttl, &v).context(NixSnafu),
+        _ => Err(Error::Nix {
+            source: Errno::EAFNOSUPPORT,
+        }),
+    }
+}
+
+/// Set the firewall mark of the packets sent through the socket
+pub fn set_mark(fd: RawFd, v: u32) -> Result<()> {
+    socket::setsockopt(fd, sockopt::Mark, &v).context(NixSnafu)
+}
+
+/// Set the protocol-defined priority of the packets sent through the socket
+pub fn set_priority(fd: RawFd, v: i32) -> Result<()> {
+    setsockopt_int(fd, libc::SOL_SOCKET, libc::SO_PRIORITY, v)
+}
+
+/// Only wake up the listener when data arrives, in seconds
+pub fn set_tcp_defer_accept(fd: RawFd, v: u32) -> Result<()> {
+    setsockopt_int(
+        fd,
+        libc::IPPROTO_TCP,
+        libc::TCP_DEFER_ACCEPT,
+        v.min(i32::MAX as u32) as i32,
+    )
+}
+
+/// Disable the Nagle algorithm
+pub fn set_tcp_nodelay(fd: RawFd, v: bool) -> Result<()> {
+    socket::setsockopt(fd, sockopt::TcpNoDelay, &v).context(NixSnafu)
+}
+
+/// Set the TCP congestion algorithm
+pub fn set_tcp_congestion(fd: RawFd, v: &str) -> Result<()> {
+    socket::setsockopt(fd, sockopt::TcpCongestion, &OsString::from(v)).context(NixSnafu)
+}
+
+/// Receive the security context of the peer in the auxiliary message
+pub fn set_pass_sec(fd: RawFd, v: bool) -> Result<()> {
+    setsockopt_int(fd, libc::SOL_SOCKET, libc::SO_PASSSEC, v as libc::c_int)
+}
+
+/// Receive the timestamp of the packets in microseconds
+pub fn set_receive_timestamp(fd: RawFd, v: bool) -> Result<()> {
+    socket::setsockopt(fd, sockopt::ReceiveTimestamp, &v).context(NixSnafu)
+}
+
+/// Receive the timestamp of the packets in nanoseconds
+pub fn set_receive_timestampns(fd: RawFd, v: bool) -> Result<()> {
+    socket::setsockopt(fd, sockopt::ReceiveTimestampns, &v).context(NixSnafu)
+}
+
+/// Get an integer socket option which is not provided by nix
+pub fn getsockopt_int(fd: RawFd, level: libc::c_int, name: libc::c_int) -> Result<i32> {
+    let mut v: libc::c_int = 0;
+    let mut len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            level,
+            name,
+            &mut v as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    Errno::result(ret).context(NixSnafu)?;
+    Ok(v)
+}
+
+fn setsockopt_int(fd: RawFd, level: libc::c_int, name: libc::c_int, v: libc::c_int) -> Result<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            name,
+            &v as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    Errno::result(ret).map(drop).context(NixSnafu)
+}
@@ -11,6 +11,7 @@
 // See the Mulan PSL v2 for more details.
 
 //!
+use nix::libc;
 use std::time::SystemTime;
 
 const USEC_INFINITY: u128 = u128::MAX;
@@ -22,3 +23,26 @@ pub fn timespec_load(systime: SystemTime) -> u128 {
         Err(_) => USEC_INFINITY,
     }
 }
+
+/// format the time as "YYYY-MM-DD HH:MM:SS" in the local timezone
+pub fn format_timestamp(systime: SystemTime) -> String {
+    let secs = match systime.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as libc::time_t,
+        Err(_) => 0,
+    };
+
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return String::new();
+    }
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
@@ -0,0 +1,259 @@
+// Copyright (c) 2022 Huawei Technologies Co.,Ltd. All rights reserved.
+//
+// sysMaster is licensed under Mulan PSL v2.
+// You can use this software according to the terms and conditions of the Mulan
+// PSL v2.
+// You may obtain a copy of Mulan PSL v2 at:
+//         http://license.coscl.org.cn/MulanPSL2
+// THIS SOFTWARE IS PROVIDED ON AN "AS IS" BASIS, WITHOUT WARRANTIES OF ANY
+// KIND, EITHER EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO
+// NON-INFRINGEMENT, MERCHANTABILITY OR FIT FOR A PARTICULAR PURPOSE.
+// See the Mulan PSL v2 for more details.
+
+//! the utils of the unit name, like "foo@bar.service"
+//!
+use nix::unistd::{Uid, User};
+
+/// the max length of the unit name
+pub const UNIT_NAME_MAX: usize = 256;
+
+/// return true if the name is a valid unit name, like "foo.service" or "foo@bar.service".
+/// Only the ascii alphanumerics and ":-_.\@" are allowed, so it never contains "/",
+/// and ".." is refused as well, the name can be used as a file name safely.
+pub fn unit_name_is_valid(name: &str) -> bool {
+    if name.is_empty() || name.len() >= UNIT_NAME_MAX || name.contains("..") {
+        return false;
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || ":-_.\\@".contains(c))
+    {
+        return false;
+    }
+
+    // the prefix and the type suffix are not empty, and there is at most one "@"
+    match name.rfind('.') {
+        Some(pos) if pos + 1 < name.len() => {}
+        _ => return false,
+    }
+    !unit_name_to_prefix(name).is_empty() && name.matches('@').count() <= 1
+}
+
+/// return the unit name without the type suffix, like "foo@bar" for "foo@bar.service"
+pub fn unit_name_without_suffix(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(pos) => &name[..pos],
+        None => name,
+    }
+}
+
+/// return the prefix of the unit name, like "foo" for "foo@bar.service" or "foo.service"
+pub fn unit_name_to_prefix(name: &str) -> &str {
+    let name = unit_name_without_suffix(name);
+    match name.find('@') {
+        Some(pos) => &name[..pos],
+        None => name,
+    }
+}
+
+/// return the instance of the unit name, like "bar" for "foo@bar.service"
+/// return None if the unit name is not a template or an instance.
+pub fn unit_name_to_instance(name: &str) -> Option<&str> {
+    let name = unit_name_without_suffix(name);
+    name.find('@').map(|pos| &name[pos + 1..])
+}
+
+/// return the template name of the instance, like "foo@.service" for "foo@bar.service"
+/// return None if the unit name is not an instance.
+pub fn unit_name_to_template(name: &str) -> Option<String> {
+    match unit_name_to_instance(name) {
+        Some(instance) if !instance.is_empty() => {
+            let pos = name.find('@')?;
+            let suffix = &name[unit_name_without_suffix(name).len()..];
+            Some(format!("{}@{}", &name[..pos], suffix))
+        }
+        _ => None,
+    }
+}
+
+/// return true if the unit name is a template, like "foo@.service"
+pub fn unit_name_is_template(name: &str) -> bool {
+    unit_name_to_instance(name) == Some("")
+}
+
+/// return the instance of the template, like "foo@bar.service" for "foo@.service" and "bar"
+pub fn unit_name_replace_instance(template: &str, instance: &str) -> Option<String> {
+    if !unit_name_is_template(template) {
+        return None;
+    }
+
+    let pos = template.find('@')?;
+    Some(format!(
+        "{}{}{}",
+        &template[..=pos],
+        instance,
+        &template[pos + 1..]
+    ))
+}
+
+/// unescape the escaped string, "-" is turned to "/" and "\\xNN" to the byte NN
+pub fn unit_name_unescape(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' => ret.push(b'/'),
+            b'\\' if i + 3 < bytes.len() && bytes[i + 1] == b'x' => {
+                let hex = std::str::from_utf8(&bytes[i + 2..i + 4]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        ret.push(b);
+                        i += 3;
+                    }
+                    None => ret.push(b'\\'),
+                }
+            }
+            b => ret.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&ret).to_string()
+}
+
+fn manager_user() -> Option<User> {
+    User::from_uid(Uid::current()).ok().flatten()
+}
+
+/// expand the unit specifiers in the string:
+/// %n: the full unit name, %N: the unit name without the type suffix,
+/// %p: the prefix name, %i: the instance name, %I: the unescaped instance name,
+/// %t: the runtime directory,
+/// %h: the home directory of the manager user, %u: the name of the manager user,
+/// %%: a single "%". Unknown specifiers are kept as they are.
+pub fn unit_specifier_expand(name: &str, s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            ret.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('%') => ret.push('%'),
+            Some('n') => ret.push_str(name),
+            Some('N') => ret.push_str(unit_name_without_suffix(name)),
+            Some('p') => ret.push_str(unit_name_to_prefix(name)),
+            Some('i') => ret.push_str(unit_name_to_instance(name).unwrap_or("")),
+            Some('I') => ret.push_str(&unit_name_unescape(
+                unit_name_to_instance(name).unwrap_or(""),
+            )),
+            Some('t') => ret.push_str("/run"),
+            Some('h') => match manager_user() {
+                Some(user) => ret.push_str(&user.dir.to_string_lossy()),
+                None => ret.push_str("/root"),
+            },
+            Some('u') => match manager_user() {
+                Some(user) => ret.push_str(&user.name),
+                None => ret.push_str("root"),
+            },
+            Some(v) => {
+                log::debug!("unknown specifier %{} in unit {}, ignoring", v, name);
+                ret.push('%');
+                ret.push(v);
+            }
+            None => ret.push('%'),
+        }
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_name() {
+        assert_eq!(unit_name_without_suffix("foo@bar.service"), "foo@bar");
+        assert_eq!(unit_name_without_suffix("foo"), "foo");
+        assert_eq!(unit_name_to_prefix("foo@bar.service"), "foo");
+        assert_eq!(unit_name_to_prefix("foo.service"), "foo");
+        assert_eq!(unit_name_to_instance("foo@bar.service"), Some("bar"));
+        assert_eq!(unit_name_to_instance("foo@.service"), Some(""));
+        assert_eq!(unit_name_to_instance("foo.service"), None);
+    }
+
+    #[test]
+    fn test_unit_name_is_valid() {
+        assert!(unit_name_is_valid("foo.service"));
+        assert!(unit_name_is_valid("foo@bar.service"));
+        assert!(unit_name_is_valid("foo@.service"));
+        assert!(unit_name_is_valid(
+            "foo@0-127.0.0.1:80-127.0.0.1:5555.service"
+        ));
+        assert!(unit_name_is_valid("dev-sda\\x2d1.mount"));
+        assert!(!unit_name_is_valid(""));
+        assert!(!unit_name_is_valid("foo"));
+        assert!(!unit_name_is_valid("foo."));
+        assert!(!unit_name_is_valid(".service"));
+        assert!(!unit_name_is_valid("@bar.service"));
+        assert!(!unit_name_is_valid("foo@bar@baz.service"));
+        assert!(!unit_name_is_valid("../foo.service"));
+        assert!(!unit_name_is_valid("foo/bar.service"));
+        assert!(!unit_name_is_valid("foo..service"));
+        assert!(!unit_name_is_valid(&format!(
+            "{}.service",
+            "a".repeat(UNIT_NAME_MAX)
+        )));
+    }
+
+    #[test]
+    fn test_unit_name_template() {
+        assert_eq!(
+            unit_name_to_template("foo@bar.service"),
+            Some("foo@.service".to_string())
+        );
+        assert_eq!(
+            unit_name_to_template("foo@bar.baz.socket"),
+            Some("foo@.socket".to_string())
+        );
+        assert_eq!(unit_name_to_template("foo@.service"), None);
+        assert_eq!(unit_name_to_template("foo.service"), None);
+        assert!(unit_name_is_template("foo@.service"));
+        assert!(!unit_name_is_template("foo@bar.service"));
+        assert_eq!(
+            unit_name_replace_instance("foo@.service", "bar"),
+            Some("foo@bar.service".to_string())
+        );
+        assert_eq!(unit_name_replace_instance("foo.service", "bar"), None);
+    }
+
+    #[test]
+    fn test_unit_name_unescape() {
+        assert_eq!(unit_name_unescape("dev-sda1"), "dev/sda1");
+        assert_eq!(unit_name_unescape("foo\\x2dbar"), "foo-bar");
+        assert_eq!(unit_name_unescape("foo\\x"), "foo\\x");
+    }
+
+    #[test]
+    fn test_unit_specifier_expand() {
+        let name = "foo@bar.service";
+        assert_eq!(
+            unit_specifier_expand(name, "%n %N %p %i"),
+            "foo@bar.service foo@bar foo bar"
+        );
+        assert_eq!(unit_specifier_expand(name, "%t/%p.pid"), "/run/foo.pid");
+        assert_eq!(unit_specifier_expand(name, "100%% %x %"), "100% %x %");
+        assert_eq!(unit_specifier_expand("foo.service", "[%i]"), "[]");
+        assert_eq!(
+            unit_specifier_expand("foo@dev-sda1.service", "%i %I"),
+            "dev-sda1 dev/sda1"
+        );
+    }
+}
@@ -15,6 +15,17 @@
 use crate::error::*;
 use nix::libc::uid_t;
 use nix::unistd::{Gid, Group, Uid, User};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// the first id of the dynamic users allocated for the units
+pub const DYNAMIC_UID_MIN: uid_t = 61184;
+/// the last id of the dynamic users allocated for the units
+pub const DYNAMIC_UID_MAX: uid_t = 65519;
+
+const PASSWD_PATH: &str = "/etc/passwd";
+const GROUP_PATH: &str = "/etc/group";
 
 /// Parse a string as UID
 pub fn parse_uid(uid_str: &String) -> Result<User> {
@@ -143,3 +154,81 @@ pub fn uid_is_system(uid: Uid) -> bool {
     const SYSTEM_UID_MAX: uid_t = 999;
     uid.as_raw() <= SYSTEM_UID_MAX
 }
+
+/// check if the user id is within the dynamic user range
+pub fn uid_is_dynamic(uid: Uid) -> bool {
+    (DYNAMIC_UID_MIN..=DYNAMIC_UID_MAX).contains(&uid.as_raw())
+}
+
+/// look up the uid and gid of the user name in /etc/passwd directly. NSS is not used, as it
+/// may call into the services managed by sysmaster.
+pub fn lookup_passwd_name(name: &str) -> Option<(Uid, Gid)> {
+    db_entries(Path::new(PASSWD_PATH))
+        .into_iter()
+        .find(|(n, _, _)| n == name)
+        .map(|(_, uid, gid)| (Uid::from_raw(uid), Gid::from_raw(gid.unwrap_or(uid))))
+}
+
+/// return the ids used by the users in /etc/passwd and the groups in /etc/group, without NSS
+pub fn used_ids() -> HashSet<u32> {
+    db_entries(Path::new(PASSWD_PATH))
+        .into_iter()
+        .chain(db_entries(Path::new(GROUP_PATH)))
+        .map(|(_, id, _)| id)
+        .collect()
+}
+
+/// return the name, the id and the group id(passwd only) of the entries in the database file
+fn db_entries(path: &Path) -> Vec<(String, u32, Option<u32>)> {
+    let content = match fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+
+    content.lines().filter_map(parse_db_line).collect()
+}
+
+fn parse_db_line(line: &str) -> Option<(String, u32, Option<u32>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let fields: Vec<&str> = line.split(':').collect();
+    if fields.len() < 3 || fields[0].is_empty() {
+        return None;
+    }
+
+    let id = fields[2].parse::<u32>().ok()?;
+    let gid = fields.get(3).and_then(|g| g.parse::<u32>().ok());
+    Some((fields[0].to_string(), id, gid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_db_line, uid_is_dynamic};
+    use nix::unistd::Uid;
+
+    #[test]
+    fn test_parse_db_line() {
+        assert_eq!(
+            parse_db_line("root:x:0:0:root:/root:/bin/bash"),
+            Some(("root".to_string(), 0, Some(0)))
+        );
+        assert_eq!(
+            parse_db_line("wheel:x:10:root,admin"),
+            Some(("wheel".to_string(), 10, None))
+        );
+        assert_eq!(parse_db_line("# comment"), None);
+        assert_eq!(parse_db_line("foo:x"), None);
+        assert_eq!(parse_db_line("foo:x:bar:0"), None);
+    }
+
+    #[test]
+    fn test_uid_is_dynamic() {
+        assert!(uid_is_dynamic(Uid::from_raw(61184)));
+        assert!(uid_is_dynamic(Uid::from_raw(65519)));
+        assert!(!uid_is_dynamic(Uid::from_raw(65534)));
+        assert!(!uid_is_dynamic(Uid::from_raw(0)));
+    }
+}
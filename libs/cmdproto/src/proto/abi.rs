@@ -2,7 +2,7 @@
 #[rustfmt::skip]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CommandRequest {
-    #[prost(oneof="command_request::RequestData", tags="1, 2, 3, 4, 5")]
+    #[prost(oneof="command_request::RequestData", tags="1, 2, 3, 4, 5, 6")]
     pub request_data: ::core::option::Option<command_request::RequestData>,
 }
 /// Nested message and enum types in `CommandRequest`.
@@ -25,6 +25,9 @@ pub mod command_request {
         ///system commands, reboot/shutdown/halt
         #[prost(message, tag="5")]
         Syscomm(super::SysComm),
+        ///unit log commands
+        #[prost(message, tag="6")]
+        Logcomm(super::LogComm),
     }
 }
 /// Command Response from server
@@ -40,6 +43,9 @@ pub struct CommandResponse {
     /// if not 2xx，message include more information
     #[prost(string, tag="3")]
     pub message: ::prost::alloc::string::String,
+    /// the cursor of the end of the returned unit log
+    #[prost(uint64, tag="4")]
+    pub cursor: u64,
 }
 #[rustfmt::skip]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -143,3 +149,17 @@ pub mod sys_comm {
         Hibernate = 5,
     }
 }
+#[rustfmt::skip]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LogComm {
+    #[prost(string, tag="1")]
+    pub unit: ::prost::alloc::string::String,
+    /// the number of the last lines to show, 0 for all
+    #[prost(uint32, tag="2")]
+    pub lines: u32,
+    /// show the log after the cursor instead of the last lines
+    #[prost(bool, tag="3")]
+    pub follow: bool,
+    #[prost(uint64, tag="4")]
+    pub cursor: u64,
+}
@@ -12,8 +12,8 @@
 
 //! Convert the command request into the corresponding execution action
 use super::{
-    mngr_comm, sys_comm, unit_comm, CommandRequest, CommandResponse, MngrComm, RequestData,
-    SysComm, UnitComm, UnitFile,
+    mngr_comm, sys_comm, unit_comm, CommandRequest, CommandResponse, LogComm, MngrComm,
+    RequestData, SysComm, UnitComm, UnitFile,
 };
 
 use crate::error::*;
@@ -48,6 +48,14 @@ pub trait ExecuterAction {
     fn status(&self, unit_name: &str) -> Result<Self::Status, Self::Error>;
     /// list all units
     fn list_units(&self) -> Result<String, Self::Error>;
+    /// show the log of unit_name and the cursor of its end
+    fn logs(
+        &self,
+        unit_name: &str,
+        lines: u32,
+        follow: bool,
+        cursor: u64,
+    ) -> Result<(String, u64), Self::Error>;
     /// suspend host
     fn suspend(&self) -> Result<i32, Self::Error>;
     /// poweroff host
@@ -91,6 +99,7 @@ where
         Some(RequestData::Mcomm(param)) => param.execute(manager, None),
         Some(RequestData::Syscomm(param)) => param.execute(manager, Some(call_back)),
         Some(RequestData::Ufile(param)) => param.execute(manager, Some(call_back)),
+        Some(RequestData::Logcomm(param)) => param.execute(manager, Some(call_back)),
         _ => CommandResponse::default(),
     };
     println!("CommandResponse :{res:?}");
@@ -179,6 +188,7 @@ impl Executer for UnitComm {
             status: StatusCode::OK.as_u16() as _,
             error_code,
             message: reply,
+            ..Default::default()
         }
     }
 }
@@ -213,6 +223,7 @@ impl Executer for MngrComm {
                     status: StatusCode::OK.as_u16() as _,
                     error_code: 0,
                     message: m,
+                    ..Default::default()
                 },
                 Err(e) => {
                     let error_message = format!("Failed to list all units:{e}");
@@ -220,6 +231,7 @@ impl Executer for MngrComm {
                         status: StatusCode::INTERNAL_SERVER_ERROR.as_u16() as _,
                         error_code: e.into() as u32,
                         message: error_message,
+                        ..Default::default()
                     }
                 }
             },
@@ -227,6 +239,37 @@ impl Executer for MngrComm {
     }
 }
 
+impl Executer for LogComm {
+    fn execute(
+        self,
+        manager: Rc<impl ExecuterAction>,
+        call_back: Option<fn(&str) -> String>,
+    ) -> CommandResponse {
+        let unit = match call_back {
+            None => self.unit.to_string(),
+            Some(f) => f(&self.unit),
+        };
+
+        match manager.logs(&unit, self.lines, self.follow, self.cursor) {
+            Ok((message, cursor)) => CommandResponse {
+                status: StatusCode::OK.as_u16() as _,
+                error_code: 0,
+                message,
+                cursor,
+            },
+            Err(e) => {
+                let error_message = format!("Failed to show the log of {unit}: {e}");
+                CommandResponse {
+                    status: StatusCode::INTERNAL_SERVER_ERROR.as_u16() as _,
+                    error_code: e.into() as u32,
+                    message: error_message,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+}
+
 impl Executer for SysComm {
     fn execute(
         self,
@@ -260,6 +303,7 @@ impl Executer for SysComm {
                 status: StatusCode::INTERNAL_SERVER_ERROR.as_u16() as _,
                 error_code: e.into() as u32,
                 message: String::from("error."),
+                ..Default::default()
             },
         }
     }
@@ -326,6 +370,7 @@ impl Executer for UnitFile {
             status: StatusCode::OK.as_u16() as _,
             error_code,
             message: reply,
+            ..Default::default()
         }
     }
 }
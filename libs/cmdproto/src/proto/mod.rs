@@ -63,6 +63,18 @@ impl CommandRequest {
             })),
         }
     }
+
+    /// Create a new command request for unit log
+    pub fn new_logcomm(unit: String, lines: u32, follow: bool, cursor: u64) -> Self {
+        Self {
+            request_data: Some(RequestData::Logcomm(LogComm {
+                unit,
+                lines,
+                follow,
+                cursor,
+            })),
+        }
+    }
 }
 
 impl fmt::Display for sys_comm::Action {
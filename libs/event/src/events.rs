@@ -137,11 +137,16 @@ impl Events {
     }
 
     /// for inotify: add watch point to inotify event
-    pub fn add_watch<P: ?Sized + NixPath>(&self, path: &P, mask: AddWatchFlags) -> WatchDescriptor {
+    pub fn add_watch<P: ?Sized + NixPath>(
+        &self,
+        path: &P,
+        mask: AddWatchFlags,
+    ) -> Result<WatchDescriptor> {
         self.data.borrow_mut().add_watch(path, mask)
     }
 
-    /// for inotify: rm watch point to inotify event
+    /// for inotify: rm watch point to inotify event, the watch point may be
+    /// already removed by the kernel when the watched path is gone
     pub fn rm_watch(&self, wd: WatchDescriptor) {
         self.data.borrow_mut().rm_watch(wd);
     }
@@ -411,16 +416,23 @@ impl EventsData {
         &self,
         path: &P,
         mask: AddWatchFlags,
-    ) -> WatchDescriptor {
-        self.inotify.add_watch(path, mask).unwrap()
+    ) -> Result<WatchDescriptor> {
+        self.inotify
+            .add_watch(path, mask)
+            .map_err(|e| Error::Syscall {
+                syscall: "inotify_add_watch",
+                ret: -1,
+                errno: e as i32,
+            })
     }
 
     pub(self) fn rm_watch(&self, wd: WatchDescriptor) {
-        self.inotify.rm_watch(wd).unwrap();
+        let _ = self.inotify.rm_watch(wd);
     }
 
+    /// the inotify fd is nonblocking, return no events on EAGAIN
     pub(self) fn read_events(&self) -> Vec<InotifyEvent> {
-        self.inotify.read_events().unwrap()
+        self.inotify.read_events().unwrap_or_default()
     }
 
     /// Wait for the event event through poller
@@ -73,7 +73,7 @@ mod test {
         e.set_enabled(s.clone(), EventState::On).unwrap();
 
         let watch = Path::new("/tmp");
-        let wd = e.add_watch(watch, AddWatchFlags::IN_ALL_EVENTS);
+        let wd = e.add_watch(watch, AddWatchFlags::IN_ALL_EVENTS).unwrap();
 
         e.rloop().unwrap();
 